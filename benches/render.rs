@@ -0,0 +1,109 @@
+// Tiled rendering is part of the binary, so its modules are built in here
+#[allow(dead_code)]
+#[path = "../src/distributed.rs"]
+mod distributed;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/tiles.rs"]
+mod tiles;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mandelbrot::{
+    benchmark_config, builtin_gradient, render, render_iterations, ColorGradient, ColorSpace,
+    Progress,
+};
+use std::{env, num::NonZeroUsize};
+use tiles::{render_tiled, Renderers, TileOrder, Tiling};
+
+/// Iterating the pixels of a small part of the benchmark scene, without
+/// coloring them, in hardware floats and at two arbitrary precisions
+fn iteration(c: &mut Criterion) {
+    let resolution = (16, 9);
+    let mut group = c.benchmark_group("iteration");
+    group.throughput(Throughput::Elements((resolution.0 * resolution.1) as u64));
+    for precision in [53, 256, 1024] {
+        let config = benchmark_config(resolution, precision);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(precision),
+            &config,
+            |b, config| b.iter(|| render_iterations(black_box(config))),
+        );
+    }
+    group.finish();
+}
+
+/// A whole image of the benchmark scene, colored, at a size that keeps
+/// each run short
+fn image(c: &mut Criterion) {
+    let resolution = (160, 90);
+    let config = benchmark_config(resolution, f64::MANTISSA_DIGITS);
+    let mut group = c.benchmark_group("image");
+    group.throughput(Throughput::Elements((resolution.0 * resolution.1) as u64));
+    group.sample_size(20);
+    group.bench_function("160x90", |b| b.iter(|| render(black_box(&config))));
+    group.finish();
+}
+
+/// Colors along the built-in gradient mixed in Oklab, from the stops and
+/// from a lookup of 4096 colors computed ahead
+fn gradient(c: &mut Criterion) {
+    let positions: Vec<f64> = (0..10_000).map(|i| i as f64 * 8_f64 / 10_000_f64).collect();
+    let mixed = ColorGradient::new(builtin_gradient(false), ColorSpace::Oklab);
+    let looked_up = mixed.clone().with_lookup(4096);
+    let mut group = c.benchmark_group("gradient");
+    group.throughput(Throughput::Elements(positions.len() as u64));
+    for (name, gradient) in [("mixed", &mixed), ("lookup", &looked_up)] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            gradient,
+            |b, gradient| {
+                b.iter(|| {
+                    for &i in &positions {
+                        black_box(gradient.get(black_box(i)));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// A tiled image of the benchmark scene, with every thread on the rows of
+/// one tile at a time, and with the threads split into pools that each
+/// take the next tile
+fn tiled(c: &mut Criterion) {
+    let resolution = (320, 180);
+    let config = benchmark_config(resolution, f64::MANTISSA_DIGITS);
+    let checkpoint = env::temp_dir().join("mandelbrot-bench.checkpoint");
+    let mut group = c.benchmark_group("tiled");
+    group.throughput(Throughput::Elements((resolution.0 * resolution.1) as u64));
+    group.sample_size(10);
+    for threads in [None, NonZeroUsize::new(2), NonZeroUsize::new(4)] {
+        let name = threads.map_or("flat".to_owned(), |threads| format!("pools of {}", threads));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let tiling = Tiling {
+                        size: 64,
+                        order: TileOrder::Rows,
+                    };
+                    render_tiled(
+                        black_box(&config),
+                        tiling,
+                        Renderers::Threads(threads),
+                        &checkpoint,
+                        false,
+                        &[],
+                        &Progress::default(),
+                    )
+                    .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, iteration, image, gradient, tiled);
+criterion_main!(benches);
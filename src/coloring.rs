@@ -0,0 +1,262 @@
+use crate::{ColoringMode, Escape, GradientMode, InteriorColoring, RenderConfig};
+use std::f64::consts::TAU;
+
+/// Periods the interior gradient is shared between before it repeats
+const PERIOD_COLORS: u32 = 8;
+
+/// How a sample that escaped is placed along the gradient
+pub(crate) trait Coloring: Sync {
+    /// Position along the gradient, from 0 to 1
+    fn position(&self, escape: &Escape) -> f64;
+}
+
+/// The coloring of every sample that escapes in an image rendered with
+/// `config`. `histogram` is used for histogram coloring, whose counts
+/// are only known once every sample has been iterated
+pub(crate) fn coloring<'a>(
+    config: &RenderConfig,
+    histogram: Option<Histogram>,
+) -> Box<dyn Coloring + 'a> {
+    let bailout = config.bailout;
+    let offset = config.palette_cycle;
+    let cycled = |coloring: Box<dyn Coloring + 'a>| -> Box<dyn Coloring + 'a> {
+        if offset == 0_f64 {
+            coloring
+        } else {
+            Box::new(Cycled { coloring, offset })
+        }
+    };
+    if let Some(degree) = config.newton {
+        return cycled(Box::new(NewtonRoot { degree }));
+    }
+    if config.invert {
+        return cycled(Box::new(Interior { bailout }));
+    }
+    if config.orbit_trap.is_some() {
+        return cycled(Box::new(OrbitTrap { bailout }));
+    }
+    if config.stripe_density.is_some() || config.triangle_inequality {
+        return cycled(Box::new(Average {
+            bailout,
+            degree: config.escape_degree(),
+        }));
+    }
+
+    // Counts are cycled as they're kept on the gradient, by their mode
+    let counts = Counts {
+        take: config.take,
+        log_scale: config.log_scale,
+        mode: config.gradient_mode,
+        offset,
+    };
+    match (config.coloring, histogram) {
+        (ColoringMode::Histogram, Some(histogram)) => cycled(Box::new(histogram)),
+        (ColoringMode::Arg, _) => cycled(Box::new(Arg)),
+        (ColoringMode::Exponential, _) => Box::new(Exponential { counts }),
+        _ => Box::new(EscapeCount {
+            counts,
+            interval: match config.repeat_palette {
+                Some(repeats) => config.take as f64 / repeats as f64,
+                None => config.gradient_interval as f64,
+            },
+        }),
+    }
+}
+
+/// The coloring of the samples inside the set, when they aren't left
+/// as the background
+pub(crate) fn interior_coloring<'a>(config: &RenderConfig) -> Option<Box<dyn Coloring + 'a>> {
+    let bailout = config.bailout;
+    config.interior.map(|interior| -> Box<dyn Coloring + 'a> {
+        match interior {
+            InteriorColoring::Modulus => Box::new(Interior { bailout }),
+            InteriorColoring::Period => Box::new(Period),
+        }
+    })
+}
+
+/// Moves the position of another coloring on by `offset`, wrapping
+struct Cycled<'a> {
+    coloring: Box<dyn Coloring + 'a>,
+    offset: f64,
+}
+
+impl<'a> Coloring for Cycled<'a> {
+    fn position(&self, escape: &Escape) -> f64 {
+        (self.coloring.position(escape) + self.offset).rem_euclid(1_f64)
+    }
+}
+
+/// Escape counts, before they are spread over the gradient
+struct Counts {
+    take: usize,
+    log_scale: bool,
+    mode: GradientMode,
+    /// `palette_cycle`
+    offset: f64,
+}
+
+impl Counts {
+    /// The escape count of `escape`, spread over `span` counts of the
+    /// gradient and kept on it by `mode`
+    fn position(&self, escape: &Escape, span: f64) -> f64 {
+        let iterations = if self.log_scale {
+            // ln(i + 1) / ln(take + 1), kept on the same range as the
+            // escape count so it still loops on the interval
+            let steps = self.take as f64;
+            steps * (escape.iterations.max(0_f64) + 1_f64).ln() / (steps + 1_f64).ln()
+        } else {
+            escape.iterations
+        };
+        let pos = iterations / span;
+        match self.mode {
+            GradientMode::Wrap => (pos + self.offset).rem_euclid(1_f64),
+            // The end of the gradient would wrap round to its start, so
+            // clamped counts are only wrapped when they're cycled
+            GradientMode::Clamp if self.offset == 0_f64 => pos.clamp(0_f64, 1_f64),
+            GradientMode::Clamp => (pos.clamp(0_f64, 1_f64) + self.offset).rem_euclid(1_f64),
+            // There and back is a whole cycle
+            GradientMode::Mirror => {
+                let pos = pos + 2_f64 * self.offset;
+                1_f64 - (pos.rem_euclid(2_f64) - 1_f64).abs()
+            }
+        }
+    }
+}
+
+/// The escape count, looping over the gradient every `interval`
+struct EscapeCount {
+    counts: Counts,
+    interval: f64,
+}
+
+impl Coloring for EscapeCount {
+    fn position(&self, escape: &Escape) -> f64 {
+        self.counts.position(escape, self.interval)
+    }
+}
+
+/// The escape count, spread over the gradient from 0 to `take`
+struct Exponential {
+    counts: Counts,
+}
+
+impl Coloring for Exponential {
+    fn position(&self, escape: &Escape) -> f64 {
+        self.counts.position(escape, self.counts.take as f64)
+    }
+}
+
+/// Cumulative distribution of escape counts, used to spread the gradient
+/// evenly over the samples rather than evenly over the counts
+pub(crate) struct Histogram {
+    /// Fraction of escaped samples with a count below each index
+    cdf: Vec<f64>,
+}
+
+impl Histogram {
+    pub(crate) fn new<'a>(escapes: impl Iterator<Item = &'a Escape>, take: usize) -> Self {
+        // Smoothed counts can run a little past `take`
+        let mut counts = vec![0_u64; take + 2];
+        let last = counts.len() - 1;
+        for escape in escapes {
+            counts[(escape.iterations.max(0_f64) as usize).min(last)] += 1;
+        }
+
+        let total = counts.iter().sum::<u64>().max(1) as f64;
+        let mut below = 0;
+        let mut cdf = Vec::with_capacity(counts.len() + 1);
+        cdf.push(0_f64);
+        for count in counts {
+            below += count;
+            cdf.push(below as f64 / total);
+        }
+
+        Histogram { cdf }
+    }
+}
+
+impl Coloring for Histogram {
+    /// Interpolates between counts for smoothed values
+    fn position(&self, escape: &Escape) -> f64 {
+        let iterations = escape.iterations.max(0_f64);
+        let i = (iterations as usize).min(self.cdf.len() - 2);
+        let fraction = (iterations - i as f64).min(1_f64);
+        self.cdf[i] + (self.cdf[i + 1] - self.cdf[i]) * fraction
+    }
+}
+
+/// The angle of z on escaping, once round the gradient from the
+/// negative real axis
+struct Arg;
+
+impl Coloring for Arg {
+    fn position(&self, escape: &Escape) -> f64 {
+        (escape.escape_angle / TAU + 0.5_f64).rem_euclid(1_f64)
+    }
+}
+
+/// How close the orbit came to the trap, from the trap itself at the
+/// start of the gradient to `bailout` away at the end
+struct OrbitTrap {
+    bailout: f64,
+}
+
+impl Coloring for OrbitTrap {
+    fn position(&self, escape: &Escape) -> f64 {
+        let distance = escape.trap_distance.unwrap_or(self.bailout);
+        (distance / self.bailout).min(1_f64)
+    }
+}
+
+/// The stripe or triangle inequality average over the orbit
+struct Average {
+    bailout: f64,
+    /// Power |z| is raised to by each step, which the final step's share
+    /// of the blend goes by
+    degree: f64,
+}
+
+impl Coloring for Average {
+    fn position(&self, escape: &Escape) -> f64 {
+        let (average, previous) = escape.average.unwrap_or((0_f64, 0_f64));
+        // Blend the averages with and without the final step by how far
+        // past the bailout it went, so the bands are continuous
+        let fraction = 1_f64 - (escape.escape_modulus.ln() / self.bailout.ln()).log(self.degree);
+        let fraction = fraction.clamp(0_f64, 1_f64);
+        fraction * average + (1_f64 - fraction) * previous
+    }
+}
+
+/// An even share of the gradient for each root of Newton's method
+struct NewtonRoot {
+    degree: u32,
+}
+
+impl Coloring for NewtonRoot {
+    fn position(&self, escape: &Escape) -> f64 {
+        escape.root.unwrap_or(0) as f64 / self.degree as f64
+    }
+}
+
+/// An even share of the gradient for each period, repeating every
+/// `PERIOD_COLORS`
+struct Period;
+
+impl Coloring for Period {
+    fn position(&self, escape: &Escape) -> f64 {
+        let period = escape.period.unwrap_or(1).max(1);
+        ((period - 1) % PERIOD_COLORS) as f64 / PERIOD_COLORS as f64
+    }
+}
+
+/// How close the orbit of a point inside the set ended to `bailout`
+struct Interior {
+    bailout: f64,
+}
+
+impl Coloring for Interior {
+    fn position(&self, escape: &Escape) -> f64 {
+        (escape.escape_modulus / self.bailout).min(1_f64)
+    }
+}
@@ -0,0 +1,69 @@
+use image::DynamicImage;
+
+/// How far an image's pixels are from those of a reference image, in
+/// channel steps of 0-255
+pub struct Difference {
+    pixels: usize,
+    /// Pixels with any channel different
+    differing: usize,
+    /// Largest difference of any one channel
+    pub max: f64,
+    /// Mean difference over every channel of every pixel
+    mean: f64,
+}
+
+impl Difference {
+    /// The difference of `img` from `reference`, which must be the same
+    /// size. 16-bit and HDR images are compared at their own depth, scaled
+    /// down to steps of 0-255
+    pub fn new(img: &DynamicImage, reference: &DynamicImage) -> Result<Self, String> {
+        if img.width() != reference.width() || img.height() != reference.height() {
+            return Err(format!(
+                "The {}x{} image isn't the size of the {}x{} reference",
+                img.width(),
+                img.height(),
+                reference.width(),
+                reference.height()
+            ));
+        }
+        let (img, reference) = (img.to_rgb32f(), reference.to_rgb32f());
+        let mut differing = 0;
+        let mut max = 0_f64;
+        let mut total = 0_f64;
+        for (pixel, expected) in img.pixels().zip(reference.pixels()) {
+            let mut differs = false;
+            for (channel, expected) in pixel.0.iter().zip(expected.0) {
+                let difference = (channel - expected).abs() as f64 * 255_f64;
+                differs |= difference > 0_f64;
+                max = max.max(difference);
+                total += difference;
+            }
+            if differs {
+                differing += 1;
+            }
+        }
+        let pixels = img.pixels().len();
+        Ok(Difference {
+            pixels,
+            differing,
+            max,
+            mean: total / (pixels * 3).max(1) as f64,
+        })
+    }
+
+    /// A human readable summary, one line at a time
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!(
+                "Differing pixels: {} of {} ({:.2}%)",
+                self.differing,
+                self.pixels,
+                self.differing as f64 / self.pixels.max(1) as f64 * 100_f64
+            ),
+            format!(
+                "Channel difference: max {:.2}, mean {:.4}",
+                self.max, self.mean
+            ),
+        ]
+    }
+}
@@ -0,0 +1,332 @@
+use serde::Deserialize;
+use std::{fmt::Display, fs, io};
+
+/// Render settings loaded from a TOML file with `--config`
+/// Keys match the long command line flags, ie.
+///
+/// ```toml
+/// resolution = "3840x2160"
+/// centered-around = "(-0.744471650, -0.1260742540)"
+/// zoom = 9
+/// take = 2000
+/// output = "seahorse.png"
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    resolution: Option<String>,
+    domain: Option<String>,
+    range: Option<String>,
+    line: Option<String>,
+    plot_orbit: Option<String>,
+    grid: bool,
+    grid_spacing: Option<String>,
+    grid_color: Option<String>,
+    scale_bar: Option<String>,
+    scale_bar_color: Option<String>,
+    kaleidoscope: Option<u32>,
+    sharpen: Option<f32>,
+    sharpen_radius: Option<f32>,
+    centered_around: Option<String>,
+    center_file: Option<String>,
+    zoom: Option<f64>,
+    magnification: Option<String>,
+    extra_precision: Option<u32>,
+    max_precision: Option<u32>,
+    aspect_lock: bool,
+    rotate: Option<f64>,
+    flip_x: bool,
+    flip_y: bool,
+    transpose: bool,
+    take: Option<usize>,
+    output: Option<String>,
+    output_format: Option<String>,
+    jpeg_quality: Option<u8>,
+    png_compression: Option<String>,
+    bit_depth: Option<u8>,
+    color_count: Option<usize>,
+    alpha: bool,
+    quantizer: Option<String>,
+    iterations_output: Option<String>,
+    profile: bool,
+    profile_output: Option<String>,
+    compare: Option<String>,
+    compare_tolerance: Option<f64>,
+    gradient_interval: Option<usize>,
+    repeat_palette: Option<u32>,
+    exponential_gradient: bool,
+    gradient_mode: Option<String>,
+    palette_cycle: Option<f64>,
+    color_space: Option<String>,
+    palette_easing: Option<String>,
+    palette_interpolation_steps: Option<usize>,
+    coloring: Option<String>,
+    log_scale: bool,
+    background: Option<String>,
+    invert: bool,
+    interior_coloring: Option<String>,
+    dither: bool,
+    gamma: Option<f64>,
+    palette: Option<String>,
+    palette_from_image: Option<String>,
+    palette_samples: Option<usize>,
+    palette_reverse: bool,
+    palette_rotate_stops: Option<i32>,
+    julia: Option<String>,
+    power: Option<u32>,
+    bailout: Option<f64>,
+    auto_bailout: bool,
+    fractal: Option<String>,
+    smooth: bool,
+    continue_past_bailout: Option<u32>,
+    distance_estimate: bool,
+    shade: bool,
+    light_angle: Option<f64>,
+    detect_periodicity: bool,
+    supersample: Option<u32>,
+    anti_aliasing: Option<String>,
+    variance_threshold: Option<f64>,
+    delta_e_threshold: Option<f64>,
+    antialias_edges_only: bool,
+    edge_dilation: Option<u32>,
+    oversize: Option<u32>,
+    fill_order: Option<String>,
+    histogram: bool,
+    orbit_trap: Option<String>,
+    trap_position: Option<String>,
+    z0: Option<String>,
+    newton: Option<u32>,
+    method: Option<String>,
+    stripe_density: Option<f64>,
+    tia: bool,
+    perturbation: bool,
+    series_terms: Option<usize>,
+    real_axis_fold: bool,
+    seed_from_reference_file: Option<String>,
+    reference_orbit_export: Option<String>,
+    no_simd: bool,
+    force_bignum: bool,
+    zoom_frames: Option<u32>,
+    zoom_start: Option<f64>,
+    zoom_end: Option<f64>,
+    zoom_easing: Option<String>,
+    animate: bool,
+    frame_delay: Option<u16>,
+    tile_size: Option<u32>,
+    tile_order: Option<String>,
+    threads_per_tile: Option<usize>,
+    distributed: Option<String>,
+    worker: Option<String>,
+    resume: bool,
+    timeout: Option<f64>,
+    tile_output: Option<u32>,
+    crop: Option<String>,
+    render_region_list: Option<String>,
+    max_threads: Option<usize>,
+    preview: Option<u32>,
+    save_state: bool,
+    seed_image: Option<String>,
+    mask: Option<String>,
+    auto_take: bool,
+    quiet: bool,
+    verbose: bool,
+    benchmark: bool,
+    dry_run: bool,
+    palette_preview: bool,
+    palette_ticks: bool,
+    bounds_from: Option<String>,
+    stdin: bool,
+    batch: Option<String>,
+    watch: bool,
+    #[cfg(feature = "interactive")]
+    interactive: bool,
+}
+
+impl ConfigFile {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The file's settings as command line arguments, so they go through
+    /// the same parsing as flags given on the command line
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        fn value(args: &mut Vec<String>, flag: &str, value: &Option<impl Display>) {
+            if let Some(value) = value {
+                args.push(format!("--{}={}", flag, value));
+            }
+        }
+
+        fn flag(args: &mut Vec<String>, flag: &str, set: bool) {
+            if set {
+                args.push(format!("--{}", flag));
+            }
+        }
+
+        value(&mut args, "resolution", &self.resolution);
+        value(&mut args, "domain", &self.domain);
+        value(&mut args, "range", &self.range);
+        value(&mut args, "line", &self.line);
+        value(&mut args, "plot-orbit", &self.plot_orbit);
+        flag(&mut args, "grid", self.grid);
+        value(&mut args, "grid-spacing", &self.grid_spacing);
+        value(&mut args, "grid-color", &self.grid_color);
+        value(&mut args, "scale-bar", &self.scale_bar);
+        value(&mut args, "scale-bar-color", &self.scale_bar_color);
+        value(&mut args, "kaleidoscope", &self.kaleidoscope);
+        value(&mut args, "sharpen", &self.sharpen);
+        value(&mut args, "sharpen-radius", &self.sharpen_radius);
+        value(&mut args, "centered-around", &self.centered_around);
+        value(&mut args, "center-file", &self.center_file);
+        value(&mut args, "zoom", &self.zoom);
+        value(&mut args, "magnification", &self.magnification);
+        value(&mut args, "extra-precision", &self.extra_precision);
+        value(&mut args, "max-precision", &self.max_precision);
+        flag(&mut args, "aspect-lock", self.aspect_lock);
+        value(&mut args, "rotate", &self.rotate);
+        flag(&mut args, "flip-x", self.flip_x);
+        flag(&mut args, "flip-y", self.flip_y);
+        flag(&mut args, "transpose", self.transpose);
+        value(&mut args, "take", &self.take);
+        value(&mut args, "output", &self.output);
+        value(&mut args, "output-format", &self.output_format);
+        value(&mut args, "jpeg-quality", &self.jpeg_quality);
+        value(&mut args, "png-compression", &self.png_compression);
+        value(&mut args, "bit-depth", &self.bit_depth);
+        value(&mut args, "color-count", &self.color_count);
+        flag(&mut args, "alpha", self.alpha);
+        value(&mut args, "quantizer", &self.quantizer);
+        value(&mut args, "iterations-output", &self.iterations_output);
+        flag(&mut args, "profile", self.profile);
+        value(&mut args, "profile-output", &self.profile_output);
+        value(&mut args, "compare", &self.compare);
+        value(&mut args, "compare-tolerance", &self.compare_tolerance);
+        value(&mut args, "gradient-interval", &self.gradient_interval);
+        value(&mut args, "repeat-palette", &self.repeat_palette);
+        flag(&mut args, "exponential-gradient", self.exponential_gradient);
+        value(&mut args, "gradient-mode", &self.gradient_mode);
+        value(&mut args, "palette-cycle", &self.palette_cycle);
+        value(&mut args, "color-space", &self.color_space);
+        value(&mut args, "palette-easing", &self.palette_easing);
+        value(
+            &mut args,
+            "palette-interpolation-steps",
+            &self.palette_interpolation_steps,
+        );
+        value(&mut args, "coloring", &self.coloring);
+        flag(&mut args, "log-scale", self.log_scale);
+        value(&mut args, "background", &self.background);
+        flag(&mut args, "invert", self.invert);
+        value(&mut args, "interior-coloring", &self.interior_coloring);
+        flag(&mut args, "dither", self.dither);
+        value(&mut args, "gamma", &self.gamma);
+        value(&mut args, "palette", &self.palette);
+        value(&mut args, "palette-from-image", &self.palette_from_image);
+        value(&mut args, "palette-samples", &self.palette_samples);
+        flag(&mut args, "palette-reverse", self.palette_reverse);
+        value(
+            &mut args,
+            "palette-rotate-stops",
+            &self.palette_rotate_stops,
+        );
+        value(&mut args, "julia", &self.julia);
+        value(&mut args, "power", &self.power);
+        value(&mut args, "bailout", &self.bailout);
+        flag(&mut args, "auto-bailout", self.auto_bailout);
+        value(&mut args, "fractal", &self.fractal);
+        flag(&mut args, "smooth", self.smooth);
+        value(
+            &mut args,
+            "continue-past-bailout",
+            &self.continue_past_bailout,
+        );
+        flag(&mut args, "distance-estimate", self.distance_estimate);
+        flag(&mut args, "shade", self.shade);
+        value(&mut args, "light-angle", &self.light_angle);
+        flag(&mut args, "detect-periodicity", self.detect_periodicity);
+        value(&mut args, "supersample", &self.supersample);
+        value(&mut args, "anti-aliasing", &self.anti_aliasing);
+        value(&mut args, "variance-threshold", &self.variance_threshold);
+        value(&mut args, "delta-e-threshold", &self.delta_e_threshold);
+        flag(&mut args, "antialias-edges-only", self.antialias_edges_only);
+        value(&mut args, "edge-dilation", &self.edge_dilation);
+        value(&mut args, "oversize", &self.oversize);
+        value(&mut args, "fill-order", &self.fill_order);
+        flag(&mut args, "histogram", self.histogram);
+        value(&mut args, "orbit-trap", &self.orbit_trap);
+        value(&mut args, "trap-position", &self.trap_position);
+        value(&mut args, "z0", &self.z0);
+        value(&mut args, "newton", &self.newton);
+        value(&mut args, "method", &self.method);
+        value(&mut args, "stripe-density", &self.stripe_density);
+        flag(&mut args, "tia", self.tia);
+        flag(&mut args, "perturbation", self.perturbation);
+        value(&mut args, "series-terms", &self.series_terms);
+        flag(&mut args, "real-axis-fold", self.real_axis_fold);
+        value(
+            &mut args,
+            "seed-from-reference-file",
+            &self.seed_from_reference_file,
+        );
+        value(
+            &mut args,
+            "reference-orbit-export",
+            &self.reference_orbit_export,
+        );
+        flag(&mut args, "no-simd", self.no_simd);
+        flag(&mut args, "force-bignum", self.force_bignum);
+        value(&mut args, "zoom-frames", &self.zoom_frames);
+        value(&mut args, "zoom-start", &self.zoom_start);
+        value(&mut args, "zoom-end", &self.zoom_end);
+        value(&mut args, "zoom-easing", &self.zoom_easing);
+        flag(&mut args, "animate", self.animate);
+        value(&mut args, "frame-delay", &self.frame_delay);
+        value(&mut args, "tile-size", &self.tile_size);
+        value(&mut args, "tile-order", &self.tile_order);
+        value(&mut args, "threads-per-tile", &self.threads_per_tile);
+        value(&mut args, "distributed", &self.distributed);
+        value(&mut args, "worker", &self.worker);
+        flag(&mut args, "resume", self.resume);
+        value(&mut args, "timeout", &self.timeout);
+        value(&mut args, "tile-output", &self.tile_output);
+        value(&mut args, "crop", &self.crop);
+        value(&mut args, "render-region-list", &self.render_region_list);
+        value(&mut args, "max-threads", &self.max_threads);
+        value(&mut args, "preview", &self.preview);
+        flag(&mut args, "save-state", self.save_state);
+        value(&mut args, "seed-image", &self.seed_image);
+        value(&mut args, "mask", &self.mask);
+        flag(&mut args, "auto-take", self.auto_take);
+        flag(&mut args, "quiet", self.quiet);
+        flag(&mut args, "verbose", self.verbose);
+        flag(&mut args, "benchmark", self.benchmark);
+        flag(&mut args, "dry-run", self.dry_run);
+        flag(&mut args, "palette-preview", self.palette_preview);
+        flag(&mut args, "palette-ticks", self.palette_ticks);
+        value(&mut args, "bounds-from", &self.bounds_from);
+        flag(&mut args, "stdin", self.stdin);
+        value(&mut args, "batch", &self.batch);
+        flag(&mut args, "watch", self.watch);
+        #[cfg(feature = "interactive")]
+        flag(&mut args, "interactive", self.interactive);
+
+        args
+    }
+}
+
+/// The value of `--<option>` if it was given, found ahead of the full
+/// parse so the settings it names can be merged in underneath the
+/// command line
+pub fn early_value<'a>(args: &'a [String], option: &str) -> Option<&'a str> {
+    let flag = format!("--{}", option);
+    let prefix = format!("--{}=", option);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if *arg == flag {
+            args.get(i + 1).map(String::as_str)
+        } else {
+            arg.strip_prefix(&prefix)
+        }
+    })
+}
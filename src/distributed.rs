@@ -0,0 +1,185 @@
+use image::{ImageBuffer, Rgb};
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+/// Longest frame read, so that a stray connection can't have either side
+/// allocate without bound. Well past the pixels of any tile worth sending
+const MAX_FRAME: u32 = 1 << 30;
+
+/// First byte of a reply to a job or tile that was rendered
+const OK: u8 = 0;
+
+/// First byte of a reply with why a job couldn't be rendered
+const FAILED: u8 = 1;
+
+/// Bytes of a request for a tile: its x, y, width and height
+const TILE_REQUEST: usize = 16;
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Send `bytes` after their length, as a big-endian u32
+fn write_frame(mut stream: impl Write, bytes: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|&len| len <= MAX_FRAME)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Frame is too long"))?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(bytes)?;
+    stream.flush()
+}
+
+/// The bytes of the next frame `write_frame` sent
+fn read_frame(mut stream: impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME {
+        return Err(invalid(format!("Frame of {} bytes is too long", len)));
+    }
+    let mut bytes = vec![0; len as usize];
+    stream.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_reply(stream: impl Write, reply: Result<Vec<u8>, String>) -> io::Result<()> {
+    let (status, mut body) = match reply {
+        Ok(body) => (OK, body),
+        Err(message) => (FAILED, message.into_bytes()),
+    };
+    body.insert(0, status);
+    write_frame(stream, &body)
+}
+
+fn read_reply(stream: impl Read) -> io::Result<Vec<u8>> {
+    let mut reply = read_frame(stream)?;
+    match reply.first() {
+        Some(&OK) => {
+            reply.remove(0);
+            Ok(reply)
+        }
+        // Only jobs fail, when the worker can't make sense of their options
+        Some(&FAILED) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            String::from_utf8_lossy(&reply[1..]).into_owned(),
+        )),
+        _ => Err(invalid("Reply has no status")),
+    }
+}
+
+/// A connection to a worker, which renders tiles of the image given by
+/// the options it was opened with
+pub struct Worker {
+    address: String,
+    stream: TcpStream,
+}
+
+impl Worker {
+    /// Connect to the worker listening at `address`, and have it get the
+    /// image `options` describe ready to render
+    pub fn connect(address: &str, options: &[String]) -> io::Result<Self> {
+        let connect = || {
+            let stream = TcpStream::connect(address)?;
+            stream.set_nodelay(true)?;
+            write_frame(&stream, options.join("\0").as_bytes())?;
+            read_reply(&stream)?;
+            Ok(stream)
+        };
+        match connect() {
+            Ok(stream) => Ok(Worker {
+                address: address.to_owned(),
+                stream,
+            }),
+            Err(e) => Err(named(address, e)),
+        }
+    }
+
+    /// The tile of the image `width` by `height` pixels from (x, y), as
+    /// the worker rendered it
+    pub fn render(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> io::Result<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let request: Vec<u8> = [x, y, width, height]
+            .iter()
+            .flat_map(|n| n.to_be_bytes())
+            .collect();
+        write_frame(&self.stream, &request)
+            .and_then(|()| read_reply(&self.stream))
+            .and_then(|pixels| {
+                ImageBuffer::from_raw(width, height, pixels)
+                    .ok_or_else(|| invalid(format!("Tile at ({}, {}) is the wrong size", x, y)))
+            })
+            .map_err(|e| named(&self.address, e))
+    }
+}
+
+/// `error` of the worker at `address`, saying which it was
+fn named(address: &str, error: io::Error) -> io::Error {
+    io::Error::new(error.kind(), format!("Worker {}: {}", address, error))
+}
+
+/// Render tiles for each coordinator that connects to `listener`, one
+/// coordinator at a time. `job` gets the image of the options a
+/// coordinator sends ready, giving what renders the tile of it with the
+/// x, y, width and height asked for, or why it can't be rendered
+pub fn serve<R>(listener: TcpListener, job: impl Fn(Vec<String>) -> Result<R, String>) -> !
+where
+    R: Fn(u32, u32, u32, u32) -> ImageBuffer<Rgb<u8>, Vec<u8>>,
+{
+    loop {
+        let served = listener
+            .accept()
+            .and_then(|(stream, _)| serve_job(&stream, &job));
+        if let Err(e) = served {
+            eprintln!("Warning: a job ended early: {}", e);
+        }
+    }
+}
+
+/// Render the tiles the coordinator on `stream` asks for, until it hangs up
+fn serve_job<R>(
+    stream: &TcpStream,
+    job: impl Fn(Vec<String>) -> Result<R, String>,
+) -> io::Result<()>
+where
+    R: Fn(u32, u32, u32, u32) -> ImageBuffer<Rgb<u8>, Vec<u8>>,
+{
+    stream.set_nodelay(true)?;
+    let options =
+        String::from_utf8(read_frame(stream)?).map_err(|_| invalid("Options aren't UTF-8"))?;
+    let options = match options.as_str() {
+        "" => Vec::new(),
+        options => options.split('\0').map(str::to_owned).collect(),
+    };
+    let render = match job(options) {
+        Ok(render) => render,
+        Err(message) => return write_reply(stream, Err(message)),
+    };
+    write_reply(stream, Ok(Vec::new()))?;
+
+    loop {
+        let request = match read_frame(stream) {
+            Ok(request) => request,
+            // The coordinator hangs up once it has every tile it wants
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if request.len() != TILE_REQUEST {
+            return Err(invalid("Tile request is the wrong size"));
+        }
+        let field = |i: usize| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&request[i * 4..i * 4 + 4]);
+            u32::from_be_bytes(bytes)
+        };
+        let tile = render(field(0), field(1), field(2), field(3));
+        write_reply(stream, Ok(tile.into_raw()))?;
+    }
+}
@@ -0,0 +1,679 @@
+use crate::{ColorSpace, Easing};
+use image::{ImageBuffer, ImageError, Rgb};
+use palette::{FromColor, Gradient, Hsl, Lab, LinSrgb, Oklab, Srgb};
+use std::{error::Error, fmt, fs, io, path::Path, sync::Arc};
+
+/// Colors at positions along a gradient, in order of position
+pub type Stops = Vec<(f64, LinSrgb<f64>)>;
+
+/// Gradient through stops given in linear sRGB, mixing between them in
+/// the color space it was built in, eased from each stop to the next
+#[derive(Clone)]
+pub struct ColorGradient {
+    mixed: Mixed,
+    /// Where each stop is, in order
+    positions: Vec<f64>,
+    easing: Easing,
+    /// Colors computed ahead at even steps across the gradient, looked up
+    /// in place of mixing each color from the stops
+    lookup: Option<Arc<Lookup>>,
+}
+
+/// Colors at even steps across a gradient, from its first stop to its last
+struct Lookup {
+    start: f64,
+    width: f64,
+    colors: Vec<LinSrgb<f64>>,
+}
+
+/// The gradient in the color space it mixes in
+#[derive(Clone)]
+enum Mixed {
+    LinSrgb(Gradient<LinSrgb<f64>>),
+    Oklab(Gradient<Oklab<f64>>),
+    Lab(Gradient<Lab<palette::white_point::D65, f64>>),
+    Hsl(Gradient<Hsl<palette::encoding::Srgb, f64>>),
+}
+
+impl ColorGradient {
+    /// Mixing evenly between the stops
+    pub fn new(stops: Stops, space: ColorSpace) -> Self {
+        fn convert<C: FromColor<LinSrgb<f64>>>(stops: Stops) -> Vec<(f64, C)> {
+            stops
+                .into_iter()
+                .map(|(position, color)| (position, C::from_color(color)))
+                .collect()
+        }
+        let positions = stops.iter().map(|&(position, _)| position).collect();
+        let mixed = match space {
+            ColorSpace::Linsrgb => Mixed::LinSrgb(Gradient::with_domain(stops)),
+            ColorSpace::Oklab => Mixed::Oklab(Gradient::with_domain(convert(stops))),
+            ColorSpace::Lab => Mixed::Lab(Gradient::with_domain(convert(stops))),
+            // HSL is a different view of sRGB encoded colors
+            ColorSpace::Hsl => Mixed::Hsl(Gradient::with_domain(
+                stops
+                    .into_iter()
+                    .map(|(position, color)| (position, Hsl::from_color(Srgb::from_linear(color))))
+                    .collect(),
+            )),
+        };
+        ColorGradient {
+            mixed,
+            positions,
+            easing: Easing::Linear,
+            lookup: None,
+        }
+    }
+
+    /// The same gradient, eased between its stops by `easing`
+    pub fn with_easing(self, easing: Easing) -> Self {
+        ColorGradient {
+            easing,
+            lookup: None,
+            ..self
+        }
+    }
+
+    /// The same gradient, with `steps` colors across it computed ahead
+    /// and mixed linearly between. Faster to get colors from, especially
+    /// mixing in Oklab, Lab or HSL, and with a few thousand steps no
+    /// different to see
+    pub fn with_lookup(self, steps: usize) -> Self {
+        let (start, end) = self.domain();
+        let last = steps.max(2) - 1;
+        let colors = (0..=last)
+            .map(|step| self.get(start + (end - start) * step as f64 / last as f64))
+            .collect();
+        ColorGradient {
+            lookup: Some(Arc::new(Lookup {
+                start,
+                width: end - start,
+                colors,
+            })),
+            ..self
+        }
+    }
+
+    /// The color at position `i`, back in linear sRGB
+    pub fn get(&self, i: f64) -> LinSrgb<f64> {
+        if let Some(lookup) = &self.lookup {
+            return lookup.get(i);
+        }
+        let i = self.eased(i);
+        match &self.mixed {
+            Mixed::LinSrgb(gradient) => gradient.get(i),
+            Mixed::Oklab(gradient) => LinSrgb::from_color(gradient.get(i)),
+            Mixed::Lab(gradient) => LinSrgb::from_color(gradient.get(i)),
+            Mixed::Hsl(gradient) => Srgb::from_color(gradient.get(i)).into_linear(),
+        }
+    }
+
+    /// Positions of the first and last stops
+    pub fn domain(&self) -> (f64, f64) {
+        match &self.mixed {
+            Mixed::LinSrgb(gradient) => gradient.domain(),
+            Mixed::Oklab(gradient) => gradient.domain(),
+            Mixed::Lab(gradient) => gradient.domain(),
+            Mixed::Hsl(gradient) => gradient.domain(),
+        }
+    }
+
+    /// Position `i` moved by the easing between the stops either side of
+    /// it, so the colors mix at its pace from one to the next. Positions
+    /// on a stop or beyond the ends don't move
+    fn eased(&self, i: f64) -> f64 {
+        if self.easing == Easing::Linear {
+            return i;
+        }
+        let next = self.positions.partition_point(|&position| position <= i);
+        if next == 0 || next == self.positions.len() {
+            return i;
+        }
+        let (from, to) = (self.positions[next - 1], self.positions[next]);
+        from + (to - from) * self.easing.ease((i - from) / (to - from))
+    }
+}
+
+impl Lookup {
+    /// The color at position `i`, mixed from the steps either side of it.
+    /// Positions beyond the ends are the color at the end
+    fn get(&self, i: f64) -> LinSrgb<f64> {
+        let last = self.colors.len() - 1;
+        let step = ((i - self.start) / self.width * last as f64).clamp(0_f64, last as f64);
+        // Only NaN, at the one stop of a gradient with no width
+        if !step.is_finite() {
+            return self.colors[0];
+        }
+        let below = (step as usize).min(last - 1);
+        let t = step - below as f64;
+        self.colors[below] * (1_f64 - t) + self.colors[below + 1] * t
+    }
+}
+
+/// One of the built-in gradients, spanning 0..128 when exponential and 0..8 otherwise
+pub fn builtin_gradient(exponential: bool) -> Stops {
+    if exponential {
+        vec![
+            (0_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
+            (0.5_f64, LinSrgb::new(0.5_f64, 0_f64, 0_f64)),
+            (1_f64, LinSrgb::new(1_f64, 0_f64, 0_f64)),
+            (2_f64, LinSrgb::new(1_f64, 0.5_f64, 0_f64)),
+            (4_f64, LinSrgb::new(0.5_f64, 1_f64, 0.5_f64)),
+            (8_f64, LinSrgb::new(0_f64, 1_f64, 1_f64)),
+            (16_f64, LinSrgb::new(0_f64, 0.5_f64, 1_f64)),
+            (32_f64, LinSrgb::new(0_f64, 0_f64, 1_f64)),
+            (64_f64, LinSrgb::new(0.25_f64, 0_f64, 1_f64)),
+            (128_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
+        ]
+    } else {
+        vec![
+            (0_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
+            (0.5_f64, LinSrgb::new(0.5_f64, 0_f64, 0_f64)),
+            (1.5_f64, LinSrgb::new(1_f64, 0_f64, 0_f64)),
+            (2.5_f64, LinSrgb::new(1_f64, 0.5_f64, 0_f64)),
+            (3.5_f64, LinSrgb::new(0.5_f64, 1_f64, 0.5_f64)),
+            (4.5_f64, LinSrgb::new(0_f64, 1_f64, 1_f64)),
+            (5.5_f64, LinSrgb::new(0_f64, 0.5_f64, 1_f64)),
+            (6.5_f64, LinSrgb::new(0_f64, 0_f64, 1_f64)),
+            (7.5_f64, LinSrgb::new(0.25_f64, 0_f64, 1_f64)),
+            (8_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
+        ]
+    }
+}
+
+/// The built-in gradient for the interior of the set, darker than the
+/// others so it stands apart from them, spanning 0..1
+pub fn interior_gradient() -> Stops {
+    vec![
+        (0_f64, LinSrgb::new(0.01_f64, 0.01_f64, 0.05_f64)),
+        (0.25_f64, LinSrgb::new(0.1_f64, 0.02_f64, 0.25_f64)),
+        (0.5_f64, LinSrgb::new(0.02_f64, 0.2_f64, 0.3_f64)),
+        (0.75_f64, LinSrgb::new(0.3_f64, 0.25_f64, 0.05_f64)),
+        (1_f64, LinSrgb::new(0.5_f64, 0.1_f64, 0.1_f64)),
+    ]
+}
+
+/// `stops` run the other way, each as far from the last position as it
+/// was from the first, so the gradient spans the same positions
+pub fn reverse_stops(stops: Stops) -> Stops {
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(&(first, _)), Some(&(last, _))) => (first, last),
+        _ => return stops,
+    };
+    stops
+        .into_iter()
+        .rev()
+        .map(|(position, color)| (first + last - position, color))
+        .collect()
+}
+
+/// `stops` with each color moved `n` stops on, wrapping around to the
+/// first, at the same positions. A gradient that starts and ends on the
+/// same color is already a loop, so that color is only moved once and
+/// the ends still match
+pub fn rotate_stops(mut stops: Stops, n: i32) -> Stops {
+    let looped = stops.len() > 1 && stops.first().map(|s| s.1) == stops.last().map(|s| s.1);
+    let colors = if looped { stops.len() - 1 } else { stops.len() };
+    if colors == 0 {
+        return stops;
+    }
+    let mut rotated: Vec<LinSrgb<f64>> = stops[..colors].iter().map(|&(_, color)| color).collect();
+    rotated.rotate_right(n.rem_euclid(colors as i32) as usize);
+    if looped {
+        rotated.push(rotated[0]);
+    }
+    for ((_, color), rotated) in stops.iter_mut().zip(rotated) {
+        *color = rotated;
+    }
+    stops
+}
+
+/// Error loading gradient stops from a palette file
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(io::Error),
+    Image(ImageError),
+    /// Line number and what was wrong with it
+    Parse(usize, String),
+    Empty,
+    /// A single stop, which spans no positions to spread the colors over
+    OneStop,
+    /// Position of a stop, and the later one before it
+    Unordered(f64, f64),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::Io(e) => write!(f, "{}", e),
+            PaletteError::Image(e) => write!(f, "{}", e),
+            PaletteError::Parse(line, message) => write!(f, "line {}: {}", line, message),
+            PaletteError::Empty => write!(f, "palette has no color stops"),
+            PaletteError::OneStop => write!(f, "palette needs at least two color stops"),
+            PaletteError::Unordered(position, next) => write!(
+                f,
+                "stop at {} comes after the one at {}, positions must not decrease",
+                next, position
+            ),
+        }
+    }
+}
+
+impl Error for PaletteError {}
+
+impl From<io::Error> for PaletteError {
+    fn from(e: io::Error) -> Self {
+        PaletteError::Io(e)
+    }
+}
+
+impl From<ImageError> for PaletteError {
+    fn from(e: ImageError) -> Self {
+        PaletteError::Image(e)
+    }
+}
+
+/// Load the stops of a gradient from a palette file
+///
+/// GIMP gradients (`.ggr`) use the colors at either end of each segment.
+/// `.json` files are an array of stops as written by `stops_json`. Any
+/// other file is read as one `position,r,g,b` stop per line, in order
+/// of position, with `#` starting a comment. Channels are 0.0-1.0, or
+/// 0-255 if any channel in the file is above 1. Colors in either are
+/// sRGB encoded, as picked in an image editor. Stops can share a
+/// position, for a hard edge between colors, but not go back
+pub fn load_palette(path: impl AsRef<Path>) -> Result<Stops, PaletteError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)?;
+
+    let extension = path.extension().and_then(|e| e.to_str());
+    let is =
+        |kind: &str| matches!(extension, Some(extension) if extension.eq_ignore_ascii_case(kind));
+    let stops = if is("ggr") {
+        parse_ggr(&contents)?
+    } else if is("json") {
+        parse_json(&contents)?
+    } else {
+        parse_stops(&contents)?
+    };
+    checked(stops)
+}
+
+/// `stops` if there are enough of them to make a gradient, in order
+fn checked(stops: Stops) -> Result<Stops, PaletteError> {
+    match stops.len() {
+        0 => return Err(PaletteError::Empty),
+        1 => return Err(PaletteError::OneStop),
+        _ => {}
+    }
+    for pair in stops.windows(2) {
+        let (position, next) = (pair[0].0, pair[1].0);
+        if position.is_nan() || next.is_nan() || next < position {
+            return Err(PaletteError::Unordered(position, next));
+        }
+    }
+    Ok(stops)
+}
+
+/// Take the stops of a gradient from the colors along the middle row of
+/// an image, ie. a screenshot of a colormap
+///
+/// `samples` evenly spaced columns are used from the left edge to the
+/// right, or every column of an image narrower than that
+pub fn palette_from_image(path: impl AsRef<Path>, samples: usize) -> Result<Stops, PaletteError> {
+    let img = image::open(path)?.to_rgb8();
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return Err(PaletteError::Empty);
+    }
+
+    let samples = samples.min(width as usize).max(1);
+    let last = (samples - 1).max(1) as f64;
+    Ok((0..samples)
+        .map(|i| {
+            let position = i as f64 / last;
+            let x = (position * (width - 1) as f64).round() as u32;
+            let [r, g, b] = img.get_pixel(x, height / 2).0;
+            let color = Srgb::new(r, g, b).into_format::<f64>().into_linear();
+            (position, color)
+        })
+        .collect())
+}
+
+/// An image of the gradient through `stops`, mixed in `space` and eased
+/// by `easing`, running across it from the first stop on the left to the
+/// last on the right. With `ticks`, a strip along the bottom marks where
+/// each stop is
+pub fn render_swatch(
+    stops: &Stops,
+    space: ColorSpace,
+    easing: Easing,
+    (width, height): (u32, u32),
+    ticks: bool,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let gradient = ColorGradient::new(stops.clone(), space).with_easing(easing);
+    let (start, end) = gradient.domain();
+    let span = end - start;
+    let column = |x: u32| start + span * (x as f64 + 0.5_f64) / width as f64;
+    let colors: Vec<Rgb<u8>> = (0..width)
+        .map(|x| {
+            let color = Srgb::from_linear(gradient.get(column(x)));
+            let byte = |channel: f64| (channel * 255_f64).round().clamp(0_f64, 255_f64) as u8;
+            Rgb([byte(color.red), byte(color.green), byte(color.blue)])
+        })
+        .collect();
+
+    // The column each stop falls in
+    let marks: Vec<u32> = stops
+        .iter()
+        .map(|(position, _)| {
+            let x = if span > 0_f64 {
+                (position - start) / span * width as f64
+            } else {
+                0_f64
+            };
+            (x as u32).min(width.saturating_sub(1))
+        })
+        .collect();
+    let strip = if ticks { (height / 8).max(1) } else { 0 };
+    ImageBuffer::from_fn(width, height, |x, y| {
+        if y + strip < height {
+            colors[x as usize]
+        } else if marks.contains(&x) {
+            Rgb([255, 255, 255])
+        } else {
+            Rgb([0, 0, 0])
+        }
+    })
+}
+
+/// `stops` as a JSON array of `{ "position", "r", "g", "b" }` objects,
+/// with sRGB encoded channels from 0 to 1, which `load_palette` reads back
+pub fn stops_json(stops: &Stops) -> String {
+    let stops: Vec<String> = stops
+        .iter()
+        .map(|&(position, color)| {
+            // Short enough to edit, and far finer than a byte
+            let color = Srgb::from_linear(color).into_components();
+            let [r, g, b] = [color.0, color.1, color.2].map(|c| (c * 1e9_f64).round() / 1e9_f64);
+            format!(
+                "  {{ \"position\": {}, \"r\": {}, \"g\": {}, \"b\": {} }}",
+                position, r, g, b
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", stops.join(",\n"))
+}
+
+/// A piece of JSON, with the line it's on
+#[derive(Debug)]
+enum Token {
+    Punct(char),
+    Key(String),
+    Number(f64),
+}
+
+fn json_tokens(contents: &str) -> Result<Vec<(usize, Token)>, PaletteError> {
+    let mut tokens = Vec::new();
+    for (n, line) in contents.lines().enumerate() {
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                '[' | ']' | '{' | '}' | ',' | ':' => Token::Punct(c),
+                '"' => {
+                    let key: String = chars
+                        .by_ref()
+                        .map(|(_, c)| c)
+                        .take_while(|&c| c != '"')
+                        .collect();
+                    Token::Key(key)
+                }
+                _ => {
+                    let mut end = start + c.len_utf8();
+                    while let Some(&(i, c)) = chars.peek() {
+                        if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    let number = &line[start..end];
+                    Token::Number(number.parse().map_err(|_| {
+                        PaletteError::Parse(n + 1, format!("expected a number, found {}", number))
+                    })?)
+                }
+            };
+            tokens.push((n + 1, token));
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_json(contents: &str) -> Result<Vec<(f64, LinSrgb<f64>)>, PaletteError> {
+    let tokens = json_tokens(contents)?;
+    let mut tokens = tokens.iter().peekable();
+    let last_line = contents.lines().count().max(1);
+    match tokens.next() {
+        Some((_, Token::Punct('['))) => {}
+        Some((line, _)) => {
+            return Err(PaletteError::Parse(
+                *line,
+                "expected a JSON array of stops".to_owned(),
+            ))
+        }
+        None => return Err(PaletteError::Empty),
+    }
+
+    let mut stops = Vec::new();
+    loop {
+        let line = match tokens.next() {
+            Some((_, Token::Punct(']'))) => break,
+            Some((line, Token::Punct('{'))) => *line,
+            Some((line, token)) => {
+                return Err(PaletteError::Parse(
+                    *line,
+                    format!("expected a stop, found {:?}", token),
+                ))
+            }
+            None => {
+                return Err(PaletteError::Parse(
+                    last_line,
+                    "expected ']' before the end".to_owned(),
+                ))
+            }
+        };
+
+        // "position", "r", "g" and "b", in any order
+        let mut values = [None; 4];
+        loop {
+            let (key, value) = match (tokens.next(), tokens.next(), tokens.next()) {
+                (
+                    Some((_, Token::Key(key))),
+                    Some((_, Token::Punct(':'))),
+                    Some((_, Token::Number(value))),
+                ) => (key, *value),
+                _ => {
+                    return Err(PaletteError::Parse(
+                        line,
+                        "expected \"key\": number in the stop".to_owned(),
+                    ))
+                }
+            };
+            match ["position", "r", "g", "b"]
+                .iter()
+                .position(|name| name == key)
+            {
+                Some(i) => values[i] = Some(value),
+                None => {
+                    return Err(PaletteError::Parse(
+                        line,
+                        format!("unknown key \"{}\"", key),
+                    ))
+                }
+            }
+            match tokens.next() {
+                Some((_, Token::Punct(','))) => {}
+                Some((_, Token::Punct('}'))) => break,
+                _ => return Err(PaletteError::Parse(line, "expected ',' or '}'".to_owned())),
+            }
+        }
+        match values {
+            [Some(position), Some(r), Some(g), Some(b)] => {
+                stops.push((position, Srgb::new(r, g, b).into_linear()))
+            }
+            _ => {
+                return Err(PaletteError::Parse(
+                    line,
+                    "a stop needs a \"position\", \"r\", \"g\" and \"b\"".to_owned(),
+                ))
+            }
+        }
+        if let Some((_, Token::Punct(','))) = tokens.peek() {
+            tokens.next();
+        }
+    }
+    Ok(stops)
+}
+
+fn parse_values(line: &str, separator: char) -> Result<Vec<f64>, String> {
+    line.split(separator)
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.trim().parse::<f64>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+fn parse_stops(contents: &str) -> Result<Vec<(f64, LinSrgb<f64>)>, PaletteError> {
+    let mut stops = Vec::new();
+    for (n, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match parse_values(line, ',').map_err(|e| PaletteError::Parse(n + 1, e))?[..] {
+            [position, r, g, b] => stops.push((position, [r, g, b])),
+            _ => {
+                return Err(PaletteError::Parse(
+                    n + 1,
+                    "expected a stop in the format position,r,g,b".to_owned(),
+                ))
+            }
+        }
+    }
+
+    let scale = if stops.iter().any(|(_, c)| c.iter().any(|&v| v > 1_f64)) {
+        255_f64
+    } else {
+        1_f64
+    };
+
+    Ok(stops
+        .into_iter()
+        .map(|(position, [r, g, b])| {
+            (
+                position,
+                Srgb::new(r / scale, g / scale, b / scale).into_linear(),
+            )
+        })
+        .collect())
+}
+
+fn parse_ggr(contents: &str) -> Result<Vec<(f64, LinSrgb<f64>)>, PaletteError> {
+    let mut lines = contents.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Gradient" => {}
+        _ => {
+            return Err(PaletteError::Parse(
+                1,
+                "expected a \"GIMP Gradient\" header".to_owned(),
+            ))
+        }
+    }
+
+    let mut stops: Vec<(f64, LinSrgb<f64>)> = Vec::new();
+    for (n, line) in lines {
+        let line = line.trim();
+        // The name and segment count aren't needed
+        if line.starts_with("Name:") || !line.contains(' ') {
+            continue;
+        }
+
+        // left middle right r0 g0 b0 a0 r1 g1 b1 a1 type color ...
+        let values = parse_values(line, ' ').map_err(|e| PaletteError::Parse(n + 1, e))?;
+        if values.len() < 11 {
+            return Err(PaletteError::Parse(n + 1, "incomplete segment".to_owned()));
+        }
+
+        let left = (
+            values[0],
+            Srgb::new(values[3], values[4], values[5]).into_linear(),
+        );
+        let right = (
+            values[2],
+            Srgb::new(values[7], values[8], values[9]).into_linear(),
+        );
+        // Segments usually share their end points
+        if stops.last() != Some(&left) {
+            stops.push(left);
+        }
+        stops.push(right);
+    }
+
+    Ok(stops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(contents: &str) -> Result<Stops, PaletteError> {
+        parse_stops(contents).and_then(checked)
+    }
+
+    #[test]
+    fn too_few_stops_are_refused() {
+        assert!(matches!(
+            stops("# just a comment\n\n"),
+            Err(PaletteError::Empty)
+        ));
+        assert!(matches!(stops("0,1,1,1"), Err(PaletteError::OneStop)));
+    }
+
+    #[test]
+    fn stops_going_back_are_refused() {
+        assert!(matches!(
+            stops("0.5,0,0,0\n0.2,1,1,1"),
+            Err(PaletteError::Unordered(position, next)) if position == 0.5 && next == 0.2
+        ));
+        // Though they can share a position
+        assert!(stops("0,0,0,0\n0.5,1,1,1\n0.5,0,0,0\n1,1,1,1").is_ok());
+    }
+
+    #[test]
+    fn channels_are_0_to_255_if_any_is_above_1() {
+        let white = LinSrgb::new(1_f64, 1_f64, 1_f64);
+        assert_eq!(stops("0,0,0,0\n1,255,255,255").unwrap()[1].1, white);
+        assert_eq!(stops("0,0,0,0\n1,1,1,1").unwrap()[1].1, white);
+        // 1 is dark once any channel is in 0-255
+        let dark = Srgb::new(1_f64 / 255_f64, 0_f64, 0_f64).into_linear();
+        assert_eq!(stops("0,1,0,0\n1,2,0,0").unwrap()[0].1, dark);
+    }
+
+    #[test]
+    fn shipped_palettes_load() {
+        let black = LinSrgb::new(0_f64, 0_f64, 0_f64);
+        let fire = load_palette("palettes/fire.txt").unwrap();
+        let positions: Vec<f64> = fire.iter().map(|&(position, _)| position).collect();
+        assert_eq!(positions, [0_f64, 0.25, 0.5, 0.75, 0.9, 1_f64]);
+        assert_eq!(fire[0].1, black);
+        assert_eq!(fire[4].1, LinSrgb::new(1_f64, 1_f64, 1_f64));
+        assert_eq!(fire[5].1, black);
+
+        // Looping back to the color it starts with
+        let ocean = load_palette("palettes/ocean.json").unwrap();
+        assert_eq!(ocean.len(), 6);
+        assert_eq!((ocean[0].0, ocean[5].0), (0_f64, 1_f64));
+        assert_eq!(ocean[0].1, ocean[5].1);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{centered_bounds, image_bounds, render_config, render_outputs, Args, Bounds};
+use mandelbrot::{render_with_rows, Progress, RenderConfig};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use rug::{Complex, Float};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Widest the window is opened, keeping the aspect ratio of the image
+const WINDOW_WIDTH: u32 = 640;
+
+/// Shortest drag, in window pixels, that selects a region rather than
+/// clicking to center the view on a point
+const MIN_SELECTION: f32 = 4_f32;
+
+/// Zoom the view starts at when the image isn't framed, which shows the
+/// whole set
+const START_ZOOM: f64 = -2_f64;
+
+/// Part of the plane shown in the window
+#[derive(Clone)]
+struct View {
+    center: Complex,
+    zoom: f64,
+}
+
+/// Explore the set in a window, starting from the image framed by `args`.
+/// Dragging selects a region to zoom in to, clicking centers the view on
+/// a point, Backspace goes back to the view before and Enter renders the
+/// view at full resolution to `output`. Escape closes the window without
+/// rendering
+pub fn explore(args: &Args, output: &str) -> minifb::Result<()> {
+    let scale = (args.resolution.0 as f64 / WINDOW_WIDTH as f64).max(1_f64);
+    let size = (
+        ((args.resolution.0 as f64 / scale) as u32).max(1),
+        ((args.resolution.1 as f64 / scale) as u32).max(1),
+    );
+
+    let mut view = if args.domain.is_some() || args.centered_around.is_some() {
+        let bounds = image_bounds(args, size);
+        View {
+            center: position(&bounds, (size.0 / 2) as f32, (size.1 / 2) as f32),
+            zoom: bounds.zoom,
+        }
+    } else {
+        View {
+            center: Complex::with_val(53, (-0.75_f64, 0_f64)),
+            zoom: START_ZOOM,
+        }
+    };
+    let mut history = Vec::new();
+
+    let mut window = Window::new(
+        "Mandelbrot",
+        size.0 as usize,
+        size.1 as usize,
+        WindowOptions::default(),
+    )?;
+    window.limit_update_rate(Some(Duration::from_millis(16)));
+
+    window.set_title(&title(&view));
+    let mut image = preview(&mut window, args, &view, size)?;
+    let mut drag = None;
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            drop(window);
+            render_view(args, &view, output);
+            return Ok(());
+        }
+
+        let mut next = None;
+        if window.is_key_pressed(Key::Backspace, KeyRepeat::No) {
+            next = history.pop();
+        }
+        let mouse = window.get_mouse_pos(MouseMode::Clamp);
+        match (drag, window.get_mouse_down(MouseButton::Left), mouse) {
+            (None, true, Some(start)) => drag = Some(start),
+            (Some(start), false, Some(end)) => {
+                drag = None;
+                history.push(view.clone());
+                next = Some(select(args, &view, size, start, end));
+            }
+            _ => {}
+        }
+        if let Some(next) = next {
+            view = next;
+            window.set_title(&title(&view));
+            image = preview(&mut window, args, &view, size)?;
+        }
+
+        match (drag, mouse) {
+            (Some(start), Some(end)) => {
+                let mut frame = image.clone();
+                outline(&mut frame, size, start, end);
+                window.update_with_buffer(&frame, size.0 as usize, size.1 as usize)?;
+            }
+            _ => window.update_with_buffer(&image, size.0 as usize, size.1 as usize)?,
+        }
+    }
+    Ok(())
+}
+
+fn title(view: &View) -> String {
+    format!(
+        "Mandelbrot ({}, {}) zoom {:.2}",
+        view.center.real().to_f64(),
+        view.center.imag().to_f64(),
+        view.zoom
+    )
+}
+
+/// The point at (`x`, `y`) in an image with `bounds`, in pixels from the
+/// top left
+fn position(bounds: &Bounds, x: f32, y: f32) -> Complex {
+    let prec = bounds.prec;
+    let x_val = Float::with_val(prec, &bounds.x_step * x as f64) + &bounds.x_begin;
+    let y_val = Float::with_val(prec, &bounds.y_step * y as f64) + &bounds.y_begin;
+    Complex::with_val(prec, (x_val, y_val))
+}
+
+/// The view of the region dragged out from `start` to `end`, or centered
+/// on `end` if it was only clicked
+fn select(args: &Args, view: &View, size: (u32, u32), start: (f32, f32), end: (f32, f32)) -> View {
+    let bounds = centered_bounds(args, &view.center, view.zoom, size);
+    let (width, height) = ((end.0 - start.0).abs(), (end.1 - start.1).abs());
+    if width.max(height) < MIN_SELECTION {
+        return View {
+            center: position(&bounds, end.0, end.1),
+            zoom: view.zoom,
+        };
+    }
+
+    // The whole of the region has to fit in the view along both axes
+    let fraction = (width / size.0 as f32).max(height / size.1 as f32);
+    View {
+        center: position(
+            &bounds,
+            (start.0 + end.0) / 2_f32,
+            (start.1 + end.1) / 2_f32,
+        ),
+        zoom: view.zoom - (fraction as f64).log2(),
+    }
+}
+
+/// `view` rendered at the size of the window, as 0RGB pixels, showing
+/// each row in the window as it is finished
+fn preview(
+    window: &mut Window,
+    args: &Args,
+    view: &View,
+    size: (u32, u32),
+) -> minifb::Result<Vec<u32>> {
+    let bounds = centered_bounds(args, &view.center, view.zoom, size);
+    let config = RenderConfig {
+        resolution: size,
+        supersample: 1,
+        ..render_config(args, bounds)
+    };
+    let (width, height) = (size.0 as usize, size.1 as usize);
+
+    // Rows not rendered yet are left black
+    let image = Mutex::new(vec![0; width * height]);
+    let done = AtomicBool::new(false);
+    let on_row = |y: u32, row: &[u8]| {
+        let mut image = image.lock().expect("No row panics while holding the image");
+        let start = y as usize * width;
+        for (pixel, rgb) in image[start..start + width]
+            .iter_mut()
+            .zip(row.chunks_exact(3))
+        {
+            *pixel = u32::from_be_bytes([0, rgb[0], rgb[1], rgb[2]]);
+        }
+    };
+    thread::scope(|s| {
+        s.spawn(|| {
+            render_with_rows(&config, &Progress::default(), &on_row);
+            done.store(true, Ordering::Relaxed);
+        });
+        while !done.load(Ordering::Relaxed) && window.is_open() {
+            let frame = image
+                .lock()
+                .expect("No row panics while holding the image")
+                .clone();
+            window.update_with_buffer(&frame, width, height)?;
+        }
+        Ok(())
+    })?;
+    Ok(image
+        .into_inner()
+        .expect("No row panics while holding the image"))
+}
+
+/// Invert the pixels along the edges of the rectangle from `start` to `end`
+fn outline(frame: &mut [u32], size: (u32, u32), start: (f32, f32), end: (f32, f32)) {
+    let clamp = |value: f32, max: u32| (value.max(0_f32) as u32).min(max - 1) as usize;
+    let (left, right) = (
+        clamp(start.0.min(end.0), size.0),
+        clamp(start.0.max(end.0), size.0),
+    );
+    let (top, bottom) = (
+        clamp(start.1.min(end.1), size.1),
+        clamp(start.1.max(end.1), size.1),
+    );
+    let width = size.0 as usize;
+
+    for x in left..=right {
+        frame[top * width + x] ^= 0xFFFFFF;
+        if bottom != top {
+            frame[bottom * width + x] ^= 0xFFFFFF;
+        }
+    }
+    for y in top + 1..bottom {
+        frame[y * width + left] ^= 0xFFFFFF;
+        if right != left {
+            frame[y * width + right] ^= 0xFFFFFF;
+        }
+    }
+}
+
+/// Render `view` at the full resolution of `args` to `output`, framed by
+/// the domain and range it spans so the zoom can be fractional
+fn render_view(args: &Args, view: &View, output: &str) {
+    let resolution = args.resolution;
+    let bounds = centered_bounds(args, &view.center, view.zoom, resolution);
+    let prec = bounds.prec;
+    let x_end = Float::with_val(prec, &bounds.x_step * resolution.0) + &bounds.x_begin;
+    let y_end = Float::with_val(prec, &bounds.y_step * resolution.1) + &bounds.y_begin;
+
+    let mut args = args.clone();
+    args.domain = Some((bounds.x_begin, x_end));
+    args.range = Some((bounds.y_begin, y_end));
+    args.centered_around = None;
+    args.zoom = None;
+    args.magnification = None;
+    render_outputs(&args, output);
+}
@@ -0,0 +1,79 @@
+use mandelbrot::PixelResult;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Write the escape count of each of `results`, row by row for an image
+/// of `resolution`, to `path`. .npy files get an array of 16-bit counts,
+/// or 32-bit when `take` doesn't fit, and anything else a 16-bit
+/// grayscale PNG
+pub fn write_iterations(
+    path: &str,
+    results: &[PixelResult],
+    resolution: (u32, u32),
+    take: usize,
+) -> io::Result<()> {
+    let wide = take > u16::MAX as usize;
+    let is_npy = matches!(
+        Path::new(path).extension().and_then(|e| e.to_str()),
+        Some(e) if e.eq_ignore_ascii_case("npy")
+    );
+    if wide && !is_npy {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("a take of {} doesn't fit in a 16-bit PNG, use .npy", take),
+        ));
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    if is_npy {
+        write_npy(&mut file, results, resolution, wide)?;
+    } else {
+        let mut encoder = png::Encoder::new(&mut file, resolution.0, resolution.1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        let mut writer = encoder.write_header()?;
+        let data: Vec<u8> = results
+            .iter()
+            .flat_map(|result| (result.iterations as u16).to_be_bytes())
+            .collect();
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+    }
+    file.flush()
+}
+
+/// NumPy's format for a single array: a magic string and version, then
+/// a header describing the array padded so the data starts on a multiple
+/// of 64 bytes
+fn write_npy(
+    writer: &mut impl Write,
+    results: &[PixelResult],
+    (width, height): (u32, u32),
+    wide: bool,
+) -> io::Result<()> {
+    let descr = if wide { "<u4" } else { "<u2" };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        descr, height, width
+    );
+    // Magic string, version and header length come first
+    let prefix = 10;
+    let padding = 63 - (prefix + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for result in results {
+        if wide {
+            writer.write_all(&result.iterations.to_le_bytes())?;
+        } else {
+            writer.write_all(&(result.iterations as u16).to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use image::{ImageBuffer, Pixel};
+use std::f64::consts::PI;
+
+/// Most segments a kaleidoscope is split into, past which each wedge is
+/// too thin to show anything
+pub const MAX_SEGMENTS: u32 = 180;
+
+/// Fold `img` into a kaleidoscope of `segments` wedges about its center.
+/// Each wedge is a copy of the one starting to the right of the center,
+/// and mirrored in half, so that neighbouring wedges meet seamlessly
+pub fn kaleidoscope<P: Pixel>(img: &mut ImageBuffer<P, Vec<P::Subpixel>>, segments: u32) {
+    let source = img.clone();
+    let (width, height) = img.dimensions();
+    let (center_x, center_y) = (width as f64 / 2_f64, height as f64 / 2_f64);
+    let half = PI / segments.max(1) as f64;
+
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f64 + 0.5 - center_x, y as f64 + 0.5 - center_y);
+        let radius = dx.hypot(dy);
+        // The angle within its wedge, from 0 to `half` and back
+        let angle = dy.atan2(dx).rem_euclid(2_f64 * half);
+        let angle = if angle > half {
+            2_f64 * half - angle
+        } else {
+            angle
+        };
+
+        // Past the edge of the image, the wedge takes the nearest pixel
+        let (sin, cos) = angle.sin_cos();
+        let from_x = (center_x + radius * cos)
+            .floor()
+            .clamp(0_f64, (width - 1) as f64);
+        let from_y = (center_y + radius * sin)
+            .floor()
+            .clamp(0_f64, (height - 1) as f64);
+        *pixel = *source.get_pixel(from_x as u32, from_y as u32);
+    }
+}
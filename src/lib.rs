@@ -0,0 +1,3106 @@
+#![feature(portable_simd)]
+
+use clap::ArgEnum;
+use image::{
+    imageops::{self, FilterType},
+    GrayImage, ImageBuffer, Pixel, Rgb, Rgba,
+};
+use palette::{white_point::D65, ColorDifference, FromColor, Lab, LinSrgb, Srgb};
+use rayon::prelude::*;
+use rug::float::Constant;
+use rug::{Complex, Float};
+use std::{
+    borrow::Cow,
+    iter,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+mod coloring;
+mod gradient;
+mod newton;
+mod perturbation;
+mod quantize;
+mod scalar;
+mod simd;
+mod state;
+
+use coloring::{coloring, interior_coloring, Coloring, Histogram};
+pub use gradient::{
+    builtin_gradient, interior_gradient, load_palette, palette_from_image, render_swatch,
+    reverse_stops, rotate_stops, stops_json, ColorGradient, PaletteError, Stops,
+};
+use newton::NewtonIter;
+pub use newton::RootMethod;
+pub use perturbation::ReferenceOrbit;
+pub use quantize::{quantize, Quantized, Quantizer};
+use scalar::ComplexScalar;
+pub use state::RenderState;
+use state::SampleState;
+
+/// Iterated function used to render the image
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fractal {
+    /// z = z^n + c
+    Mandelbrot,
+    /// z = (|re z| + i|im z|)^n + c
+    BurningShip,
+    /// z = conj(z)^n + c, also known as the Mandelbar set
+    Tricorn,
+    /// z = |re z^n| + i(im z^n) + c
+    Celtic,
+    /// z = (|re z| - i(im z))^n + c
+    Perpendicular,
+    /// z = (|re z| + i(im z))^n + c
+    Heart,
+    /// z = c * exp(z)
+    Exp,
+    /// z = c * sin(z)
+    Sin,
+    /// z = c * cos(z)
+    Cos,
+}
+
+/// How escape counts are spread over the gradient
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColoringMode {
+    /// Looping over it every `gradient_interval` counts
+    EscapeCount,
+    /// Once over the whole `take`
+    Exponential,
+    /// By each count's rank among all the samples in the image, so every
+    /// part of the gradient is used as much
+    Histogram,
+    /// By the argument of z on escaping, its angle about 0, once round
+    /// the gradient. Darkened through each escape count band when smooth
+    Arg,
+}
+
+/// How points inside the set are colored, instead of with `background`
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteriorColoring {
+    /// By |z| at the end of the orbit, relative to the escape radius
+    Modulus,
+    /// By the period of the cycle the orbit settles into. Points whose
+    /// cycle isn't found within `take` keep the background
+    Period,
+}
+
+/// What happens to escape counts past the end of the gradient
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientMode {
+    /// Start again from the beginning
+    Wrap,
+    /// Stay at the last color
+    Clamp,
+    /// Run back through it, and forward again, without a seam
+    Mirror,
+}
+
+/// Color space the gradient mixes between its stops in
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Linear sRGB, which can muddy the colors halfway between stops
+    Linsrgb,
+    /// Perceptually even steps in lightness, chroma and hue
+    Oklab,
+    /// CIE L*a*b*, also perceptual but less even in hue than Oklab
+    Lab,
+    /// Hue, saturation and lightness, going round the hue wheel
+    Hsl,
+}
+
+/// How the gradient mixes from one stop to the next, or a zoom animation
+/// from its first frame to its last, as a share of the way between them
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    /// At an even pace
+    Linear,
+    /// Slowly out of the start and into the end, and quickly halfway
+    #[clap(alias = "ease-in-out")]
+    Smoothstep,
+    /// Slowly out of the start and quickly into the end
+    EaseIn,
+    /// Quickly out of the start and slowly into the end
+    EaseOut,
+}
+
+impl Easing {
+    /// How far along the way it has eased after `t` of it, both from 0
+    /// to 1
+    pub fn ease(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::Smoothstep => t * t * (3_f64 - 2_f64 * t),
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2_f64 - t),
+        }
+    }
+}
+
+/// Which pixels are rendered with a grid of `supersample` samples
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AntiAliasing {
+    /// Every one
+    Grid,
+    /// Only those whose escape count, sampled once, differs from those
+    /// around it by more than `variance_threshold`
+    Adaptive,
+    /// Only those whose color, sampled once, differs from one of those
+    /// around it by more than `delta_e_threshold`, so that the edges the
+    /// gradient shows are supersampled and those it hides aren't
+    Perceptual,
+    /// Only those on the boundary of the set, next to a pixel that is on
+    /// the other side of it, and within `edge_dilation` pixels of one
+    Edges,
+}
+
+/// Order the rows of the image are rendered in, which only shows while
+/// it is being rendered
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillOrder {
+    /// From the top down
+    Rows,
+    /// Outwards from the middle row in steps of the golden ratio of the
+    /// height, so that the rows finished so far are spread across it
+    Golden,
+}
+
+/// Shape the orbit is measured against for orbit trap coloring
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrbitTrap {
+    /// Distance to the trap position
+    Point,
+    /// Distance to the nearer of the horizontal and vertical lines
+    /// through the trap position
+    Cross,
+}
+
+/// Everything needed to render an image, with bounds already
+/// resolved to the working precision
+#[derive(Clone)]
+pub struct RenderConfig {
+    /// Output size in pixels (width, height)
+    pub resolution: (u32, u32),
+    /// Bits of precision used for every point in the image
+    pub precision: u32,
+    /// Real part of the top left pixel of the whole image
+    pub x_begin: Float,
+    /// Imaginary part of the top left pixel of the whole image
+    pub y_begin: Float,
+    /// Pixel of the whole image this one starts at, when it is a tile of
+    /// it. Each pixel is placed from the whole image's top left, so that
+    /// tiles round the same way it does
+    pub origin: (u32, u32),
+    /// Distance between pixels on the real axis
+    pub x_step: Float,
+    /// Distance between pixels on the imaginary axis
+    pub y_step: Float,
+    /// Samples to iterate before a point is considered in the set
+    pub take: usize,
+    /// Colors are looked up across the whole domain of the gradient
+    pub gradient: ColorGradient,
+    /// Interval the gradient loops on with escape count coloring
+    pub gradient_interval: usize,
+    /// Loop over the gradient this many times across `take`, in place of
+    /// `gradient_interval`
+    pub repeat_palette: Option<u32>,
+    /// How escape counts are colored, when nothing else about the orbit
+    /// colors it
+    pub coloring: ColoringMode,
+    /// How escape counts past the end of the interval (or `take`) are
+    /// colored
+    pub gradient_mode: GradientMode,
+    /// Fraction of the gradient every position along it is moved on by,
+    /// wrapping past the end
+    pub palette_cycle: f64,
+    /// sRGB color of points inside the set, or escaping ones when inverted
+    pub background: [u8; 3],
+    /// Color the interior of the set by where its orbits end, and points
+    /// that escape with `background`
+    pub invert: bool,
+    /// Color the interior of the set from `interior_gradient` as well as
+    /// the points that escape
+    pub interior: Option<InteriorColoring>,
+    /// Colors looked up for the interior, across its whole domain
+    pub interior_gradient: ColorGradient,
+    /// Dither 8-bit output with an ordered (Bayer) pattern
+    pub dither: bool,
+    /// Encode 8-bit output with this power curve instead of the sRGB
+    /// transfer function
+    pub gamma: Option<f64>,
+    /// Scale escape counts logarithmically, from 0 to `take`, before they
+    /// are looked up in the gradient
+    pub log_scale: bool,
+    pub fractal: Fractal,
+    /// Exponent of the iterated polynomial (z^n + c)
+    pub power: u32,
+    /// Escape radius: a point has escaped once |z| exceeds this, or for
+    /// the transcendental fractals, once the part of z they grow with does
+    pub bailout: f64,
+    /// Render the Julia set for this constant instead of the Mandelbrot set
+    pub julia: Option<Complex>,
+    pub smooth: bool,
+    /// Steps iterated past escaping before taking the modulus smooth
+    /// coloring works from, which is truer the further z has run off
+    pub continue_past_bailout: u32,
+    pub distance_estimate: bool,
+    /// Light the boundary as a surface whose normals follow z / dc
+    pub shade: bool,
+    /// Direction the shading light comes from, in degrees from the
+    /// positive real axis towards the positive imaginary axis
+    pub light_angle: f64,
+    /// Stop iterating points whose orbit has settled into a cycle
+    pub detect_periodicity: bool,
+    /// Samples per pixel along each axis, averaged for antialiasing
+    pub supersample: u32,
+    /// Ignored with histogram coloring, and when continuing from a state
+    pub anti_aliasing: AntiAliasing,
+    /// Ignored with histogram coloring and adaptive anti-aliasing, which
+    /// color every row at once
+    pub fill_order: FillOrder,
+    /// Variance of the escape counts in the 3x3 pixels around a pixel
+    /// above which adaptive anti-aliasing supersamples it, where points
+    /// in the set count as `take`
+    pub variance_threshold: f64,
+    /// CIEDE2000 difference between the color of a pixel and one of the
+    /// 8 around it above which perceptual anti-aliasing supersamples it
+    pub delta_e_threshold: f64,
+    /// Pixels around the boundary of the set that edge anti-aliasing
+    /// supersamples too
+    pub edge_dilation: u32,
+    /// Color every point by the orbit's closest approach to a trap
+    /// instead of by its escape count
+    pub orbit_trap: Option<OrbitTrap>,
+    /// Where the orbit trap is centered
+    pub trap_position: Complex,
+    /// Starting value of z for every orbit of the Mandelbrot set
+    pub z0: Complex,
+    /// Render the basins of Newton's method for z^n - 1 of this degree
+    /// instead, colored by root
+    pub newton: Option<u32>,
+    /// How the roots are found when rendering `newton` basins
+    pub root_method: RootMethod,
+    /// Color escaping points by the average of
+    /// 0.5 + 0.5 * sin(density * arg z) over their orbit
+    pub stripe_density: Option<f64>,
+    /// Color escaping points by the triangle inequality average over
+    /// their orbit (TIA), instead of by escape count
+    pub triangle_inequality: bool,
+    /// Iterate pixels in hardware floats as offsets from a full precision
+    /// orbit through the center of the image. Only used for Mandelbrot
+    /// z^2 + c deeper than hardware floats reach
+    pub perturbation: bool,
+    /// Terms of the series approximating the first iterations of each
+    /// pixel when rendering with perturbation, or 0 to iterate them all
+    pub series_terms: usize,
+    /// Move the reference orbit of a perturbation render onto the real
+    /// axis when it runs within a pixel of the center of the image
+    pub real_axis_fold: bool,
+    /// Reference orbit computed before, ie. for another frame of a zoom,
+    /// used instead of computing one when it serves this image
+    pub reference: Option<Arc<ReferenceOrbit>>,
+    /// Point the sampling grid is turned about, and by how many degrees,
+    /// from the positive real axis towards the positive imaginary axis
+    pub rotation: Option<(Complex, f64)>,
+    /// Pixels of the whole image to render, those that are white in it.
+    /// The rest aren't iterated: they're colored from what the state of
+    /// the render knows of them, or left as the background
+    pub mask: Option<Arc<GrayImage>>,
+    /// Iterate plain z^2 + c orbits in hardware floats several points at
+    /// a time with SIMD. Turned off to check against iterating each alone
+    pub simd: bool,
+    /// Iterate in `precision` bit floats even when it fits in hardware
+    /// floats, to check the two agree
+    pub force_bignum: bool,
+}
+
+impl RenderConfig {
+    /// The `width` by `height` part of the image with its top left
+    /// pixel at (`x`, `y`)
+    pub fn tile(&self, x: u32, y: u32, width: u32, height: u32) -> RenderConfig {
+        RenderConfig {
+            resolution: (width, height),
+            origin: (self.origin.0 + x, self.origin.1 + y),
+            ..self.clone()
+        }
+    }
+
+    /// The same image at `factor` times the resolution across and down,
+    /// with the bits to tell its smaller pixels apart
+    pub fn oversized(&self, factor: u32) -> RenderConfig {
+        let precision = self.precision + (u32::BITS - (factor - 1).leading_zeros());
+        RenderConfig {
+            resolution: (self.resolution.0 * factor, self.resolution.1 * factor),
+            precision,
+            x_begin: Float::with_val(precision, &self.x_begin),
+            y_begin: Float::with_val(precision, &self.y_begin),
+            origin: (self.origin.0 * factor, self.origin.1 * factor),
+            x_step: Float::with_val(precision, &self.x_step / factor),
+            y_step: Float::with_val(precision, &self.y_step / factor),
+            ..self.clone()
+        }
+    }
+
+    /// Whether orbits are iterated in hardware floats, which are far
+    /// faster, and at least as accurate while the image needs no more
+    /// precision than they have
+    pub fn in_hardware(&self) -> bool {
+        !self.force_bignum && self.precision <= f64::MANTISSA_DIGITS
+    }
+
+    /// The same image with its points worked out to at least as many
+    /// bits as hardware floats hold, so that iterating them in hardware
+    /// or forced arbitrary precision starts from the same points
+    fn at_hardware_precision(&self) -> Cow<'_, RenderConfig> {
+        if self.precision < f64::MANTISSA_DIGITS {
+            Cow::Owned(RenderConfig {
+                precision: f64::MANTISSA_DIGITS,
+                ..self.clone()
+            })
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+
+    /// The power |z| is raised to by each step once it is large: the
+    /// `power` of z, or 2 for the transcendental fractals, whose `f(z)`
+    /// grows about as fast as z^2 does
+    pub(crate) fn escape_degree(&self) -> f64 {
+        if self.fractal.is_transcendental() {
+            2_f64
+        } else {
+            self.power as f64
+        }
+    }
+}
+
+struct SquaresComplex<T> {
+    z: T,
+    c: T,
+    power: u32,
+    fractal: Fractal,
+    bailout: f64,
+    julia: bool,
+    /// Derivative of z, only tracked for distance estimation
+    dc: Option<T>,
+    /// Steps taken past escaping before measuring the orbit
+    past_bailout: u32,
+    /// |z| at the step the orbit escaped, if it has
+    escape_modulus: f64,
+    /// arg z at the step the orbit escaped, if it has
+    escape_angle: f64,
+    /// |dc| at the step the orbit escaped, if it has
+    derivative_modulus: Float,
+    /// Angle of z / dc at the step the orbit escaped, which points along
+    /// the normal of the surface slope shading lights
+    normal_angle: f64,
+    periodicity: Option<Periodicity<T>>,
+    /// Length of the cycle the orbit stopped on finding it repeats
+    period: Option<u32>,
+    trap: Option<Trap<T>>,
+    average: Option<Average>,
+}
+
+/// Height of the slope shading light above the plane, relative to the
+/// length of its direction along the plane
+const LIGHT_HEIGHT: f64 = 1.5;
+
+/// Fraction of the image that may stop at `take` before it is doubled,
+/// and that may escape after doubling it before `take` counts as stable
+const AUTO_TAKE_FRACTION: f64 = 1e-3;
+
+/// Most times `auto_take` doubles `take`
+const AUTO_TAKE_DOUBLINGS: u32 = 10;
+
+/// Iterations over which a Newton basin fades to half brightness
+const NEWTON_FADE: f64 = 8_f64;
+
+/// Brightness of the end of each escape count band, when coloring by
+/// the argument of z with smoothing
+const ARG_DARKEST: f64 = 0.4;
+
+/// Fraction of a pixel the rows may be off from mirroring each other
+/// about the real axis and still be copied rather than rendered
+const MIRROR_TOLERANCE: f64 = 1e-6;
+
+/// (1 + sqrt 5) / 2
+const GOLDEN_RATIO: f64 = 1.618_033_988_749_895_f64;
+
+/// How an orbit ended, however it was iterated
+struct OrbitEnd {
+    /// Steps taken before escaping, or `take` if it never did
+    iterations: usize,
+    /// |z| on escaping, or on the final iteration for interior coloring
+    escape_modulus: f64,
+    /// arg z on escaping, from -pi to pi, or 0 if it never did
+    escape_angle: f64,
+    derivative_modulus: Float,
+    normal_angle: f64,
+    /// Length of the cycle the orbit was found to repeat, which stopped it
+    period: Option<u32>,
+    trap_distance: Option<f64>,
+    /// Stripe or triangle inequality averages with and without the final
+    /// step, if escaped
+    average: Option<(f64, f64)>,
+    /// Root converged to by Newton's method, whose distance from it is
+    /// then kept in `escape_modulus`
+    root: Option<usize>,
+}
+
+/// Quantity averaged over the orbit to color escaping points by
+#[derive(Clone, Copy)]
+enum Averaged {
+    /// 0.5 + 0.5 * sin(density * arg z), for stripe average coloring
+    Stripe(f64),
+    /// Where |z| lies between the bounds the triangle inequality puts on
+    /// it, ||z^n| - |c|| and |z^n| + |c|, for TIA coloring
+    TriangleInequality,
+}
+
+impl Averaged {
+    fn from_config(config: &RenderConfig) -> Option<Self> {
+        match config.stripe_density {
+            Some(density) => Some(Averaged::Stripe(density)),
+            None if config.triangle_inequality => Some(Averaged::TriangleInequality),
+            None => None,
+        }
+    }
+}
+
+/// Running average of an `Averaged` quantity over the orbit
+struct Average {
+    averaged: Averaged,
+    /// |c|, which the triangle inequality bounds are offset by
+    c_modulus: f64,
+    sum: f64,
+    last: f64,
+    count: usize,
+}
+
+impl Average {
+    fn new<T: ComplexScalar>(averaged: Averaged, c: &T) -> Self {
+        let c_modulus = match averaged {
+            Averaged::Stripe(_) => 0_f64,
+            Averaged::TriangleInequality => c.modulus().to_f64(),
+        };
+        Average {
+            averaged,
+            c_modulus,
+            sum: 0_f64,
+            last: 0_f64,
+            count: 0,
+        }
+    }
+
+    /// Add the step that reached `z` by adding `c`
+    fn update<T: ComplexScalar>(&mut self, z: &T, c: &T) {
+        self.last = match self.averaged {
+            Averaged::Stripe(density) => 0.5_f64 + 0.5_f64 * (density * z.arg()).sin(),
+            Averaged::TriangleInequality => {
+                let z_pow = z.distance(c).to_f64();
+                let low = (z_pow - self.c_modulus).abs();
+                let high = z_pow + self.c_modulus;
+                // The bounds meet when z^n is 0, ie. on the first step
+                if high <= low {
+                    return;
+                }
+                (z.modulus().to_f64() - low) / (high - low)
+            }
+        };
+        self.sum += self.last;
+        self.count += 1;
+    }
+
+    /// The average with and without the final step
+    fn averages(&self) -> (f64, f64) {
+        let average = self.sum / self.count.max(1) as f64;
+        let previous = if self.count > 1 {
+            (self.sum - self.last) / (self.count - 1) as f64
+        } else {
+            average
+        };
+        (average, previous)
+    }
+}
+
+/// Closest the orbit has come to an orbit trap
+struct Trap<T> {
+    kind: OrbitTrap,
+    position: T,
+    min_distance: f64,
+}
+
+impl<T: ComplexScalar> Trap<T> {
+    fn new(kind: OrbitTrap, position: T) -> Self {
+        Trap {
+            kind,
+            position,
+            min_distance: f64::INFINITY,
+        }
+    }
+
+    fn update(&mut self, z: &T) {
+        let distance = match self.kind {
+            OrbitTrap::Point => z.distance(&self.position).to_f64(),
+            OrbitTrap::Cross => z.axis_distance(&self.position),
+        };
+        self.min_distance = self.min_distance.min(distance);
+    }
+}
+
+/// Brent's cycle detection: `z` is compared against a reference that is
+/// replaced after every power of two steps
+struct Periodicity<T> {
+    reference: T,
+    epsilon: Float,
+    epsilon_squared: Float,
+    /// Room to work out the distance to `reference` in each step
+    scratch: T,
+    steps: u32,
+    limit: u32,
+}
+
+impl<T: ComplexScalar> Periodicity<T> {
+    fn new(z: &T) -> Self {
+        // The orbit is only "repeating" once it is within a few bits of the
+        // working precision, so no escaping point is ever cut short
+        let epsilon = Float::with_val(53, 1) >> z.prec().saturating_sub(4);
+        Periodicity {
+            reference: z.clone(),
+            epsilon_squared: Float::with_val(53, epsilon.square_ref()),
+            epsilon,
+            scratch: z.clone(),
+            steps: 0,
+            limit: 1,
+        }
+    }
+
+    /// Steps `z` took to come back round to where it was, if it has. A
+    /// multiple of the period when the orbit spirals in on its cycle
+    fn cycle(&mut self, z: &T) -> Option<u32> {
+        if z.within(&self.reference, &self.epsilon_squared, &mut self.scratch) {
+            return Some(self.steps + 1);
+        }
+
+        self.steps += 1;
+        if self.steps == self.limit {
+            self.reference.clone_from(z);
+            self.steps = 0;
+            self.limit = self.limit.saturating_mul(2);
+        }
+        None
+    }
+
+    /// The period of the cycle that `z` took `cycle` steps to come back
+    /// round on: the first step it comes back within the square root of
+    /// the tolerance, as a spiral towards the cycle comes nowhere near
+    /// that close to itself before going round it
+    fn period(&self, z: &T, cycle: u32, mut step: impl FnMut(&mut T)) -> u32 {
+        let tolerance = Float::with_val(53, self.epsilon.sqrt_ref());
+        let mut orbit = z.clone();
+        for period in 1..cycle {
+            step(&mut orbit);
+            if orbit.distance(z) < tolerance {
+                return period;
+            }
+        }
+        cycle
+    }
+}
+
+impl Fractal {
+    /// Whether each step multiplies c by a function of z, which ignores
+    /// `power`, rather than adding c to a power of it
+    pub fn is_transcendental(self) -> bool {
+        matches!(self, Fractal::Exp | Fractal::Sin | Fractal::Cos)
+    }
+
+    /// Escape radius that suits the fractal when none is given. The
+    /// transcendental fractals only escape once exp(z) grows without
+    /// bound, by which point the part of z it grows with is large
+    pub fn bailout(self) -> f64 {
+        if self.is_transcendental() {
+            50_f64
+        } else {
+            2_f64
+        }
+    }
+
+    /// Whether z has escaped `bailout`. exp(z) is periodic in the
+    /// imaginary part of z, and sin(z) and cos(z) in its real part, so
+    /// only the other part of z tells the orbit will run off
+    fn escaped<T: ComplexScalar>(self, z: &T, bailout: f64) -> bool {
+        match self {
+            Fractal::Exp => z.parts().0 > bailout,
+            Fractal::Sin | Fractal::Cos => z.parts().1.abs() > bailout,
+            _ => z.exceeds(bailout),
+        }
+    }
+
+    /// What is done to z before it is raised to the power
+    fn fold<T: ComplexScalar>(self, z: &mut T) {
+        match self {
+            Fractal::Mandelbrot => {}
+            Fractal::BurningShip => z.abs_parts_mut(),
+            Fractal::Tricorn => z.conj_mut(),
+            Fractal::Celtic => {}
+            Fractal::Perpendicular => {
+                z.abs_real_mut();
+                z.conj_mut();
+            }
+            Fractal::Heart => z.abs_real_mut(),
+            Fractal::Exp | Fractal::Sin | Fractal::Cos => {}
+        }
+    }
+
+    /// The function of z a transcendental fractal multiplies c by, or
+    /// its derivative
+    fn apply<T: ComplexScalar>(self, z: &mut T, derivative: bool) {
+        match (self, derivative) {
+            (Fractal::Exp, _) => z.exp_mut(),
+            (Fractal::Sin, false) | (Fractal::Cos, true) => z.sin_mut(),
+            (Fractal::Cos, false) | (Fractal::Sin, true) => z.cos_mut(),
+            _ => unreachable!("{:?} is not transcendental", self),
+        }
+        // d/dz cos(z) = -sin(z)
+        if self == Fractal::Cos && derivative {
+            z.neg_mut();
+        }
+    }
+
+    /// Steps the derivative `dc` of z along with the `fold`ed z
+    fn derive<T: ComplexScalar>(self, dc: &mut T, z: &T, power: u32, c: &T, julia: bool) {
+        if self.is_transcendental() {
+            // d/dc (c * f(z)) = c * f'(z) * dc + f(z)
+            let mut slope = z.clone();
+            self.apply(&mut slope, true);
+            dc.mul_mut(&slope);
+            dc.mul_mut(c);
+            if !julia {
+                let mut value = z.clone();
+                self.apply(&mut value, false);
+                dc.add_mut(&value);
+            }
+            return;
+        }
+
+        // d/dc (z^n + c) = n * z^(n - 1) * dc + 1
+        if power == 2 {
+            dc.mul_mut(z);
+        } else {
+            let mut z_pow = z.clone();
+            z_pow.pow_mut(power - 1);
+            dc.mul_mut(&z_pow);
+        }
+        dc.scale_mut(power);
+        if !julia {
+            dc.add_one_mut();
+        }
+    }
+
+    /// The rest of the step from `fold`ed z: z^power, and then c added
+    fn raise<T: ComplexScalar>(self, z: &mut T, power: u32, c: &T) {
+        if self.is_transcendental() {
+            self.apply(z, false);
+            z.mul_mut(c);
+            return;
+        }
+        if power == 2 {
+            z.square_mut();
+        } else {
+            z.pow_mut(power);
+        }
+        if self == Fractal::Celtic {
+            z.abs_real_mut();
+        }
+        z.add_mut(c);
+    }
+}
+
+impl<T: ComplexScalar> SquaresComplex<T> {
+    fn with_derivative(mut self) -> Self {
+        // Mandelbrot differentiates by c (dc_0 = 0), Julia by z_0 (dz_0 = 1)
+        let mut dc = self.z.zero_like();
+        if self.julia {
+            dc.add_one_mut();
+        }
+        self.dc = Some(dc);
+        self
+    }
+
+    fn with_steps_past_bailout(mut self, steps: u32) -> Self {
+        self.past_bailout = steps;
+        self
+    }
+
+    fn with_periodicity(mut self) -> Self {
+        self.periodicity = Some(Periodicity::new(&self.z));
+        self
+    }
+
+    fn with_trap(mut self, kind: OrbitTrap, position: &T) -> Self {
+        self.trap = Some(Trap::new(kind, position.clone()));
+        self
+    }
+
+    fn with_average(mut self, averaged: Averaged) -> Self {
+        self.average = Some(Average::new(averaged, &self.c));
+        self
+    }
+
+    /// Iterate z, and its derivative if tracked, once
+    fn step(&mut self) {
+        self.fractal.fold(&mut self.z);
+
+        if let Some(dc) = &mut self.dc {
+            self.fractal
+                .derive(dc, &self.z, self.power, &self.c, self.julia);
+        }
+
+        self.fractal.raise(&mut self.z, self.power, &self.c);
+    }
+}
+
+impl<T: ComplexScalar> Iterator for SquaresComplex<T> {
+    type Item = ();
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step();
+
+        // The starting point is the same for every Mandelbrot orbit, so
+        // only iterated points are measured
+        if let Some(trap) = &mut self.trap {
+            trap.update(&self.z);
+        }
+        if let Some(average) = &mut self.average {
+            average.update(&self.z, &self.c);
+        }
+
+        if self.fractal.escaped(&self.z, self.bailout) {
+            for _ in 0..self.past_bailout {
+                self.step();
+            }
+            self.escape_modulus = self.z.modulus().to_f64();
+            self.escape_angle = self.z.arg();
+            if let Some(dc) = &self.dc {
+                self.derivative_modulus = dc.modulus();
+                self.normal_angle = self.z.arg() - dc.arg();
+            }
+            None
+        } else if let Some(periodicity) = &mut self.periodicity {
+            match periodicity.cycle(&self.z) {
+                Some(cycle) => {
+                    let (fractal, power) = (self.fractal, self.power);
+                    let step = |z: &mut T| {
+                        fractal.fold(z);
+                        fractal.raise(z, power, &self.c);
+                    };
+                    self.period = Some(periodicity.period(&self.z, cycle, step));
+                    None
+                }
+                None => Some(()),
+            }
+        } else {
+            Some(())
+        }
+    }
+}
+
+fn square_iter<T: ComplexScalar>(
+    mut z0: T,
+    c: T,
+    power: u32,
+    fractal: Fractal,
+    bailout: f64,
+) -> SquaresComplex<T> {
+    // c * sin(z) never leaves 0, so its orbits start from the critical
+    // point pi / 2 instead. Every other fractal's first step, from 0,
+    // already lands on c
+    if fractal == Fractal::Sin {
+        let prec = z0.prec();
+        let half_pi = Float::with_val(prec, Constant::Pi) / 2;
+        z0.add_mut(&T::from_complex(&Complex::with_val(prec, (half_pi, 0))));
+    }
+    SquaresComplex {
+        z: z0,
+        c,
+        power,
+        fractal,
+        bailout,
+        julia: false,
+        dc: None,
+        past_bailout: 0,
+        escape_modulus: 0_f64,
+        escape_angle: 0_f64,
+        derivative_modulus: Float::new(53),
+        normal_angle: 0_f64,
+        periodicity: None,
+        period: None,
+        trap: None,
+        average: None,
+    }
+}
+
+fn julia_iter<T: ComplexScalar>(
+    z0: T,
+    c: T,
+    power: u32,
+    fractal: Fractal,
+    bailout: f64,
+) -> SquaresComplex<T> {
+    SquaresComplex {
+        z: z0,
+        c,
+        power,
+        fractal,
+        bailout,
+        julia: true,
+        dc: None,
+        past_bailout: 0,
+        escape_modulus: 0_f64,
+        escape_angle: 0_f64,
+        derivative_modulus: Float::new(53),
+        normal_angle: 0_f64,
+        periodicity: None,
+        period: None,
+        trap: None,
+        average: None,
+    }
+}
+
+/// Steps of an orbit, counted from 1, with z after each
+struct Steps<T> {
+    orbit: SquaresComplex<T>,
+    iteration: usize,
+    done: bool,
+}
+
+impl<T: ComplexScalar> SquaresComplex<T> {
+    fn steps(self) -> Steps<T> {
+        Steps {
+            orbit: self,
+            iteration: 0,
+            done: false,
+        }
+    }
+}
+
+impl<T: ComplexScalar> Iterator for Steps<T> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.iteration += 1;
+        if self.orbit.next().is_none() {
+            self.done = true;
+            // Orbits found to repeat never escape, so have no last step
+            if self.orbit.period.is_some() {
+                return None;
+            }
+        }
+        Some((self.iteration, self.orbit.z.clone()))
+    }
+}
+
+/// The orbit of `point` in the fractal `config` renders, at its
+/// precision: each step, counted from 1, with z after it. It ends with
+/// the step z escapes on, or after `take` steps if it never does, so
+/// that any coloring can be worked out from it without iterating again
+pub fn orbit(config: &RenderConfig, point: &Complex) -> impl Iterator<Item = (usize, Complex)> {
+    let prec = config.precision;
+    let point = Complex::with_val(prec, point);
+    let orbit = match &config.julia {
+        Some(c) => {
+            let (real_prec, imag_prec) = c.prec();
+            let c = Complex::with_val(prec.max(real_prec).max(imag_prec), c);
+            julia_iter(point, c, config.power, config.fractal, config.bailout)
+        }
+        None => square_iter(
+            Complex::with_val(prec, &config.z0),
+            point,
+            config.power,
+            config.fractal,
+            config.bailout,
+        ),
+    };
+    orbit.steps().take(config.take)
+}
+
+/// The point at (`x`, `y`) in pixels from the top left of the image
+/// `config` renders, which may be outside of it. The inverse of
+/// `pixel_of`
+pub fn point_of(config: &RenderConfig, (x, y): (f64, f64)) -> Complex {
+    let prec = config.precision;
+    let x = Float::with_val(prec, x + config.origin.0 as f64) * &config.x_step;
+    let y = Float::with_val(prec, y + config.origin.1 as f64) * &config.y_step;
+    let point = Complex::with_val(prec, (x + &config.x_begin, y + &config.y_begin));
+    rotate(config, point)
+}
+
+/// Where `point` falls in the image `config` renders, in pixels from its
+/// top left, which may be outside of it. The inverse of the point each
+/// pixel is iterated at
+pub fn pixel_of(config: &RenderConfig, point: &Complex) -> (f64, f64) {
+    let prec = config.precision;
+    let point = Complex::with_val(prec, point);
+    let point = match &config.rotation {
+        Some((center, degrees)) => turn(prec, center, -degrees, point),
+        None => point,
+    };
+    let (re, im) = point.into_real_imag();
+    let x = Float::with_val(prec, re - &config.x_begin) / &config.x_step;
+    let y = Float::with_val(prec, im - &config.y_begin) / &config.y_step;
+    (
+        x.to_f64() - config.origin.0 as f64,
+        y.to_f64() - config.origin.1 as f64,
+    )
+}
+
+/// Whether `c` lies in the main cardioid or the period-2 bulb, both of
+/// which are entirely inside the Mandelbrot set
+fn in_main_bulbs(c: &Complex) -> bool {
+    let prec = c.prec().0;
+    let (x, y) = (c.real(), c.imag());
+    let y2 = Float::with_val(prec, y.square_ref());
+
+    // q * (q + (x - 1/4)) <= y^2 / 4
+    let x_shift = Float::with_val(prec, x - 0.25_f64);
+    let q = Float::with_val(prec, x_shift.square_ref()) + &y2;
+    let cardioid = Float::with_val(prec, &q + &x_shift) * &q;
+    if cardioid <= Float::with_val(prec, &y2 / 4) {
+        return true;
+    }
+
+    // (x + 1)^2 + y^2 <= 1/16
+    let bulb = Float::with_val(prec, x + 1).square() + &y2;
+    bulb <= 0.0625_f64
+}
+
+/// Point at the center of the image, at the working precision
+fn image_center(config: &RenderConfig) -> Complex {
+    let prec = config.precision;
+    let (x, y) = (
+        config.origin.0 + config.resolution.0 / 2,
+        config.origin.1 + config.resolution.1 / 2,
+    );
+    let x = Float::with_val(prec, &config.x_step * x) + &config.x_begin;
+    let y = Float::with_val(prec, &config.y_step * y) + &config.y_begin;
+    rotate(config, Complex::with_val(prec, (x, y)))
+}
+
+/// `point` of the unturned grid, turned by `config.rotation`
+fn rotate(config: &RenderConfig, point: Complex) -> Complex {
+    match &config.rotation {
+        Some((center, degrees)) => turn(config.precision, center, *degrees, point),
+        None => point,
+    }
+}
+
+/// `point` turned `degrees` anticlockwise about `center`
+fn turn(prec: u32, center: &Complex, degrees: f64, point: Complex) -> Complex {
+    // Quarter turns are exact, so that they only move pixels around
+    let turn = degrees.rem_euclid(360_f64);
+    let (sin, cos) = if turn == 90_f64 {
+        (1_f64, 0_f64)
+    } else if turn == 180_f64 {
+        (0_f64, -1_f64)
+    } else if turn == 270_f64 {
+        (-1_f64, 0_f64)
+    } else {
+        turn.to_radians().sin_cos()
+    };
+    let (re, im) = Complex::with_val(prec, &point - center).into_real_imag();
+    let turned = (
+        Float::with_val(prec, &re * cos) - Float::with_val(prec, &im * sin),
+        Float::with_val(prec, &re * sin) + Float::with_val(prec, &im * cos),
+    );
+    Complex::with_val(prec, turned) + center
+}
+
+/// Point the reference orbit of a perturbation render goes through: the
+/// center of the image, or the point on the real axis level with it when
+/// folding onto the axis and it runs within a pixel of the center
+fn reference_center(config: &RenderConfig) -> Complex {
+    let mut center = image_center(config);
+    if config.real_axis_fold && *center.imag().as_abs() <= *config.y_step.as_abs() {
+        *center.mut_imag() = Float::new(center.imag().prec());
+    }
+    center
+}
+
+/// The full precision orbit perturbation renders the image `config`
+/// frames relative to
+pub fn reference_orbit(config: &RenderConfig) -> ReferenceOrbit {
+    ReferenceOrbit::new(reference_center(config), config.take, config.bailout)
+}
+
+/// The full precision orbit of the point `reference_orbit` is computed
+/// at for `config`, as `orbit` iterates it
+pub fn reference_steps(config: &RenderConfig) -> impl Iterator<Item = (usize, Complex)> {
+    orbit(config, &reference_center(config))
+}
+
+/// Corners and edge midpoints of the image, which the series
+/// approximation has to stay accurate at
+fn probe_points(config: &RenderConfig) -> Vec<Complex> {
+    let prec = config.precision;
+    let (width, height) = config.resolution;
+    let xs = [0, width / 2, width];
+    let ys = [0, height / 2, height];
+
+    xs.iter()
+        .flat_map(|&x| ys.iter().map(move |&y| (x, y)))
+        .filter(|&(x, y)| x != width / 2 || y != height / 2)
+        .map(|(x, y)| {
+            let re =
+                Float::with_val(prec, &config.x_step * (config.origin.0 + x)) + &config.x_begin;
+            let im =
+                Float::with_val(prec, &config.y_step * (config.origin.1 + y)) + &config.y_begin;
+            rotate(config, Complex::with_val(prec, (re, im)))
+        })
+        .collect()
+}
+
+/// Whether the image bounds overlap the boxes around the main cardioid
+/// and period-2 bulb, so the interior check is worth running per pixel
+fn overlaps_main_bulbs(config: &RenderConfig) -> bool {
+    // A turned image's corners aren't where its unturned grid puts them
+    if config.rotation.is_some() {
+        return true;
+    }
+    let (x, y) = config.origin;
+    let x_begin = Float::with_val(53, &config.x_step * x).to_f64() + config.x_begin.to_f64();
+    let y_begin = Float::with_val(53, &config.y_step * y).to_f64() + config.y_begin.to_f64();
+    let x_end = Float::with_val(53, &config.x_step * config.resolution.0).to_f64() + x_begin;
+    let y_end = Float::with_val(53, &config.y_step * config.resolution.1).to_f64() + y_begin;
+    let (x_min, x_max) = (x_begin.min(x_end), x_begin.max(x_end));
+    let (y_min, y_max) = (y_begin.min(y_end), y_begin.max(y_end));
+
+    let overlaps = |(bx_min, bx_max): (f64, f64), (by_min, by_max): (f64, f64)| {
+        x_min <= bx_max && x_max >= bx_min && y_min <= by_max && y_max >= by_min
+    };
+
+    overlaps((-0.76, 0.38), (-0.66, 0.66)) || overlaps((-1.26, -0.74), (-0.26, 0.26))
+}
+
+/// When rows `y` and `axis - y` of the image are mirror images of each
+/// other about the real axis, `axis`. Only when the image straddles the
+/// axis on a grid that lines up with it, and the fractal and coloring
+/// are symmetric about it
+fn mirror_axis(config: &RenderConfig) -> Option<i64> {
+    let real = |c: &Complex| c.imag().is_zero();
+    let symmetric = config.fractal != Fractal::BurningShip
+        && config.julia.iter().all(real)
+        && real(&config.z0)
+        && (config.orbit_trap.is_none() || real(&config.trap_position))
+        && config.newton.is_none()
+        && config.stripe_density.is_none()
+        && !config.shade
+        && !config.dither
+        && config.coloring != ColoringMode::Arg
+        && config.rotation.is_none();
+    if !symmetric {
+        return None;
+    }
+
+    // Sample `sub` of row y is at y + sub / samples rows from the top, so
+    // with N samples the mirror of row y is axis - y where axis is
+    // -2 * y_begin / y_step - (N - 1) / N in the whole image, and
+    // `origin` rows up from each side of that in a tile of it
+    let samples = config.supersample.max(1);
+    let rows = Float::with_val(53, &config.y_begin / &config.y_step).to_f64();
+    let axis = -2_f64 * (rows + config.origin.1 as f64) - (samples - 1) as f64 / samples as f64;
+    let rounded = axis.round();
+    let straddles = rounded > 0_f64 && rounded < 2_f64 * config.resolution.1 as f64;
+    if straddles && (axis - rounded).abs() <= MIRROR_TOLERANCE {
+        Some(rounded as i64)
+    } else {
+        None
+    }
+}
+
+/// What iterating a single pixel found, independent of how it is colored
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelResult {
+    /// Iterations before escaping, or `take` if it never did. Converging
+    /// counts as escaping for Newton's method
+    pub iterations: u32,
+    /// |z| on the final iteration, or the distance from the root that
+    /// was converged to for Newton's method. 0 if it never escaped
+    pub final_modulus: f64,
+    /// arg z on the final iteration, from -pi to pi. 0 if it never
+    /// escaped, or for Newton's method
+    pub final_angle: f64,
+    pub escaped: bool,
+}
+
+/// How far a render has got, updated as it goes so another thread can
+/// report on it
+#[derive(Default, Debug)]
+pub struct Progress {
+    /// Pixels finished, added a row (or tile) at a time
+    pub pixels: AtomicU64,
+    /// Samples iterated, of every pixel that was rendered rather than
+    /// copied
+    pub samples: AtomicU64,
+    /// Samples found to be in the set, or that reached `take`
+    pub inside: AtomicU64,
+    /// Samples whose position on the gradient came out NaN or infinite,
+    /// which were given the background instead
+    pub non_finite: AtomicU64,
+    /// Set to stop the render early. Rows it hadn't finished are left
+    /// black, and their states where they were
+    pub cancelled: AtomicBool,
+}
+
+impl Progress {
+    /// Fraction of the samples iterated so far that were in the set
+    pub fn inside_fraction(&self) -> f64 {
+        let samples = self.samples.load(Ordering::Relaxed);
+        if samples == 0 {
+            return 0_f64;
+        }
+        self.inside.load(Ordering::Relaxed) as f64 / samples as f64
+    }
+}
+
+/// How a sample escaped, kept apart from its color so coloring can
+/// depend on every other sample in the image
+#[derive(Clone)]
+struct Escape {
+    /// Escape count, fractional when smoothing
+    iterations: f64,
+    /// |z| on escaping, as in `OrbitEnd`
+    escape_modulus: f64,
+    /// arg z on escaping, as in `OrbitEnd`
+    escape_angle: f64,
+    trap_distance: Option<f64>,
+    average: Option<(f64, f64)>,
+    root: Option<usize>,
+    period: Option<u32>,
+    /// Whether the orbit escaped, or converged for Newton's method
+    escaped: bool,
+    /// Inside the set, to be colored by the interior coloring
+    inside: bool,
+    /// Distance estimate shading, 1 when not estimating
+    brightness: f64,
+}
+
+/// Conversion of the linear light of pixel (x, y) to the output format
+type Convert<S> = fn(&RenderConfig, LinSrgb<f64>, (u32, u32)) -> Rgb<S>;
+
+/// Called with each row of the image, and its subpixels, once finished
+pub type RowSink<'a, S> = dyn Fn(u32, &[S]) + Sync + 'a;
+
+/// Called with the result of each pixel of a row once it is iterated
+pub type ResultSink<'a> = dyn Fn(u32, &[PixelResult]) + Sync + 'a;
+
+/// Scale a channel to a byte, clamping values outside 0-1 that custom
+/// palettes can produce. `offset`, from -0.5 to 0.5, dithers the rounding
+fn to_u8(channel: f64, offset: f64) -> u8 {
+    (channel * 255_f64 + offset).round().clamp(0_f64, 255_f64) as u8
+}
+
+/// Threshold of pixel (x, y) in an 8x8 ordered dither, from -0.5 to 0.5
+fn bayer(x: u32, y: u32) -> f64 {
+    // Interleave the bits of x ^ y and y, most significant first
+    let mut threshold = 0;
+    for bit in 0..3 {
+        let pair = ((((x ^ y) >> bit) & 1) << 1) | ((y >> bit) & 1);
+        threshold |= pair << (2 * (2 - bit));
+    }
+    (threshold as f64 + 0.5_f64) / 64_f64 - 0.5_f64
+}
+
+/// Encode linear light for display, with the sRGB transfer function or
+/// the power curve of `config.gamma`
+fn encode(config: &RenderConfig, color: LinSrgb<f64>) -> Srgb<f64> {
+    match config.gamma {
+        Some(gamma) => {
+            let encode = |channel: f64| channel.max(0_f64).powf(1_f64 / gamma);
+            Srgb::new(encode(color.red), encode(color.green), encode(color.blue))
+        }
+        None => Srgb::from_linear(color),
+    }
+}
+
+/// Encode linear light for display and quantize pixel (x, y) to bytes
+fn to_rgb(config: &RenderConfig, color: LinSrgb<f64>, (x, y): (u32, u32)) -> Rgb<u8> {
+    let color = encode(config, color);
+    let (x, y) = (config.origin.0 + x, config.origin.1 + y);
+    let offset = if config.dither { bayer(x, y) } else { 0_f64 };
+    Rgb([
+        to_u8(color.red, offset),
+        to_u8(color.green, offset),
+        to_u8(color.blue, offset),
+    ])
+}
+
+/// Encode linear light for display like `to_rgb`, in 16 bits a channel,
+/// which are too fine to band and so aren't dithered
+fn to_rgb16(config: &RenderConfig, color: LinSrgb<f64>, _pixel: (u32, u32)) -> Rgb<u16> {
+    let color = encode(config, color);
+    let scale = |channel: f64| (channel * 65535_f64).round().clamp(0_f64, 65535_f64) as u16;
+    Rgb([scale(color.red), scale(color.green), scale(color.blue)])
+}
+
+/// Linear light is kept as it is, for formats that store it
+fn to_rgb_f32(_config: &RenderConfig, color: LinSrgb<f64>, _pixel: (u32, u32)) -> Rgb<f32> {
+    Rgb([color.red as f32, color.green as f32, color.blue as f32])
+}
+
+/// Resolution `--benchmark` renders `benchmark_config` at
+pub const BENCHMARK_RESOLUTION: (u32, u32) = (640, 360);
+
+/// A fixed scene to time renders with, so that they can be compared
+/// between builds: the seahorse valley, 2^-8 across with square pixels
+/// and 1000 iterations, at `resolution` and `precision` bits
+pub fn benchmark_config(resolution: (u32, u32), precision: u32) -> RenderConfig {
+    let (x, y) = (-0.743643887037151_f64, 0.131825904205330_f64);
+    let step = Float::with_val(precision, 2_f64.powi(-8)) / resolution.0;
+    let half = |pixels: u32| Float::with_val(precision, &step * pixels) / 2;
+    RenderConfig {
+        resolution,
+        precision,
+        x_begin: Float::with_val(precision, x) - half(resolution.0),
+        y_begin: Float::with_val(precision, y) - half(resolution.1),
+        origin: (0, 0),
+        x_step: step.clone(),
+        y_step: step,
+        take: 1000,
+        gradient: ColorGradient::new(builtin_gradient(false), ColorSpace::Linsrgb),
+        gradient_interval: 300,
+        repeat_palette: None,
+        coloring: ColoringMode::EscapeCount,
+        gradient_mode: GradientMode::Wrap,
+        palette_cycle: 0_f64,
+        background: [0, 0, 0],
+        invert: false,
+        interior: None,
+        interior_gradient: ColorGradient::new(interior_gradient(), ColorSpace::Linsrgb),
+        dither: false,
+        gamma: None,
+        log_scale: false,
+        fractal: Fractal::Mandelbrot,
+        power: 2,
+        bailout: 2_f64,
+        julia: None,
+        smooth: false,
+        continue_past_bailout: 0,
+        distance_estimate: false,
+        shade: false,
+        light_angle: 45_f64,
+        detect_periodicity: false,
+        supersample: 1,
+        anti_aliasing: AntiAliasing::Grid,
+        fill_order: FillOrder::Rows,
+        variance_threshold: 0.1_f64,
+        delta_e_threshold: 2_f64,
+        edge_dilation: 1,
+        orbit_trap: None,
+        trap_position: Complex::new(precision),
+        z0: Complex::new(precision),
+        newton: None,
+        root_method: RootMethod::Newton,
+        stripe_density: None,
+        triangle_inequality: false,
+        perturbation: false,
+        series_terms: 0,
+        real_axis_fold: false,
+        reference: None,
+        rotation: None,
+        mask: None,
+        simd: true,
+        force_bignum: false,
+    }
+}
+
+pub fn render(config: &RenderConfig) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    render_with_progress(config, &Progress::default())
+}
+
+/// Render the image, adding the number of pixels in each finished row
+/// to `progress` so another thread can report on it
+pub fn render_with_progress(
+    config: &RenderConfig,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    render_pixels(config, None, progress, to_rgb, None, None, None)
+}
+
+/// Render the image with an alpha channel, transparent where its samples
+/// were in the set, masked out, or couldn't be colored, and partly so
+/// where only some of them were. The background is left out of the
+/// colors, for whatever the image is laid over to show through instead
+pub fn render_rgba_with_progress(
+    config: &RenderConfig,
+    progress: &Progress,
+) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let (width, height) = config.resolution;
+    let alpha = Mutex::new(vec![0_u8; width as usize * height as usize]);
+    let on_coverage = |y: u32, coverage: &[f32]| {
+        let start = y as usize * width as usize;
+        let mut alpha = alpha.lock().expect("No render panicked holding the alpha");
+        for (alpha, &coverage) in alpha[start..start + coverage.len()]
+            .iter_mut()
+            .zip(coverage)
+        {
+            *alpha = (coverage * 255_f32).round() as u8;
+        }
+    };
+    let img = render_pixels(
+        config,
+        None,
+        progress,
+        to_rgb,
+        None,
+        None,
+        Some(&on_coverage),
+    );
+    let alpha = alpha
+        .into_inner()
+        .expect("No render panicked holding the alpha");
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let Rgb([red, green, blue]) = *img.get_pixel(x, y);
+        Rgba([red, green, blue, alpha[(y * width + x) as usize]])
+    })
+}
+
+/// Render the image, handing each row to `on_row` as soon as it is
+/// finished, in the order of `config.fill_order`, for showing the image
+/// as it fills in
+pub fn render_with_rows(
+    config: &RenderConfig,
+    progress: &Progress,
+    on_row: &RowSink<'_, u8>,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    render_pixels(config, None, progress, to_rgb, Some(on_row), None, None)
+}
+
+/// Render the image, picking each sample up from where `state` left it
+/// and leaving where it got to there, so the render can be continued
+/// with a larger `take`
+pub fn render_with_state(
+    config: &RenderConfig,
+    state: &mut RenderState,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    render_pixels(config, Some(state), progress, to_rgb, None, None, None)
+}
+
+/// Render the image, handing the result of each pixel of a row to
+/// `on_results` as the row is iterated, as `render_iterations` would
+/// find them, without iterating the image a second time for them. Rows
+/// can come in any order, and none do when the image is masked
+pub fn render_with_results(
+    config: &RenderConfig,
+    progress: &Progress,
+    on_results: &ResultSink<'_>,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    render_pixels(config, None, progress, to_rgb, None, Some(on_results), None)
+}
+
+/// Render the image in 16 bits a channel, for smooth gradients without
+/// the bands of 8
+pub fn render_16_with_progress(
+    config: &RenderConfig,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    render_pixels(config, None, progress, to_rgb16, None, None, None)
+}
+
+/// `render_with_state` in 16 bits a channel
+pub fn render_16_with_state(
+    config: &RenderConfig,
+    state: &mut RenderState,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    render_pixels(config, Some(state), progress, to_rgb16, None, None, None)
+}
+
+pub fn render_hdr(config: &RenderConfig) -> ImageBuffer<Rgb<f32>, Vec<f32>> {
+    render_hdr_with_progress(config, &Progress::default())
+}
+
+/// Render the image in linear light without quantizing it to bytes, for
+/// formats with the range to hold it
+pub fn render_hdr_with_progress(
+    config: &RenderConfig,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<f32>, Vec<f32>> {
+    render_pixels(config, None, progress, to_rgb_f32, None, None, None)
+}
+
+/// Render the image at `factor` times the resolution across and down,
+/// then shrink it with a Lanczos filter in linear light. The larger image
+/// is held in memory, 12 bytes a pixel
+pub fn render_oversized(
+    config: &RenderConfig,
+    factor: u32,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    oversized_in(config, factor, progress, to_rgb)
+}
+
+/// `render_oversized` in 16 bits a channel
+pub fn render_oversized_16(
+    config: &RenderConfig,
+    factor: u32,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    oversized_in(config, factor, progress, to_rgb16)
+}
+
+/// `render_oversized`, with the shrunk pixels converted by `convert`
+fn oversized_in<S>(
+    config: &RenderConfig,
+    factor: u32,
+    progress: &Progress,
+    convert: Convert<S>,
+) -> ImageBuffer<Rgb<S>, Vec<S>>
+where
+    Rgb<S>: Pixel<Subpixel = S>,
+{
+    let img = render_oversized_hdr(config, factor, progress);
+    ImageBuffer::from_fn(config.resolution.0, config.resolution.1, |x, y| {
+        // The filter's negative lobes can ring below black
+        let Rgb([red, green, blue]) = img.get_pixel(x, y).map(|channel| channel.max(0_f32));
+        convert(
+            config,
+            LinSrgb::new(red as f64, green as f64, blue as f64),
+            (x, y),
+        )
+    })
+}
+
+/// `render_oversized` in linear light, like `render_hdr_with_progress`
+pub fn render_oversized_hdr(
+    config: &RenderConfig,
+    factor: u32,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<f32>, Vec<f32>> {
+    let img = render_hdr_with_progress(&config.oversized(factor), progress);
+    imageops::resize(
+        &img,
+        config.resolution.0,
+        config.resolution.1,
+        FilterType::Lanczos3,
+    )
+}
+
+/// `render_with_state` in linear light, like `render_hdr_with_progress`
+pub fn render_hdr_with_state(
+    config: &RenderConfig,
+    state: &mut RenderState,
+    progress: &Progress,
+) -> ImageBuffer<Rgb<f32>, Vec<f32>> {
+    render_pixels(config, Some(state), progress, to_rgb_f32, None, None, None)
+}
+
+/// Iterate every pixel without coloring it, returning the results row by
+/// row from the top left. Each pixel is sampled once, at its top left
+/// corner, so supersampling has no effect
+pub fn render_iterations(config: &RenderConfig) -> Vec<PixelResult> {
+    render_iterations_with_progress(config, &Progress::default())
+}
+
+/// Iterate every pixel without coloring it, adding the number of pixels
+/// in each finished row to `progress`
+pub fn render_iterations_with_progress(
+    config: &RenderConfig,
+    progress: &Progress,
+) -> Vec<PixelResult> {
+    let config = &config.at_hardware_precision();
+    if config.in_hardware() {
+        iterations_in::<(f64, f64)>(config, progress)
+    } else {
+        iterations_in::<Complex>(config, progress)
+    }
+}
+
+/// Find a `take` that shows all the detail in the image, doubling the
+/// configured one while a significant fraction of the image stops at it
+/// without being found to be in the set. Only those pixels are iterated
+/// again after each doubling, and it stops once doubling lets few of
+/// them escape
+pub fn auto_take(config: &RenderConfig) -> usize {
+    let config = &config.at_hardware_precision();
+    if config.in_hardware() {
+        auto_take_in::<(f64, f64)>(config)
+    } else {
+        auto_take_in::<Complex>(config)
+    }
+}
+
+fn render_pixels<S>(
+    config: &RenderConfig,
+    state: Option<&mut RenderState>,
+    progress: &Progress,
+    convert: Convert<S>,
+    on_row: Option<&RowSink<'_, S>>,
+    on_results: Option<&ResultSink<'_>>,
+    on_coverage: Option<&RowSink<'_, f32>>,
+) -> ImageBuffer<Rgb<S>, Vec<S>>
+where
+    Rgb<S>: Pixel<Subpixel = S>,
+    S: Copy + Send + Sync,
+{
+    let config = &config.at_hardware_precision();
+    if config.in_hardware() {
+        render_in::<(f64, f64), S>(
+            config,
+            state,
+            progress,
+            convert,
+            on_row,
+            on_results,
+            on_coverage,
+        )
+    } else {
+        render_in::<Complex, S>(
+            config,
+            state,
+            progress,
+            convert,
+            on_row,
+            on_results,
+            on_coverage,
+        )
+    }
+}
+
+/// Whether points in the main cardioid and period-2 bulb can be skipped
+/// as inside the set without iterating them
+fn check_bulbs(config: &RenderConfig) -> bool {
+    // Orbit traps and interior coloring color the interior too, so it
+    // has to be iterated
+    config.fractal == Fractal::Mandelbrot
+        && !config.invert
+        && config.interior.is_none()
+        && config.newton.is_none()
+        && config.power == 2
+        && config.orbit_trap.is_none()
+        && config.julia.is_none()
+        && config.z0.is_zero()
+        && overlaps_main_bulbs(config)
+}
+
+/// Distance from an orbit's point to the set, estimated from |z| and
+/// |dz| when it escaped: de = |z| * ln|z| / |dz|
+fn distance_estimate(modulus: f64, derivative_modulus: &Float) -> Float {
+    let modulus = Float::with_val(53, modulus);
+    Float::with_val(53, modulus.ln_ref()) * &modulus / derivative_modulus
+}
+
+/// Escape count of an orbit that escaped, made continuous by how far
+/// past the bailout R the step it escaped on took it
+fn smooth_iterations(config: &RenderConfig, orbit: &OrbitEnd, past_bailout: u32) -> f64 {
+    // The step z escapes on takes |z| from up to R to up to R^power, so
+    // log_power(ln |z| / ln R) is how far through that step it went, from
+    // 0 to 1, whatever R is. Each step past the bailout adds 1 more
+    let past = (orbit.escape_modulus.ln() / config.bailout.ln()).log(config.escape_degree());
+    (orbit.iterations + 1) as f64 + past_bailout as f64 - past
+}
+
+/// Steps to iterate past escaping before measuring an orbit. Only the
+/// polynomial fractals' smooth coloring is worked out from the modulus,
+/// and stripe and TIA averages blend by it on the step they escaped on
+fn steps_past_bailout(config: &RenderConfig) -> u32 {
+    if config.smooth
+        && config.newton.is_none()
+        && !config.fractal.is_transcendental()
+        && Averaged::from_config(config).is_none()
+    {
+        config.continue_past_bailout
+    } else {
+        0
+    }
+}
+
+/// Orbits of many points at once iterated with SIMD, where nothing but
+/// their escape counts (and where they stopped) is needed from plain
+/// z^2 + c in hardware floats. None when the orbit tracks anything more,
+/// so points are iterated one at a time by `orbits`
+fn simd_orbits(
+    config: &RenderConfig,
+) -> Option<impl Fn(&[Complex]) -> Vec<Option<OrbitEnd>> + Sync + '_> {
+    let prec = config.precision;
+    if !config.simd
+        || !config.in_hardware()
+        || config.newton.is_some()
+        || config.fractal != Fractal::Mandelbrot
+        || config.power != 2
+        || config.distance_estimate
+        || config.shade
+        || config.detect_periodicity
+        || config.interior == Some(InteriorColoring::Period)
+        || config.orbit_trap.is_some()
+        || Averaged::from_config(config).is_some()
+    {
+        return None;
+    }
+
+    // Rounded to hardware floats the same way as by `orbits`
+    let julia = config.julia.as_ref().map(|c| {
+        let (real_prec, imag_prec) = c.prec();
+        <(f64, f64)>::from_complex(&Complex::with_val(prec.max(real_prec).max(imag_prec), c))
+    });
+    let z0 = <(f64, f64)>::from_complex(&Complex::with_val(prec, &config.z0));
+    let check_bulbs = check_bulbs(config);
+    let keep_modulus = config.invert || config.interior.is_some();
+    let past_bailout = steps_past_bailout(config);
+
+    Some(move |points: &[Complex]| {
+        let inside: Vec<bool> = points
+            .iter()
+            .map(|point| check_bulbs && in_main_bulbs(point))
+            .collect();
+        let orbits: Vec<_> = points
+            .iter()
+            .zip(&inside)
+            .filter(|(_, inside)| !**inside)
+            .map(|(point, _)| {
+                let point = <(f64, f64)>::from_complex(point);
+                match julia {
+                    Some(c) => (point, c),
+                    None => (z0, point),
+                }
+            })
+            .collect();
+
+        let mut ends = simd::iterate(&orbits, config.take, config.bailout)
+            .into_iter()
+            .zip(&orbits);
+        inside
+            .into_iter()
+            .map(|inside| {
+                if inside {
+                    return None;
+                }
+                let ((iterations, mut z), (_, c)) = ends.next()?;
+                let escaped = iterations < config.take;
+                if escaped {
+                    for _ in 0..past_bailout {
+                        Fractal::Mandelbrot.raise(&mut z, 2, c);
+                    }
+                }
+                Some(OrbitEnd {
+                    iterations,
+                    escape_modulus: if escaped || keep_modulus {
+                        z.0.hypot(z.1)
+                    } else {
+                        0_f64
+                    },
+                    escape_angle: if escaped { z.1.atan2(z.0) } else { 0_f64 },
+                    derivative_modulus: Float::new(53),
+                    normal_angle: 0_f64,
+                    period: None,
+                    trap_distance: None,
+                    average: None,
+                    root: None,
+                })
+            })
+            .collect()
+    })
+}
+
+/// How the orbit of each point ends, or None for points known to be in
+/// the set without iterating, shared by every kind of render. Given a
+/// state, the orbit is picked up from it and left there for next time
+fn orbits<'a, T: ComplexScalar + 'a>(
+    config: &'a RenderConfig,
+) -> impl Fn(&Complex, Option<&mut SampleState>) -> Option<OrbitEnd> + Sync + 'a {
+    let prec = config.precision;
+    let take = config.take;
+    let power = config.power;
+    let fractal = config.fractal;
+    let bailout = config.bailout;
+    let past_bailout = steps_past_bailout(config);
+
+    let julia = config.julia.as_ref().map(|c| {
+        let (real_prec, imag_prec) = c.prec();
+        T::from_complex(&Complex::with_val(prec.max(real_prec).max(imag_prec), c))
+    });
+    let trap_center = T::from_complex(&Complex::with_val(prec, &config.trap_position));
+    let z0 = T::from_complex(&Complex::with_val(prec, &config.z0));
+    let zero_seed = config.z0.is_zero();
+    let averaged = Averaged::from_config(config);
+
+    let pixel_size = Float::with_val(53, config.x_step.abs_ref());
+
+    let roots: Vec<T> = config
+        .newton
+        .map(|degree| {
+            newton::roots(degree, prec)
+                .iter()
+                .map(T::from_complex)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Pixel offsets from the reference have to fit in an f64
+    let reference = (config.perturbation
+        && config.newton.is_none()
+        && prec > f64::MANTISSA_DIGITS
+        && fractal == Fractal::Mandelbrot
+        && power == 2
+        && julia.is_none()
+        && zero_seed
+        && pixel_size > 1e-290_f64)
+        .then(|| {
+            let reference = match &config.reference {
+                Some(reference) if reference.serves(config) => ReferenceOrbit::clone(reference),
+                _ => reference_orbit(config),
+            }
+            .with_steps_past_bailout(past_bailout);
+            // Skipped iterations would never be measured against a trap or
+            // added to the average
+            if config.series_terms > 0 && config.orbit_trap.is_none() && averaged.is_none() {
+                reference.with_series(config.series_terms, &probe_points(config), bailout)
+            } else {
+                reference
+            }
+        });
+    let perturbed_trap = config
+        .orbit_trap
+        .map(|kind| (kind, <(f64, f64)>::from_complex(&config.trap_position)));
+
+    // Orbits at full precision, in `T`
+    let iterate = move |point: &Complex, state: Option<&mut SampleState>| {
+        let point = T::from_complex(point);
+        let (start, z) = match state.as_deref() {
+            Some(SampleState::Pending { iterations, z }) => (*iterations, Some(T::from_complex(z))),
+            _ => (0, None),
+        };
+        let orbit = match &julia {
+            Some(c) => julia_iter(z.unwrap_or(point), c.clone(), power, fractal, bailout),
+            None => square_iter(
+                z.unwrap_or_else(|| z0.clone()),
+                point,
+                power,
+                fractal,
+                bailout,
+            ),
+        };
+        let orbit = if config.distance_estimate || config.shade {
+            orbit.with_derivative()
+        } else {
+            orbit
+        };
+        let orbit = orbit.with_steps_past_bailout(past_bailout);
+        let orbit =
+            if config.detect_periodicity || config.interior == Some(InteriorColoring::Period) {
+                orbit.with_periodicity()
+            } else {
+                orbit
+            };
+        let orbit = match config.orbit_trap {
+            Some(kind) => orbit.with_trap(kind, &trap_center),
+            None => orbit,
+        };
+        let mut orbit = match averaged {
+            Some(averaged) => orbit.with_average(averaged),
+            None => orbit,
+        };
+        let iterations = start + orbit.by_ref().take(take.saturating_sub(start)).count();
+        let escaped = iterations < take && orbit.period.is_none();
+        if !escaped && (config.invert || config.interior.is_some()) {
+            orbit.escape_modulus = orbit.z.modulus().to_f64();
+        }
+
+        if let Some(state) = state {
+            *state = if escaped {
+                SampleState::Escaped {
+                    iterations,
+                    escape_modulus: orbit.escape_modulus,
+                    escape_angle: orbit.escape_angle,
+                }
+            } else if orbit.period.is_some() {
+                SampleState::Inside
+            } else {
+                SampleState::Pending {
+                    iterations,
+                    z: orbit.z.to_complex(),
+                }
+            };
+        }
+
+        OrbitEnd {
+            iterations,
+            escape_modulus: orbit.escape_modulus,
+            escape_angle: orbit.escape_angle,
+            derivative_modulus: orbit.derivative_modulus,
+            normal_angle: orbit.normal_angle,
+            period: orbit.period,
+            trap_distance: orbit.trap.map(|trap| trap.min_distance),
+            average: orbit
+                .average
+                .filter(|_| escaped)
+                .map(|average| average.averages()),
+            root: None,
+        }
+    };
+
+    let check_bulbs = check_bulbs(config);
+
+    move |point: &Complex, state: Option<&mut SampleState>| {
+        if let Some(degree) = config.newton {
+            let mut newton =
+                NewtonIter::new(T::from_complex(point), degree, config.root_method, &roots);
+            let iterations = newton.by_ref().take(take).count();
+            return Some(OrbitEnd {
+                iterations,
+                escape_modulus: newton.distance,
+                escape_angle: 0_f64,
+                derivative_modulus: Float::new(53),
+                normal_angle: 0_f64,
+                period: None,
+                trap_distance: None,
+                average: None,
+                root: newton.root,
+            });
+        }
+
+        match state.as_deref() {
+            Some(SampleState::Inside) => return None,
+            Some(SampleState::Escaped {
+                iterations,
+                escape_modulus,
+                escape_angle,
+            }) => {
+                return Some(OrbitEnd {
+                    iterations: *iterations,
+                    escape_modulus: *escape_modulus,
+                    escape_angle: *escape_angle,
+                    derivative_modulus: Float::new(53),
+                    normal_angle: 0_f64,
+                    period: None,
+                    trap_distance: None,
+                    average: None,
+                    root: None,
+                })
+            }
+            _ => {}
+        }
+
+        if check_bulbs && in_main_bulbs(point) {
+            if let Some(state) = state {
+                *state = SampleState::Inside;
+            }
+            return None;
+        }
+
+        // Only full precision orbits can be picked up again
+        if state.is_some() {
+            return Some(iterate(point, state));
+        }
+
+        // Glitched pixels fall back to full precision
+        let orbit = reference
+            .as_ref()
+            .and_then(|reference| {
+                reference.iterate(
+                    point,
+                    take,
+                    bailout,
+                    config.distance_estimate || config.shade,
+                    perturbed_trap,
+                    averaged,
+                )
+            })
+            .unwrap_or_else(|| iterate(point, None));
+        Some(orbit)
+    }
+}
+
+fn auto_take_in<T: ComplexScalar>(config: &RenderConfig) -> usize {
+    let prec = config.precision;
+    let (width, height) = config.resolution;
+    let threshold = (AUTO_TAKE_FRACTION * width as f64 * height as f64) as usize;
+
+    let point = |(x, y): (u32, u32)| {
+        let (x, y) = (config.origin.0 + x, config.origin.1 + y);
+        let x_val = &config.x_begin + Float::with_val(prec, x * &config.x_step);
+        let y_val = &config.y_begin + Float::with_val(prec, y * &config.y_step);
+        rotate(config, Complex::with_val(prec, (x_val, y_val)))
+    };
+
+    let mut take = config.take;
+    let mut pending: Vec<(u32, u32)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .collect();
+    for doubling in 0..=AUTO_TAKE_DOUBLINGS {
+        // Periodic orbits are in the set however long they're iterated
+        let search = RenderConfig {
+            take,
+            detect_periodicity: true,
+            ..config.clone()
+        };
+        let orbit = orbits::<T>(&search);
+        let before = pending.len();
+        pending = pending
+            .into_par_iter()
+            .filter(|&pixel| match orbit(&point(pixel), None) {
+                Some(orbit) => {
+                    orbit.iterations == take && orbit.period.is_none() && orbit.root.is_none()
+                }
+                None => false,
+            })
+            .collect();
+
+        if doubling == 0 {
+            if pending.len() <= threshold {
+                return take;
+            }
+        } else if before - pending.len() <= threshold {
+            // The last doubling found little, so the take before it was
+            // enough
+            return take / 2;
+        }
+        take *= 2;
+    }
+    take / 2
+}
+
+/// The result of a pixel from where its orbit ended, or None if it was
+/// found to be in the set without iterating
+fn pixel_result(config: &RenderConfig, orbit: &Option<OrbitEnd>) -> PixelResult {
+    match orbit {
+        Some(orbit) => {
+            let escaped = match config.newton {
+                Some(_) => orbit.root.is_some(),
+                None => orbit.iterations < config.take && orbit.period.is_none(),
+            };
+            PixelResult {
+                // Orbits found to repeat stop short of `take`, but are in
+                // the set all the same
+                iterations: if escaped {
+                    orbit.iterations as u32
+                } else {
+                    config.take as u32
+                },
+                final_modulus: if escaped { orbit.escape_modulus } else { 0_f64 },
+                final_angle: orbit.escape_angle,
+                escaped,
+            }
+        }
+        None => PixelResult {
+            iterations: config.take as u32,
+            final_modulus: 0_f64,
+            final_angle: 0_f64,
+            escaped: false,
+        },
+    }
+}
+
+/// The result of each pixel with orbits iterated in `T`
+fn iterations_in<T: ComplexScalar>(config: &RenderConfig, progress: &Progress) -> Vec<PixelResult> {
+    let prec = config.precision;
+    let orbit = orbits::<T>(config);
+
+    let rows: Vec<Vec<PixelResult>> = (0..config.resolution.1)
+        .into_par_iter()
+        .map(|y| {
+            let y_val =
+                &config.y_begin + Float::with_val(prec, (config.origin.1 + y) * &config.y_step);
+            let row = (0..config.resolution.0)
+                .map(|x| {
+                    let x = config.origin.0 + x;
+                    let x_val = &config.x_begin + Float::with_val(prec, x * &config.x_step);
+                    let point = rotate(config, Complex::with_val(prec, (x_val, &y_val)));
+                    pixel_result(config, &orbit(&point, None))
+                })
+                .collect();
+            progress
+                .pixels
+                .fetch_add(config.resolution.0 as u64, Ordering::Relaxed);
+            row
+        })
+        .collect();
+
+    rows.into_iter().flatten().collect()
+}
+
+/// Render the image with orbits iterated in `T`, and pixels converted
+/// to `S` by `convert`. Each row is colored straight into the image as
+/// it's iterated, keeping nothing of it past that, except that histogram
+/// coloring keeps the escape of every sample until all are counted, and
+/// adaptive anti-aliasing the color and count of every pixel to find the
+/// edges by
+fn render_in<T: ComplexScalar, S>(
+    config: &RenderConfig,
+    state: Option<&mut RenderState>,
+    progress: &Progress,
+    convert: Convert<S>,
+    on_row: Option<&RowSink<'_, S>>,
+    on_results: Option<&ResultSink<'_>>,
+    on_coverage: Option<&RowSink<'_, f32>>,
+) -> ImageBuffer<Rgb<S>, Vec<S>>
+where
+    Rgb<S>: Pixel<Subpixel = S>,
+    S: Copy + Send + Sync,
+{
+    // Masked pixels aren't iterated, so they have no results to give
+    let on_results = on_results.filter(|_| config.mask.is_none());
+    let prec = config.precision;
+    let take = config.take;
+
+    let pixel_size = Float::with_val(53, config.x_step.abs_ref());
+
+    let (light_y, light_x) = config.light_angle.to_radians().sin_cos();
+    let light = (light_x, light_y);
+
+    let (gradient_start, gradient_end) = config.gradient.domain();
+    let gradient_width = gradient_end - gradient_start;
+
+    let orbit = orbits::<T>(config);
+    let batch = simd_orbits(config);
+    let past_bailout = steps_past_bailout(config);
+
+    // How a single point escaped, from where its orbit ended, or None if
+    // it is in the set
+    let escape = |orbit: Option<OrbitEnd>| {
+        progress.samples.fetch_add(1, Ordering::Relaxed);
+        let orbit = match orbit {
+            Some(orbit) => orbit,
+            None => {
+                progress.inside.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        let i = orbit.iterations;
+
+        if config.newton.is_some() {
+            // Each root is given an even share of the gradient, darkening
+            // the slower a point converges
+            orbit.root?;
+            let iterations = if config.smooth {
+                // Convergence is quadratic with Newton's method, so the
+                // distance squares with each iteration, or cubes with
+                // Halley's
+                let order = config.root_method.order();
+                let fraction = (newton::TOLERANCE.ln() / orbit.escape_modulus.ln()).log(order);
+                i as f64 + fraction.max(-1_f64)
+            } else {
+                i as f64
+            };
+            return Some(Escape {
+                iterations,
+                escape_modulus: orbit.escape_modulus,
+                escape_angle: 0_f64,
+                trap_distance: None,
+                average: None,
+                root: orbit.root,
+                period: None,
+                escaped: true,
+                inside: false,
+                brightness: 0.5_f64.powf(iterations / NEWTON_FADE),
+            });
+        }
+
+        let escaped = i < take && orbit.period.is_none();
+        if !escaped {
+            progress.inside.fetch_add(1, Ordering::Relaxed);
+        }
+        if config.invert {
+            // The interior is colored by how close its orbit ended to
+            // the escape radius instead
+            return if escaped {
+                None
+            } else {
+                Some(Escape {
+                    iterations: i as f64,
+                    escape_modulus: orbit.escape_modulus,
+                    escape_angle: 0_f64,
+                    trap_distance: None,
+                    average: None,
+                    root: None,
+                    period: None,
+                    escaped: false,
+                    inside: false,
+                    brightness: 1_f64,
+                })
+            };
+        }
+        if let (false, Some(interior)) = (escaped, config.interior) {
+            return match (interior, orbit.period) {
+                (InteriorColoring::Period, None) => None,
+                _ => Some(Escape {
+                    iterations: i as f64,
+                    escape_modulus: orbit.escape_modulus,
+                    escape_angle: 0_f64,
+                    trap_distance: None,
+                    average: None,
+                    root: None,
+                    period: orbit.period,
+                    escaped: false,
+                    inside: true,
+                    brightness: 1_f64,
+                }),
+            };
+        }
+        if !escaped && orbit.trap_distance.is_none() {
+            return None;
+        }
+
+        let iterations = if config.smooth {
+            smooth_iterations(config, &orbit, past_bailout)
+        } else {
+            i as f64
+        };
+
+        let brightness = if config.distance_estimate && escaped {
+            // Measured in pixels
+            let de =
+                distance_estimate(orbit.escape_modulus, &orbit.derivative_modulus) / &pixel_size;
+            de.to_f64().tanh()
+        } else {
+            1_f64
+        };
+
+        let brightness = if config.shade && escaped {
+            // Lambertian lighting of the normal, tilted up towards a light
+            // `LIGHT_HEIGHT` above the plane
+            let (normal_y, normal_x) = orbit.normal_angle.sin_cos();
+            let lambert = normal_x * light.0 + normal_y * light.1;
+            brightness * ((lambert + LIGHT_HEIGHT) / (1_f64 + LIGHT_HEIGHT)).max(0_f64)
+        } else {
+            brightness
+        };
+
+        let brightness = if config.coloring == ColoringMode::Arg && config.smooth && escaped {
+            // Darkest at the end of each band, where |z| went furthest
+            // past the bailout
+            let band = iterations.rem_euclid(1_f64);
+            brightness * (ARG_DARKEST + (1_f64 - ARG_DARKEST) * band)
+        } else {
+            brightness
+        };
+
+        Some(Escape {
+            iterations,
+            escape_modulus: orbit.escape_modulus,
+            escape_angle: orbit.escape_angle,
+            trap_distance: orbit.trap_distance,
+            average: orbit.average,
+            root: None,
+            period: None,
+            escaped,
+            inside: false,
+            brightness,
+        })
+    };
+
+    let interior = interior_coloring(config);
+    let (interior_start, interior_end) = config.interior_gradient.domain();
+    let interior_width = interior_end - interior_start;
+
+    let background = {
+        let [red, green, blue] = config.background.map(|channel| channel as f64 / 255_f64);
+        Srgb::new(red, green, blue).into_linear()
+    };
+
+    // The color of a sample, or None when it is given the background
+    let color = |coloring: &dyn Coloring, escape: &Option<Escape>| {
+        let escape = escape.as_ref()?;
+        let (pos, brightness) = match (&interior, escape.inside) {
+            (Some(interior), true) => (interior.position(escape), 1_f64),
+            _ => (coloring.position(escape), escape.brightness),
+        };
+        // ln(ln |z|) and the distance estimate run off to NaN or infinity
+        // at the edges of where they're defined, ie. |z| right at the
+        // bailout, which would otherwise speckle the image
+        if !(pos.is_finite() && brightness.is_finite()) {
+            progress.non_finite.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(match (&interior, escape.inside) {
+            (Some(_), true) => config
+                .interior_gradient
+                .get(interior_start + pos * interior_width),
+            _ => config.gradient.get(gradient_start + pos * gradient_width) * brightness,
+        })
+    };
+    // A pixel of the colors of its samples and the share of them colored
+    let straight = on_coverage.is_some();
+    let blend = |colors: &mut dyn Iterator<Item = Option<LinSrgb<f64>>>, samples: usize| {
+        blend(colors, samples, background, straight)
+    };
+
+    let samples = config.supersample.max(1);
+    let sub_x_step = Float::with_val(prec, &config.x_step / samples);
+    let sub_y_step = Float::with_val(prec, &config.y_step / samples);
+
+    // The sample at (`sub_x`, `sub_y`) in the NxN grid across the pixel
+    let point_at = |x: u32, y: u32, sub_x: u32, sub_y: u32| {
+        let (x, y) = (config.origin.0 + x, config.origin.1 + y);
+        let x_offset = Float::with_val(prec, x * &config.x_step);
+        let y_offset = Float::with_val(prec, y * &config.y_step);
+        let x_val = &config.x_begin
+            + Float::with_val(prec, &x_offset + Float::with_val(prec, sub_x * &sub_x_step));
+        let y_val = &config.y_begin
+            + Float::with_val(prec, &y_offset + Float::with_val(prec, sub_y * &sub_y_step));
+        rotate(config, Complex::with_val(prec, (x_val, y_val)))
+    };
+
+    // Every sample of row `y`, pixel by pixel
+    let row_points = |y: u32| -> Vec<Complex> {
+        (0..config.resolution.0)
+            .flat_map(|x| {
+                (0..samples).flat_map(move |sub_x| {
+                    (0..samples).map(move |sub_y| point_at(x, y, sub_x, sub_y))
+                })
+            })
+            .collect()
+    };
+
+    let cancelled = || progress.cancelled.load(Ordering::Relaxed);
+
+    // How each of `points` escaped, with the state of each when
+    // continuing a render. The result of each is added to `results` if
+    // given, from the same orbit
+    let escapes_of = |points: &[Complex],
+                      states: Option<&mut [SampleState]>,
+                      mut results: Option<&mut Vec<PixelResult>>|
+     -> Vec<Option<Escape>> {
+        if cancelled() {
+            return Vec::new();
+        }
+        let mut escape = |orbit: Option<OrbitEnd>| {
+            if let Some(results) = results.as_mut() {
+                results.push(pixel_result(config, &orbit));
+            }
+            escape(orbit)
+        };
+        match (&batch, states) {
+            (Some(batch), None) => batch(points).into_iter().map(&mut escape).collect(),
+            (_, states) => {
+                let mut states = states.map(|states| states.iter_mut());
+                points
+                    .iter()
+                    .map(|point| {
+                        // The rest of a row is skipped once cancelled
+                        if cancelled() {
+                            return None;
+                        }
+                        escape(orbit(
+                            point,
+                            states.as_mut().and_then(|states| states.next()),
+                        ))
+                    })
+                    .collect()
+            }
+        }
+    };
+
+    // Whether the pixel at (`x`, `y`) is left out by the mask
+    let masked = |x: u32, y: u32| match &config.mask {
+        Some(mask) => mask.get_pixel(config.origin.0 + x, config.origin.1 + y).0[0] < 128,
+        None => false,
+    };
+
+    // How each sample of row `y` escaped, iterating only those of the
+    // pixels the mask keeps. The result of each pixel's first sample is
+    // handed to `on_results`
+    let row_escapes = |y: u32, states: Option<&mut [SampleState]>| -> Vec<Option<Escape>> {
+        let points = row_points(y);
+        let kept: Vec<bool> = (0..config.resolution.0).map(|x| !masked(x, y)).collect();
+        let per_pixel = (samples * samples) as usize;
+        if kept.iter().all(|&kept| kept) {
+            let mut results = Vec::new();
+            let escapes = escapes_of(&points, states, on_results.map(|_| &mut results));
+            if let (Some(on_results), false) = (on_results, cancelled()) {
+                let results: Vec<PixelResult> = results.into_iter().step_by(per_pixel).collect();
+                on_results(y, &results);
+            }
+            return escapes;
+        }
+        match states {
+            Some(states) => points
+                .iter()
+                .zip(states)
+                .enumerate()
+                .map(|(i, (point, state))| {
+                    // Samples already known cost nothing to color again
+                    let known = matches!(state, SampleState::Inside | SampleState::Escaped { .. });
+                    if kept[i / per_pixel] || known {
+                        escape(orbit(point, Some(state)))
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            None => {
+                let rendered: Vec<Complex> = points
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| kept[i / per_pixel])
+                    .map(|(_, point)| point.clone())
+                    .collect();
+                let mut escapes = escapes_of(&rendered, None, None).into_iter();
+                (0..points.len())
+                    .map(|i| {
+                        if kept[i / per_pixel] {
+                            escapes.next().flatten()
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            }
+        }
+    };
+
+    let adaptive = config.anti_aliasing != AntiAliasing::Grid
+        && config.coloring != ColoringMode::Histogram
+        && state.is_none();
+
+    // Rows below the real axis that mirror a row above it are copied from
+    // that row instead of rendered. Saved states are only kept for the
+    // rows that are rendered, so they're always rendered in full, and
+    // adaptive anti-aliasing compares pixels with the rows around them.
+    // A mask needn't be the same either side of the axis, and every row
+    // is iterated for its results or coverage
+    let axis = match state {
+        Some(_) => None,
+        None if adaptive || config.mask.is_some() || on_results.is_some() => None,
+        None if on_coverage.is_some() => None,
+        None => mirror_axis(config),
+    };
+    let mirrored_from = |y: usize| {
+        let source = axis? - y as i64;
+        if source >= 0 && source < y as i64 {
+            Some(source as usize)
+        } else {
+            None
+        }
+    };
+
+    let mut img: ImageBuffer<Rgb<S>, Vec<S>> =
+        ImageBuffer::new(config.resolution.0, config.resolution.1);
+    let row_len = config.resolution.0 as usize * 3;
+    let per_pixel = (samples * samples) as usize;
+
+    let row_samples = config.resolution.0 as usize * per_pixel;
+    let state_rows: Vec<Option<&mut [SampleState]>> = match state {
+        Some(state) => state.samples.chunks_mut(row_samples).map(Some).collect(),
+        None => (0..config.resolution.1).map(|_| None).collect(),
+    };
+
+    // Samples are averaged in linear light, with samples inside the set
+    // counting as the background
+    if config.coloring == ColoringMode::Histogram {
+        // Every escape count has to be known before any pixel can be
+        // colored, so the first pass only keeps the escapes
+        let mut escapes: Vec<Vec<Option<Escape>>> = state_rows
+            .into_par_iter()
+            .enumerate()
+            .map(|(y, states)| {
+                if mirrored_from(y).is_some() {
+                    return Vec::new();
+                }
+                let row = row_escapes(y as u32, states);
+                if cancelled() {
+                    return Vec::new();
+                }
+                progress
+                    .pixels
+                    .fetch_add(config.resolution.0 as u64, Ordering::Relaxed);
+                row
+            })
+            .collect();
+        for y in 0..escapes.len() {
+            if let Some(source) = mirrored_from(y) {
+                // Each pixel's samples are in a different order in the
+                // mirror, but counting and averaging them doesn't care
+                escapes[y] = escapes[source].clone();
+                progress
+                    .pixels
+                    .fetch_add(config.resolution.0 as u64, Ordering::Relaxed);
+            }
+        }
+
+        let histogram = Histogram::new(
+            escapes
+                .iter()
+                .flatten()
+                .flatten()
+                .filter(|escape| !escape.inside),
+            take,
+        );
+        let coloring = coloring(config, Some(histogram));
+
+        img.par_chunks_mut(row_len)
+            .zip(escapes.par_iter())
+            .enumerate()
+            .for_each(|(y, (row, escapes))| {
+                let mut coverage = Vec::new();
+                let pixels = row.chunks_exact_mut(3).zip(escapes.chunks(per_pixel));
+                for (x, (rgb, escapes)) in pixels.enumerate() {
+                    let mut colors = escapes.iter().map(|escape| color(&*coloring, escape));
+                    let (blended, colored) = blend(&mut colors, per_pixel);
+                    if on_coverage.is_some() {
+                        coverage.push(colored);
+                    }
+                    rgb.copy_from_slice(&convert(config, blended, (x as u32, y as u32)).0);
+                }
+                if let Some(on_row) = on_row {
+                    on_row(y as u32, row);
+                }
+                if let Some(on_coverage) = on_coverage {
+                    on_coverage(y as u32, &coverage);
+                }
+            });
+    } else if adaptive {
+        let coloring = coloring(config, None);
+        let (width, height) = config.resolution;
+
+        // A sample per pixel first, from the corner its grid starts at,
+        // with its escape count and whether it escaped
+        let (colors, counts): (Vec<Vec<_>>, Vec<Vec<_>>) = (0..height)
+            .into_par_iter()
+            .map(|y| {
+                let points: Vec<Complex> = (0..width).map(|x| point_at(x, y, 0, 0)).collect();
+                let mut results = Vec::new();
+                let escapes = escapes_of(&points, None, on_results.map(|_| &mut results));
+                if cancelled() {
+                    return (
+                        vec![None; width as usize],
+                        vec![(take as f64, false); width as usize],
+                    );
+                }
+                if let Some(on_results) = on_results {
+                    on_results(y, &results);
+                }
+                escapes
+                    .into_iter()
+                    .map(|escape| {
+                        let count = match &escape {
+                            Some(escape) => (escape.iterations, escape.escaped),
+                            None => (take as f64, false),
+                        };
+                        (color(&*coloring, &escape), count)
+                    })
+                    .unzip()
+            })
+            .unzip();
+        let labs: Vec<Vec<Lab<D65, f64>>> = match config.anti_aliasing {
+            AntiAliasing::Perceptual => colors
+                .par_iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&color| Lab::from_color(color.unwrap_or(background)))
+                        .collect()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let boundary = match config.anti_aliasing {
+            AntiAliasing::Edges => boundary_mask(&counts, config.edge_dilation as usize),
+            _ => Vec::new(),
+        };
+        let edge = |x: usize, y: usize| match config.anti_aliasing {
+            AntiAliasing::Perceptual => {
+                neighborhood_delta_e(&labs, x, y) > config.delta_e_threshold
+            }
+            AntiAliasing::Edges => boundary[y][x],
+            _ => neighborhood_variance(&counts, x, y) > config.variance_threshold,
+        };
+
+        img.par_chunks_mut(row_len)
+            .enumerate()
+            .for_each(|(y, row)| {
+                if cancelled() {
+                    return;
+                }
+                let mut coverage = Vec::new();
+                for (x, rgb) in row.chunks_exact_mut(3).enumerate() {
+                    let first = iter::once(colors[y][x]);
+                    let (blended, colored) = if edge(x, y) {
+                        let (x, y) = (x as u32, y as u32);
+                        let points: Vec<Complex> = (1..samples * samples)
+                            .map(|sub| point_at(x, y, sub / samples, sub % samples))
+                            .collect();
+                        let rest = escapes_of(&points, None, None);
+                        let rest = rest.iter().map(|escape| color(&*coloring, escape));
+                        blend(&mut first.chain(rest), per_pixel)
+                    } else {
+                        blend(&mut first.into_iter(), 1)
+                    };
+                    if on_coverage.is_some() {
+                        coverage.push(colored);
+                    }
+                    rgb.copy_from_slice(&convert(config, blended, (x as u32, y as u32)).0);
+                }
+                if let Some(on_row) = on_row {
+                    on_row(y as u32, row);
+                }
+                if let Some(on_coverage) = on_coverage {
+                    on_coverage(y as u32, &coverage);
+                }
+                progress.pixels.fetch_add(width as u64, Ordering::Relaxed);
+            });
+    } else {
+        let coloring = coloring(config, None);
+
+        let mut rows: Vec<_> = img
+            .chunks_mut(row_len)
+            .zip(state_rows)
+            .enumerate()
+            .map(Some)
+            .collect();
+        let rows: Vec<_> = row_order(config.fill_order, config.resolution.1)
+            .into_iter()
+            .filter_map(|y| rows[y].take())
+            .collect();
+        rows.into_par_iter().for_each(|(y, (row, states))| {
+            if mirrored_from(y).is_some() {
+                return;
+            }
+            let escapes = row_escapes(y as u32, states);
+            if cancelled() {
+                return;
+            }
+            let mut coverage = Vec::new();
+            let pixels = row.chunks_exact_mut(3).zip(escapes.chunks(per_pixel));
+            for (x, (rgb, escapes)) in pixels.enumerate() {
+                let mut colors = escapes.iter().map(|escape| color(&*coloring, escape));
+                let (blended, colored) = blend(&mut colors, per_pixel);
+                if on_coverage.is_some() {
+                    coverage.push(colored);
+                }
+                rgb.copy_from_slice(&convert(config, blended, (x as u32, y as u32)).0);
+            }
+            if let Some(on_row) = on_row {
+                on_row(y as u32, row);
+            }
+            if let Some(on_coverage) = on_coverage {
+                on_coverage(y as u32, &coverage);
+            }
+            progress
+                .pixels
+                .fetch_add(config.resolution.0 as u64, Ordering::Relaxed);
+        });
+        for y in 0..config.resolution.1 as usize {
+            if let Some(source) = mirrored_from(y) {
+                img.copy_within(source * row_len..(source + 1) * row_len, y * row_len);
+                if let Some(on_row) = on_row {
+                    on_row(y as u32, &img.as_raw()[y * row_len..(y + 1) * row_len]);
+                }
+                progress
+                    .pixels
+                    .fetch_add(config.resolution.0 as u64, Ordering::Relaxed);
+            }
+        }
+    }
+
+    img
+}
+
+/// Every row of an image `height` high, in the order they're rendered in
+fn row_order(order: FillOrder, height: u32) -> Vec<usize> {
+    let height = height as usize;
+    match order {
+        FillOrder::Rows => (0..height).collect(),
+        FillOrder::Golden => {
+            // Steps of the height over the golden ratio, coprime with it
+            // so every row comes up once, spread evenly from the start
+            let gcd = |mut a: usize, mut b: usize| {
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                a
+            };
+            let mut step = ((height as f64 / GOLDEN_RATIO).round() as usize).max(1);
+            while gcd(step, height) > 1 {
+                step += 1;
+            }
+            (0..height)
+                .map(|n| (height / 2 + n * step) % height)
+                .collect()
+        }
+    }
+}
+
+/// A pixel of `samples` samples of `colors`, None for those given the
+/// `background`, and the share of them colored. `straight` leaves the
+/// background out, for it to show through the pixel's transparency
+fn blend(
+    colors: &mut dyn Iterator<Item = Option<LinSrgb<f64>>>,
+    samples: usize,
+    background: LinSrgb<f64>,
+    straight: bool,
+) -> (LinSrgb<f64>, f32) {
+    let black = LinSrgb::new(0_f64, 0_f64, 0_f64);
+    let (mut total, mut colored_total, mut colored) = (black, black, 0);
+    for color in colors {
+        match color {
+            Some(color) => {
+                total += color;
+                colored_total += color;
+                colored += 1;
+            }
+            None => total += background,
+        }
+    }
+    let coverage = colored as f32 / samples as f32;
+    match (straight, colored) {
+        (false, _) => (total / samples as f64, coverage),
+        (true, 0) => (background, 0_f32),
+        (true, colored) => (colored_total / colored as f64, coverage),
+    }
+}
+
+/// Largest CIEDE2000 difference between the color of the pixel at
+/// (`x`, `y`) and those of the pixels around it
+fn neighborhood_delta_e(labs: &[Vec<Lab<D65, f64>>], x: usize, y: usize) -> f64 {
+    let center = labs[y][x];
+    labs[y.saturating_sub(1)..(y + 2).min(labs.len())]
+        .iter()
+        .flat_map(|row| &row[x.saturating_sub(1)..(x + 2).min(row.len())])
+        .map(|lab| center.get_color_difference(lab))
+        .fold(0_f64, f64::max)
+}
+
+/// Which pixels have one next to them on the other side of the boundary
+/// of the set, from whether each of `counts` escaped, spread out by
+/// `dilation` pixels in every direction
+fn boundary_mask(counts: &[Vec<(f64, bool)>], dilation: usize) -> Vec<Vec<bool>> {
+    let height = counts.len();
+    let mask: Vec<Vec<bool>> = (0..height)
+        .into_par_iter()
+        .map(|y| {
+            let rows = &counts[y.saturating_sub(1)..(y + 2).min(height)];
+            (0..counts[y].len())
+                .map(|x| {
+                    let escaped = counts[y][x].1;
+                    rows.iter()
+                        .flat_map(|row| &row[x.saturating_sub(1)..(x + 2).min(row.len())])
+                        .any(|&(_, other)| other != escaped)
+                })
+                .collect()
+        })
+        .collect();
+    if dilation == 0 {
+        return mask;
+    }
+
+    // Along the rows, then down the columns of that
+    let across: Vec<Vec<bool>> = mask
+        .par_iter()
+        .map(|row| {
+            (0..row.len())
+                .map(|x| {
+                    row[x.saturating_sub(dilation)..(x + dilation + 1).min(row.len())]
+                        .iter()
+                        .any(|&set| set)
+                })
+                .collect()
+        })
+        .collect();
+    (0..height)
+        .into_par_iter()
+        .map(|y| {
+            let rows = &across[y.saturating_sub(dilation)..(y + dilation + 1).min(height)];
+            (0..across[y].len())
+                .map(|x| rows.iter().any(|row| row[x]))
+                .collect()
+        })
+        .collect()
+}
+
+/// Variance of the escape counts of the pixel at (`x`, `y`) and the
+/// pixels around it
+fn neighborhood_variance(counts: &[Vec<(f64, bool)>], x: usize, y: usize) -> f64 {
+    let rows = &counts[y.saturating_sub(1)..(y + 2).min(counts.len())];
+    let around: Vec<f64> = rows
+        .iter()
+        .flat_map(|row| &row[x.saturating_sub(1)..(x + 2).min(row.len())])
+        .map(|&(count, _)| count)
+        .collect();
+    let mean = around.iter().sum::<f64>() / around.len() as f64;
+    around
+        .iter()
+        .map(|count| (count - mean).powi(2))
+        .sum::<f64>()
+        / around.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `resolution` pixels of square pixels `width` across, centered on
+    /// `center`, at `precision` bits
+    fn view(
+        center: (f64, f64),
+        width: f64,
+        resolution: (u32, u32),
+        precision: u32,
+    ) -> RenderConfig {
+        let step = Float::with_val(precision, width) / resolution.0;
+        let half = |pixels: u32| Float::with_val(precision, &step * pixels) / 2;
+        RenderConfig {
+            x_begin: Float::with_val(precision, center.0) - half(resolution.0),
+            y_begin: Float::with_val(precision, center.1) - half(resolution.1),
+            x_step: step.clone(),
+            y_step: step,
+            ..benchmark_config(resolution, precision)
+        }
+    }
+
+    fn result_at(config: &RenderConfig, (x, y): (u32, u32)) -> PixelResult {
+        render_iterations(config)[(y * config.resolution.0 + x) as usize]
+    }
+
+    fn counts(config: &RenderConfig) -> Vec<(u32, bool)> {
+        render_iterations(config)
+            .iter()
+            .map(|result| (result.iterations, result.escaped))
+            .collect()
+    }
+
+    #[test]
+    fn colors_out_of_range_clamp() {
+        let config = benchmark_config((1, 1), 53);
+        let color = LinSrgb::new(2_f64, -1_f64, f64::NAN);
+        assert_eq!(to_rgb(&config, color, (0, 0)), Rgb([255, 0, 0]));
+        assert_eq!(to_rgb16(&config, color, (0, 0)), Rgb([65535, 0, 0]));
+        let dithered = RenderConfig {
+            dither: true,
+            ..config
+        };
+        assert_eq!(to_rgb(&dithered, color, (0, 0)), Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn supersampling_blends_edges() {
+        // White outside the set and black in it, so one sample a pixel
+        // gives nothing in between
+        let white = LinSrgb::new(1_f64, 1_f64, 1_f64);
+        let config = RenderConfig {
+            gradient: ColorGradient::new(vec![(0_f64, white), (1_f64, white)], ColorSpace::Linsrgb),
+            ..view((-0.75, 0.1), 0.5, (16, 16), 53)
+        };
+        let supersampled = RenderConfig {
+            supersample: 2,
+            ..config.clone()
+        };
+        let gray = |pixel: &Rgb<u8>| pixel.0 != [0, 0, 0] && pixel.0 != [255, 255, 255];
+        assert!(!render(&config).pixels().any(gray));
+        assert!(render(&supersampled).pixels().any(gray));
+    }
+
+    #[test]
+    fn multibrot_center_is_in_the_set() {
+        let config = RenderConfig {
+            power: 3,
+            ..view((0_f64, 0_f64), 3_f64, (32, 32), 53)
+        };
+        // The pixel whose corner is at 0
+        assert_eq!(render(&config).get_pixel(16, 16), &Rgb(config.background));
+        assert!(!result_at(&config, (16, 16)).escaped);
+        assert!(result_at(&config, (0, 0)).escaped);
+    }
+
+    #[test]
+    fn distance_estimate_vanishes_at_the_boundary() {
+        let estimate = |c: (f64, f64)| {
+            let mut orbit =
+                square_iter((0_f64, 0_f64), c, 2, Fractal::Mandelbrot, 1e10).with_derivative();
+            assert!(orbit.by_ref().take(100_000).count() < 100_000);
+            distance_estimate(orbit.escape_modulus, &orbit.derivative_modulus).to_f64()
+        };
+        // Just past the tip of the set at -2, and well away from it
+        assert!(estimate((-2.000_001, 0_f64)) < 1e-5);
+        assert!(estimate((1_f64, 1_f64)) > 0.1);
+    }
+
+    #[test]
+    fn periodicity_stops_interior_orbits_early() {
+        fn steps<T: ComplexScalar>(c: T, periodicity: bool) -> (usize, Option<u32>) {
+            let orbit = square_iter(c.zero_like(), c, 2, Fractal::Mandelbrot, 2_f64);
+            let mut orbit = if periodicity {
+                orbit.with_periodicity()
+            } else {
+                orbit
+            };
+            (orbit.by_ref().take(10_000).count(), orbit.period)
+        }
+        // -0.1 is drawn in to a fixed point of z^2 + c
+        let (hardware, period) = steps((-0.1_f64, 0_f64), true);
+        assert!(hardware < 1000);
+        assert_eq!(period, Some(1));
+        let (precise, period) = steps(Complex::with_val(128, (-0.1_f64, 0)), true);
+        assert!(precise < 1000);
+        assert_eq!(period, Some(1));
+        assert_eq!(steps((-0.1_f64, 0_f64), false), (10_000, None));
+    }
+
+    #[test]
+    fn tricorn_has_three_corners() {
+        let steps = |(re, im): (f64, f64)| {
+            square_iter((0_f64, 0_f64), (re, im), 2, Fractal::Tricorn, 2_f64)
+                .take(50)
+                .count()
+        };
+        let (sin, cos) = (2_f64 * std::f64::consts::PI / 3_f64).sin_cos();
+        let turn = |(re, im): (f64, f64)| (re * cos - im * sin, re * sin + im * cos);
+        let mut escaped = 0;
+        for y in -12..12 {
+            for x in -12..12 {
+                let point = (x as f64 / 6_f64 + 0.01, y as f64 / 6_f64 + 0.02);
+                let count = steps(point);
+                escaped += (count < 50) as u32;
+                assert_eq!(steps((point.0, -point.1)), count, "at {:?}", point);
+                assert_eq!(steps(turn(point)), count, "at {:?}", point);
+                assert_eq!(steps(turn(turn(point))), count, "at {:?}", point);
+            }
+        }
+        assert!(escaped > 0 && escaped < 24 * 24);
+    }
+
+    #[test]
+    fn quarter_turn_turns_the_image() {
+        let n = 16;
+        let config = RenderConfig {
+            take: 100,
+            ..view((-0.5, 0.3), 2.5, (n, n), 53)
+        };
+        // About the corner of the middle pixel, which takes the corner of
+        // each pixel onto that of another
+        let turned = RenderConfig {
+            rotation: Some((point_of(&config, (8_f64, 8_f64)), 90_f64)),
+            ..config.clone()
+        };
+        let (plain, turned) = (counts(&config), counts(&turned));
+        let n = n as usize;
+        // Each pixel is sampled at its corner, which the turn takes to the
+        // far corner of the pixel its square lands on, so the transposed
+        // and flipped image is a pixel over and the first row and column
+        // come from outside it
+        for y in 1..n {
+            for x in 1..n {
+                assert_eq!(turned[y * n + x], plain[x * n + n - y], "at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn hardware_floats_count_as_forced_bignum() {
+        // Up to the most bits hardware floats are used for, where the
+        // two are closest to disagreeing
+        for precision in [21, f64::MANTISSA_DIGITS] {
+            let config = RenderConfig {
+                take: 2000,
+                ..view((-0.75, 0.1), 0.125, (48, 36), precision)
+            };
+            let forced = RenderConfig {
+                force_bignum: true,
+                ..config.clone()
+            };
+            assert!(config.in_hardware() && !forced.in_hardware());
+            assert_eq!(counts(&config), counts(&forced));
+        }
+    }
+
+    #[test]
+    fn hardware_floats_render_as_forced_bignum() {
+        let config = view((-0.75, 0_f64), 3.5, (64, 48), 53);
+        let forced = RenderConfig {
+            force_bignum: true,
+            ..config.clone()
+        };
+        assert!(render(&config) == render(&forced));
+    }
+
+    #[test]
+    fn mirrored_rows_match_rendered_ones() {
+        // Two samples a pixel only line up with the axis when it is a
+        // quarter of a pixel off center
+        for (supersample, center) in [(1, 0_f64), (2, 3_f64 / 48_f64 / 4_f64)] {
+            let config = RenderConfig {
+                supersample,
+                take: 200,
+                ..view((-0.5, center), 3_f64, (48, 36), 53)
+            };
+            assert!(mirror_axis(&config).is_some());
+            // Rows a tile each, which mostly have nothing to mirror
+            let mut stitched = ImageBuffer::new(48, 36);
+            for y in 0..36 {
+                let row = render(&config.tile(0, y, 48, 1));
+                for x in 0..48 {
+                    stitched.put_pixel(x, y, *row.get_pixel(x, 0));
+                }
+            }
+            assert!(render(&config) == stitched, "at {} samples", supersample);
+        }
+    }
+
+    /// Smooth escape counts of the points along `start` to `end`, most of
+    /// which escape within a few steps of each other
+    fn smooth_along(
+        config: &RenderConfig,
+        start: (f64, f64),
+        end: (f64, f64),
+    ) -> Vec<(usize, f64)> {
+        let orbit = orbits::<(f64, f64)>(config);
+        let past_bailout = steps_past_bailout(config);
+        (0..=1000)
+            .map(|step| {
+                let t = step as f64 / 1000_f64;
+                let point = (
+                    start.0 + (end.0 - start.0) * t,
+                    start.1 + (end.1 - start.1) * t,
+                );
+                let orbit = orbit(&Complex::with_val(53, point), None).unwrap();
+                assert!(orbit.iterations < config.take);
+                (
+                    orbit.iterations,
+                    smooth_iterations(config, &orbit, past_bailout),
+                )
+            })
+            .collect()
+    }
+
+    /// Counts jump along `along` but each smooth count is close to the
+    /// next, and no more than one past the count it smooths
+    fn assert_continuous(along: &[(usize, f64)]) {
+        for &(count, smooth) in along {
+            assert!(
+                smooth > count as f64 && smooth <= (count + 1) as f64,
+                "{} at {}",
+                smooth,
+                count
+            );
+        }
+        let mut jumps = 0;
+        for pair in along.windows(2) {
+            let ((count, smooth), (next_count, next_smooth)) = (pair[0], pair[1]);
+            jumps += (count != next_count) as u32;
+            assert!(
+                (smooth - next_smooth).abs() < 0.05,
+                "{:?} to {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+        assert!(jumps > 2);
+    }
+
+    #[test]
+    fn smooth_counts_are_continuous_at_any_bailout() {
+        let config = RenderConfig {
+            smooth: true,
+            bailout: 128_f64,
+            ..benchmark_config((1, 1), 53)
+        };
+        assert_continuous(&smooth_along(&config, (0.3, 0_f64), (0.6, 0_f64)));
+    }
+
+    #[test]
+    fn smooth_counts_are_continuous_at_any_power() {
+        let config = RenderConfig {
+            smooth: true,
+            power: 3,
+            // Large enough that c barely takes z past R^3 on the step it
+            // escapes on
+            bailout: 256_f64,
+            ..benchmark_config((1, 1), 53)
+        };
+        assert_continuous(&smooth_along(&config, (0.45, 0_f64), (0.8, 0_f64)));
+    }
+
+    #[test]
+    fn threads_render_the_same_bytes() {
+        let in_threads = |threads: usize, config: &RenderConfig| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| render(config))
+        };
+        // Supersampling sums samples, and histogram coloring counts every
+        // pixel before coloring any
+        for coloring in [ColoringMode::EscapeCount, ColoringMode::Histogram] {
+            let config = RenderConfig {
+                supersample: 3,
+                smooth: true,
+                coloring,
+                ..view((-0.75, 0.1), 0.125, (48, 36), 53)
+            };
+            assert!(
+                in_threads(1, &config) == in_threads(4, &config),
+                "{:?}",
+                coloring
+            );
+        }
+    }
+
+    #[test]
+    fn folded_references_count_as_forced_bignum() {
+        // Around a Misiurewicz point on the axis, past hardware floats, a
+        // third of a pixel off it for the folded reference to be moved
+        // onto it
+        let plain = RenderConfig {
+            take: 2000,
+            perturbation: true,
+            ..view(
+                (-1.543_689_012_692_076, 1e-12 / 48_f64 / 3_f64),
+                1e-12,
+                (48, 36),
+                80,
+            )
+        };
+        let forced = RenderConfig {
+            perturbation: false,
+            force_bignum: true,
+            ..plain.clone()
+        };
+        let expected = counts(&forced);
+        for real_axis_fold in [false, true] {
+            let config = RenderConfig {
+                real_axis_fold,
+                ..plain.clone()
+            };
+            assert_eq!(reference_center(&config).imag().is_zero(), real_axis_fold);
+            assert_eq!(counts(&config), expected, "folded: {}", real_axis_fold);
+        }
+    }
+
+    #[test]
+    fn repeating_orbits_count_as_take() {
+        // Inside the period 3 bulb, where every orbit is found to repeat
+        let config = RenderConfig {
+            take: 3000,
+            detect_periodicity: true,
+            ..view((-0.12, 0.74), 0.05, (16, 12), 53)
+        };
+        assert_eq!(counts(&config), vec![(3000, false); 16 * 12]);
+    }
+
+    #[test]
+    fn periodicity_keeps_what_is_in_the_set() {
+        // Mostly the period 3 bulb, with the filaments around it, in
+        // hardware floats and past them
+        for precision in [53, 80] {
+            let config = RenderConfig {
+                take: 2000,
+                ..view((-0.12, 0.74), 0.25, (32, 24), precision)
+            };
+            let detected = RenderConfig {
+                detect_periodicity: true,
+                ..config.clone()
+            };
+            let iterated = counts(&config);
+            let inside = iterated.iter().filter(|&&(_, escaped)| !escaped).count();
+            assert!(inside > iterated.len() / 2);
+            assert_eq!(iterated, counts(&detected));
+        }
+    }
+}
@@ -1,14 +1,215 @@
 #![feature(int_log)]
 
-use clap::Parser;
-use image::{ImageBuffer, Rgb};
+mod compare;
+mod config;
+mod distributed;
+#[cfg(feature = "interactive")]
+mod interactive;
+mod iterations;
+mod kaleidoscope;
+mod metadata;
+mod plot;
+mod profile;
+mod sharpen;
+mod strips;
+mod tiles;
+
+use clap::{ArgEnum, CommandFactory, ErrorKind, Parser};
+use compare::Difference;
+use config::{early_value, ConfigFile};
+use image::{imageops, DynamicImage, GrayImage, ImageFormat, ImageOutputFormat, Rgb};
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use iterations::write_iterations;
 use itertools::Itertools;
-use palette::{Gradient, LinSrgb};
-use rayon::prelude::*;
-use rug::{complex::ParseComplexError, float::ParseFloatError, Complex, Float};
+use kaleidoscope::MAX_SEGMENTS;
+use mandelbrot::{
+    auto_take, benchmark_config, builtin_gradient, interior_gradient, load_palette, orbit,
+    palette_from_image, pixel_of, quantize, reference_orbit, reference_steps, render,
+    render_16_with_progress, render_16_with_state, render_hdr_with_progress, render_hdr_with_state,
+    render_iterations, render_oversized, render_oversized_16, render_oversized_hdr,
+    render_rgba_with_progress, render_swatch, render_with_progress, render_with_results,
+    render_with_state, reverse_stops, rotate_stops, stops_json, AntiAliasing, ColorGradient,
+    ColorSpace, ColoringMode, Easing, FillOrder, Fractal, GradientMode, InteriorColoring,
+    OrbitTrap, PixelResult, Progress, Quantizer, ReferenceOrbit, RenderConfig, RenderState,
+    ResultSink, RootMethod, Stops, BENCHMARK_RESOLUTION,
+};
+use metadata::{
+    apng_writer, read_png_text, write_indexed_png, write_png, write_png16, write_png_rgba,
+};
+use palette::Srgb;
+use plot::Corner;
+use profile::Profile;
+use rayon::ThreadPoolBuilder;
+use rug::{Complex, Float, Integer};
+use sharpen::sharpen;
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Cursor, Seek, Write},
+    iter, mem,
+    net::TcpListener,
+    num::{NonZeroU32, NonZeroUsize},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use strips::render_strips;
+use tiles::{checkpoint_dir, render_tiled, Renderers, TileOrder, Tiling};
+
+/// Options whose orbits can't be picked up from a saved state
+const STATE_CONFLICTS: &[&str] = &[
+    "zoom-frames",
+    "tile-size",
+    "perturbation",
+    "distance-estimate",
+    "shade",
+    "orbit-trap",
+    "stripe-density",
+    "tia",
+    "newton",
+];
+
+/// `output` that writes the image to stdout
+const STDOUT: &str = "-";
+
+/// Settings recorded in a PNG's metadata that `bounds_from` reuses
+const BOUNDS_KEYS: &[&str] = &[
+    "domain",
+    "range",
+    "line",
+    "centered-around",
+    "zoom",
+    "rotate",
+    "flip-x",
+    "flip-y",
+    "transpose",
+    "extra-precision",
+    "resolution",
+    "take",
+];
+
+/// Escape radius `auto_bailout` gives smooth coloring and distance
+/// estimates
+const SMOOTH_BAILOUT: f64 = 1e10;
+
+/// Escape radius `auto_bailout` gives stripe and TIA coloring
+const AVERAGE_BAILOUT: f64 = 1e3;
+
+/// Bits kept beyond the decimal digits of a number on the command line
+const PARSE_GUARD_BITS: u32 = 4;
+
+/// Bits kept beyond the domain and range for rounding in pixel positions
+const DOMAIN_GUARD_BITS: u32 = 4;
+
+/// Bits kept beyond the zoom for rounding in pixel positions
+const ZOOM_GUARD_BITS: u32 = 3;
+
+/// `take` of an image that isn't zoomed in, when none is given
+const DEFAULT_TAKE: usize = 500;
+
+/// Complex numbers an orbit keeps at once, for `dry_run`'s estimate of
+/// the memory a render takes
+const ORBIT_NUMBERS: u64 = 6;
+
+/// Largest `oversize` factor
+const MAX_OVERSIZE: u32 = 8;
+
+/// Most pixels the larger image rendered for `oversize` may have
+const MAX_OVERSIZE_PIXELS: u64 = 1 << 28;
+
+/// Most colors `palette_interpolation_steps` computes ahead, 400MB of them
+const MAX_INTERPOLATION_STEPS: usize = 1 << 24;
+
+/// How often `watch` looks for changes to the config file
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the config file has to go unchanged before `watch` renders
+/// it, so that several saves in a row render once
+const WATCH_SETTLE: Duration = Duration::from_millis(500);
+
+/// Levels of zoom over which the `take` chosen when none is given grows
+/// by another `DEFAULT_TAKE`
+const TAKE_ZOOM_LEVELS: f64 = 4_f64;
+
+/// Print a status message, to stderr when stdout is taken by the image
+macro_rules! status {
+    ($args:expr, $($arg:tt)*) => {
+        if $args.output.as_deref() == Some(STDOUT) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Whether a failure should only end the job from stdin it happened in,
+/// rather than the whole process
+static IN_JOB: AtomicBool = AtomicBool::new(false);
+
+/// Report a failure with `message` and exit, or end the job if in one
+fn fail(kind: ErrorKind, message: impl Display) -> ! {
+    abort(Args::command().error(kind, message))
+}
+
+/// Report `error` and exit, or end the job if in one
+fn abort(error: clap::Error) -> ! {
+    if IN_JOB.load(Ordering::Relaxed) {
+        // The error is already reported, so unwinding skips the panic hook
+        let _ = error.print();
+        panic::resume_unwind(Box::new(error))
+    }
+    error.exit()
+}
+
+/// Format to encode the image in, instead of the one its extension names
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tiff,
+    Webp,
+    Exr,
+}
+
+impl OutputFormat {
+    fn image_format(self) -> ImageFormat {
+        match self {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+            OutputFormat::Webp => ImageFormat::WebP,
+            OutputFormat::Exr => ImageFormat::OpenExr,
+        }
+    }
+}
+
+/// How hard PNG output is compressed
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PngCompression {
+    Fast,
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    fn compression(self) -> png::Compression {
+        match self {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        }
+    }
+}
 
-#[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[derive(Parser, Debug, Clone)]
+#[clap(author, version, about, long_about = None, args_override_self = true)]
 struct Args {
     /// Final resolution of the X axis (in pixels)
     #[clap(short = 'x', long, parse(try_from_str=parse_resolution), default_value = "2560x1440")]
@@ -24,25 +225,218 @@ struct Args {
     #[clap(short = 'r', long, parse(try_from_str=parse_range))]
     range: Option<(Float, Float)>,
 
+    /// Plot a cross-section along this imaginary part instead of an image
+    /// Spans `domain` with as many points as the X `resolution`. A .csv
+    /// `output` gets the escape count of each point, anything else is
+    /// rendered as a strip one pixel high
+    #[clap(long, parse(try_from_str=parse_coordinate), requires = "domain", conflicts_with_all = &["range", "zoom-frames", "tile-size", "preview", "save-state", "seed-image"])]
+    line: Option<Float>,
+
+    /// Draw the orbit of this point over a faint render of the image
+    /// Each z it steps through is marked and joined to the next, up to
+    /// the step it escapes on or `take` steps if it never does
+    #[clap(long, parse(try_from_str=parse_point), conflicts_with_all = &["zoom-frames", "line", "tile-size", "tile-output", "save-state", "seed-image", "render-region-list", "oversize", "newton"])]
+    plot_orbit: Option<Complex>,
+
+    /// Draw a grid of lines at round real and imaginary parts over the
+    /// image, each labeled with its value, the real parts along the
+    /// bottom and the imaginary parts along the left
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "tile-output", "plot-orbit"])]
+    grid: bool,
+
+    /// Distance between the lines of the grid, along both axes
+    /// ie. 0.25 or 1e-20. Without it, 1, 2 or 5 times a power of ten that
+    /// puts about 8 lines across the wider of the image's spans
+    #[clap(long, parse(try_from_str=parse_grid_spacing), requires = "grid")]
+    grid_spacing: Option<(Integer, i32)>,
+
+    /// Color of the grid's lines and labels
+    /// (r,g,b): 0-255 each
+    #[clap(long, parse(try_from_str=parse_color), default_value = "128,128,128", requires = "grid")]
+    grid_color: [u8; 3],
+
+    /// Draw a scale bar in this corner of the image
+    /// As long as a round distance across the plane, labeled with it, ie.
+    /// 2e-9, to show how deep the image is zoomed
+    #[clap(long, arg_enum, conflicts_with_all = &["line", "tile-output", "plot-orbit"])]
+    scale_bar: Option<Corner>,
+
+    /// Color of the scale bar and its label
+    /// (r,g,b): 0-255 each
+    #[clap(long, parse(try_from_str=parse_color), default_value = "128,128,128", requires = "scale-bar")]
+    scale_bar_color: [u8; 3],
+
+    /// Fold the image into a kaleidoscope of this many mirrored wedges
+    /// about its center, each a copy of the one to the right of it
+    #[clap(long, parse(try_from_str=parse_segments), conflicts_with_all = &["grid", "tile-output", "plot-orbit"])]
+    kaleidoscope: Option<u32>,
+
+    /// Sharpen the image by this much with an unsharp mask, in linear
+    /// light
+    /// Adds this many times the difference between the image and a blur
+    /// of it, ie. 0.5 for a subtle crispness after `supersample` or
+    /// `oversize` soften the detail
+    #[clap(long, parse(try_from_str=parse_sharpen))]
+    sharpen: Option<f32>,
+
+    /// Standard deviation of the blur `sharpen` takes away, in pixels
+    /// Larger radii sharpen broader detail
+    #[clap(long, parse(try_from_str=parse_sharpen), default_value = "1", requires = "sharpen")]
+    sharpen_radius: f32,
+
     /// Center the image about this position
     /// (real, imaginary): (1.5, -0.754)
     #[clap(short = 'c', long, parse(try_from_str=parse_point))]
     centered_around: Option<Complex>,
 
+    /// Read `centered_around` from this file, in place of any other
+    /// For centers with too many digits for the command line. The real
+    /// and imaginary parts are split by a comma, and may be in
+    /// parentheses and wrapped over any number of lines. Lines starting
+    /// with # are skipped
+    #[clap(long)]
+    center_file: Option<String>,
+
     /// Zoom level about the position
     /// Used with `centered_around`, to provide precision
-    /// for rendering (1/(2 ^ zoom))
+    /// for rendering (1/(2 ^ zoom)). The image spans 1/(2 ^ zoom) along
+    /// each axis, stretching the pixels when it isn't square, or along
+    /// the longer one with `aspect_lock`. Fractional levels zoom in
+    /// between
     #[clap(short = 'z', long)]
-    zoom: Option<u32>,
+    zoom: Option<f64>,
+
+    /// Magnification about the position, instead of `zoom`
+    /// The image spans 1/magnification, ie. 1e30 is zoom 99.66. Any size
+    /// of number is taken, well beyond the range of a float
+    #[clap(long, parse(try_from_str=parse_magnification), conflicts_with = "zoom")]
+    magnification: Option<Float>,
+
+    /// Keep the pixels of a centered image square
+    /// Its longer side spans 1/(2 ^ zoom), and the shorter one as much
+    /// less as it has fewer pixels, so circles stay round
+    #[clap(long)]
+    aspect_lock: bool,
+
+    /// Turn the view about `centered_around` by this many degrees
+    /// From the positive real axis towards the positive imaginary axis,
+    /// for framing diagonal features
+    #[clap(long, requires = "centered-around", conflicts_with_all = &["domain", "range", "line"])]
+    rotate: Option<f64>,
+
+    /// Mirror the image left to right
+    /// The real part falls across the image instead of rising, sampled at
+    /// the same points as the image rendered without it
+    #[clap(long)]
+    flip_x: bool,
+
+    /// Mirror the image top to bottom
+    /// The imaginary part rises up the image instead of down, as most
+    /// plots draw it, sampled at the same points as the image rendered
+    /// without it
+    #[clap(long)]
+    flip_y: bool,
+
+    /// Swap the image's axes, so the real part runs down it and the
+    /// imaginary part across
+    /// Each pixel is spaced as it would be without it, so a sample that
+    /// isn't square spans as much of the real part down the image as
+    /// there are rows. Applied before `flip_x` and `flip_y`
+    #[clap(long, conflicts_with = "line")]
+    transpose: bool,
+
+    /// Bits of precision to add to those chosen for the image
+    /// Try raising it if a deep zoom looks blocky or pixelated
+    #[clap(long, default_value_t = 0)]
+    extra_precision: u32,
+
+    /// Most bits of precision to render with before giving up
+    /// Every number in a render is held at the image's precision, so one
+    /// zoomed far too deep (ie. a mistyped `zoom`) would otherwise take
+    /// all the memory there is
+    #[clap(long, default_value_t = 100_000)]
+    max_precision: u32,
 
     /// Samples to iterate before deterimining that a
     /// point has converged
-    #[clap(short = 't', long, default_value_t = 500)]
-    take: usize,
+    /// Starts from 500 and grows the further the image is zoomed in, by
+    /// another 500 every 4 levels of `zoom`, unless it is given
+    #[clap(short = 't', long)]
+    take: Option<usize>,
 
     /// Output file (and format)
-    #[clap(short = 'o', long)]
-    output: String,
+    /// PNG files record the settings they were rendered with, see
+    /// `read_metadata`. EXR files hold unclamped 32-bit float linear
+    /// light, for tone mapping elsewhere
+    #[clap(short = 'o', long, required_unless_present_any = &["read-metadata", "benchmark", "batch", "dry-run", "gradient-json", "worker"])]
+    output: Option<String>,
+
+    /// Format to save `output` in, instead of the one its extension names
+    /// Needed to choose the format when `output` is -, which writes the
+    /// image to stdout (as PNG by default)
+    #[clap(long, arg_enum)]
+    output_format: Option<OutputFormat>,
+
+    /// Quality of JPEG output, from 1 to 100
+    #[clap(long, parse(try_from_str=parse_quality), default_value_t = 75)]
+    jpeg_quality: u8,
+
+    /// Compression of PNG output, trading file size for saving time
+    #[clap(long, arg_enum, default_value = "default")]
+    png_compression: PngCompression,
+
+    /// Bits of each color channel of the image, 8 or 16
+    /// 16 bits hold smooth gradients without bands, for PNG and TIFF
+    /// output
+    #[clap(long, parse(try_from_str=parse_bit_depth), default_value_t = 8, conflicts_with_all = &["tile-size", "tile-output", "animate", "color-count"])]
+    bit_depth: u8,
+
+    /// Save the image in at most this many colors, from 2 to 256
+    /// PNG output is saved with them as its palette, and GIF output keeps
+    /// exactly them
+    #[clap(long, parse(try_from_str=parse_color_count), conflicts_with = "tile-output")]
+    color_count: Option<usize>,
+
+    /// Save the image with an alpha channel, transparent in the set
+    /// Pixels in the set, masked out, or that couldn't be colored are
+    /// left transparent instead of the `background`, and those partly so
+    /// partly transparent, for laying the image over others. Interior
+    /// coloring is kept opaque. 8-bit PNG and TIFF output only
+    #[clap(long, conflicts_with_all = &["tile-size", "tile-output", "oversize", "save-state", "seed-image", "animate", "render-region-list", "color-count", "kaleidoscope", "sharpen", "grid", "scale-bar", "line", "plot-orbit"])]
+    alpha: bool,
+
+    /// How the `color_count` colors are chosen
+    #[clap(long, arg_enum, default_value = "median-cut", requires = "color-count")]
+    quantizer: Quantizer,
+
+    /// Also write the escape count of every pixel to this file
+    /// A .npy file for NumPy, or otherwise a 16-bit grayscale PNG. Counts
+    /// are 16-bit unless `take` is over 65535, which needs .npy. Each
+    /// pixel is sampled once at its top left corner
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "tile-output"])]
+    iterations_output: Option<String>,
+
+    /// Print statistics of the pixels' escape counts after rendering
+    /// The fraction in the set, the min, max, mean and median count of
+    /// the rest, and a coarse histogram, to help pick `take`
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "tile-output"])]
+    profile: bool,
+
+    /// Also write the `--profile` statistics to this JSON file
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "tile-output"])]
+    profile_output: Option<String>,
+
+    /// Compare the image with this one once it's saved, and fail if they
+    /// differ by more than `compare_tolerance`
+    /// Reports how many pixels differ, and by how much, for checking
+    /// that a change to the renderer leaves its output as it was
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "plot-orbit"])]
+    compare: Option<String>,
+
+    /// Most any channel of a pixel may differ from `compare` by, in steps
+    /// of 0-255
+    #[clap(long, default_value_t = 0_f64, requires = "compare")]
+    compare_tolerance: f64,
 
     /// Interval range for Gradient
     /// The gradient shifts in a loop on this interval. Large values
@@ -51,22 +445,760 @@ struct Args {
     #[clap(short = 'g', long, default_value = "300")]
     gradient_interval: usize,
 
+    /// Cycle through the gradient this many times across `take`
+    /// Loops on `take` / n instead of `gradient_interval`, so the image
+    /// keeps the same number of color bands however many iterations it
+    /// takes. Only for escape count coloring
+    #[clap(long, parse(try_from_str=parse_repeats), conflicts_with_all = &["gradient-interval", "exponential-gradient", "histogram", "coloring"])]
+    repeat_palette: Option<u32>,
+
     /// Exponential Gradient
     /// Determines if the gradient should be exponential in nature
     #[clap(short = 'e', long)]
     exponential_gradient: bool,
+
+    /// How escape counts are spread over the gradient
+    /// Looping over it every `gradient_interval`, once over the whole
+    /// `take`, equalized with a histogram, or by the angle z escapes at.
+    /// `exponential_gradient` and `histogram` are short for the second
+    /// and third. Orbit traps, stripes and TIA color by the orbit instead
+    #[clap(long, arg_enum, conflicts_with_all = &["exponential-gradient", "histogram"])]
+    coloring: Option<ColoringMode>,
+
+    /// How escape counts past the end of the gradient are colored
+    /// Wraps around to the start by default, or clamps to the last color
+    /// with `exponential_gradient`. Mirror runs back and forth through
+    /// the gradient, without the seam wrapping has between its ends
+    #[clap(long, arg_enum)]
+    gradient_mode: Option<GradientMode>,
+
+    /// Rotate the gradient by this fraction of it, from 0 up to 1
+    /// Stepping it across frames cycles the colors. Mirrored gradients
+    /// rotate through both directions, so that frames still loop. A render
+    /// saved with `save_state` is recolored with `seed_image` and the same
+    /// `take` without iterating it again
+    #[clap(long, parse(try_from_str=parse_cycle), default_value_t = 0_f64)]
+    palette_cycle: f64,
+
+    /// Color space the gradients mix between their stops in
+    /// Oklab steps evenly in lightness and hue, where linear sRGB can
+    /// muddy the colors halfway between stops. HSL goes round the hue
+    /// wheel between them
+    #[clap(long, arg_enum, default_value = "linsrgb")]
+    color_space: ColorSpace,
+
+    /// How the gradient mixes from each of its stops to the next
+    /// Smoothstep holds each stop's color longer and changes quickly
+    /// between them, for harder bands. Ease-in and ease-out hold on the
+    /// near or far stop of each pair
+    #[clap(long, arg_enum, default_value = "linear")]
+    palette_easing: Easing,
+
+    /// Compute this many colors evenly across the gradient ahead, and mix
+    /// each pixel's from the two nearest
+    /// Faster to color large images with than mixing every color from
+    /// the stops, most of all in the oklab, lab and hsl `color_space`s.
+    /// Without a number, 4096, which is no different to see
+    #[clap(long, min_values = 0, default_missing_value = "4096", parse(try_from_str=parse_interpolation_steps))]
+    palette_interpolation_steps: Option<usize>,
+
+    /// Color of points inside the set
+    /// (r,g,b): 0-255 each, ie. 20,20,40
+    #[clap(long, parse(try_from_str=parse_color), default_value = "0,0,0")]
+    background: [u8; 3],
+
+    /// Invert the coloring
+    /// Points inside the set are colored by how close their orbits end
+    /// to the escape radius, and points that escape get `background`
+    #[clap(long, conflicts_with_all = &["newton", "orbit-trap", "save-state", "seed-image"])]
+    invert: bool,
+
+    /// Color points inside the set too, from a darker gradient of their own
+    /// By |z| where their orbit ends, or by the period of the cycle it
+    /// settles into (which turns on `detect_periodicity`)
+    #[clap(long, arg_enum, conflicts_with_all = &["invert", "newton", "save-state", "seed-image"])]
+    interior_coloring: Option<InteriorColoring>,
+
+    /// Dither the 8-bit output to hide banding in smooth gradients
+    /// Uses an ordered (Bayer) pattern, so the same image is reproduced
+    /// every time
+    #[clap(long)]
+    dither: bool,
+
+    /// Encode the image with this gamma instead of the sRGB curve
+    /// ie. 2.2. Doesn't apply to EXR output, which is linear
+    #[clap(long, parse(try_from_str=parse_gamma))]
+    gamma: Option<f64>,
+
+    /// Logarithmic iteration scaling
+    /// Looks escape counts up in the gradient by ln(i + 1) / ln(take + 1),
+    /// giving more of it to the low counts where most of the structure
+    /// is. Still loops on `gradient_interval` unless `exponential_gradient`
+    /// is set
+    #[clap(long, conflicts_with = "histogram")]
+    log_scale: bool,
+
+    /// Load the gradient from a palette file instead of the built-ins
+    /// One `position,r,g,b` stop per line (channels 0.0-1.0 or 0-255),
+    /// a GIMP `.ggr` gradient, or the `.json` of `gradient_json`, with at
+    /// least two stops in order of position. Positions can be on any
+    /// scale: the span from the first to the last is what's stretched
+    /// over `gradient_interval` escapes, looping, or across `take` when
+    /// exponential. See palettes/ for examples
+    #[clap(long)]
+    palette: Option<String>,
+
+    /// Take the gradient from the colors along the middle row of an image
+    /// Lets a palette be picked from a screenshot of a colormap or any
+    /// other picture, sampled evenly from its left edge to its right
+    #[clap(long, conflicts_with = "palette")]
+    palette_from_image: Option<String>,
+
+    /// Columns of `palette_from_image` to take the gradient's stops from
+    /// Every column is used from an image narrower than this
+    #[clap(long, default_value = "32", requires = "palette-from-image")]
+    palette_samples: NonZeroUsize,
+
+    /// Run the gradient backwards
+    /// From its last stop to its first over the same span, whether
+    /// built in or loaded. Wraps, clamps and mirrors as it would have
+    #[clap(long)]
+    palette_reverse: bool,
+
+    /// Move each color of the gradient this many stops on, wrapping
+    /// around from the last stop to the first
+    /// The stops stay where they were, for another look at the same
+    /// colors. Negative numbers move them back. After `palette_reverse`
+    #[clap(long, allow_hyphen_values = true)]
+    palette_rotate_stops: Option<i32>,
+
+    /// Render the Julia set for this constant instead of the Mandelbrot set
+    /// Each pixel becomes the starting point of the orbit. Perturbation
+    /// only renders the Mandelbrot set, so can't be used with it
+    /// (real, imaginary): (-0.8, 0.156)
+    #[clap(short = 'j', long, parse(try_from_str=parse_point), conflicts_with = "perturbation")]
+    julia: Option<Complex>,
+
+    /// Exponent of the iterated polynomial (z^n + c)
+    /// Values above 2 render Multibrot sets. The transcendental
+    /// fractals (exp, sin, cos) ignore it
+    #[clap(short = 'p', long, default_value_t = 2)]
+    power: u32,
+
+    /// Escape radius
+    /// A point has escaped once |z| exceeds this. Larger values
+    /// improve the quality of smooth coloring. Defaults to 2, or to 50
+    /// for exp, sin and cos, which escape once the real (exp) or
+    /// imaginary (sin, cos) part of z exceeds it instead
+    #[clap(short = 'b', long)]
+    bailout: Option<f64>,
+
+    /// Choose the escape radius for the coloring, when no `bailout` is
+    /// given
+    /// Smooth coloring and distance estimates get 1e10, so the fractional
+    /// counts are measured far from the set, and stripe and TIA coloring
+    /// 1000. Other colorings, and exp, sin, cos and `newton`, keep their
+    /// usual one. The chosen `bailout` is printed and recorded in the
+    /// metadata. Always on with `smooth`
+    #[clap(long)]
+    auto_bailout: bool,
+
+    /// Fractal to render
+    /// The imaginary axis increases down the image, which is the
+    /// flipped orientation burning-ship is conventionally drawn in
+    /// (the "ship" appears upright); coordinates are not mirrored
+    #[clap(short = 'f', long, arg_enum, default_value = "mandelbrot")]
+    fractal: Fractal,
+
+    /// Smooth (continuous) coloring
+    /// Uses the modulus at escape to compute a fractional iteration
+    /// count, removing the visible bands between iteration counts. Looks
+    /// best with a large `bailout`, so turns on `auto_bailout` unless one
+    /// is given
+    #[clap(short = 's', long)]
+    smooth: bool,
+
+    /// Steps to carry on iterating past the bailout for smooth coloring
+    /// The fractional count is worked out from the larger modulus, which
+    /// removes the faint banding left near the escape boundary. 2 or 3
+    /// is plenty. Has no effect on exp, sin, cos, `--newton`, or stripe
+    /// and TIA coloring
+    #[clap(long, requires = "smooth", default_value_t = 0)]
+    continue_past_bailout: u32,
+
+    /// Distance estimate shading
+    /// Tracks the derivative of the orbit to estimate the distance to
+    /// the set, darkening pixels close to the boundary so that thin
+    /// filaments stay visible at deep zooms
+    #[clap(long)]
+    distance_estimate: bool,
+
+    /// Slope shading
+    /// Lights the boundary as if it were a raised surface, from the
+    /// direction of `light_angle`
+    #[clap(long)]
+    shade: bool,
+
+    /// Direction the slope shading light comes from, in degrees
+    /// from the positive real axis towards the positive imaginary axis
+    /// (clockwise as drawn, the imaginary axis increases down the image)
+    #[clap(long, default_value_t = 45.0)]
+    light_angle: f64,
+
+    /// Periodicity checking
+    /// Stops iterating a point once its orbit repeats, which saves
+    /// most of the `take` budget for points inside the set. An orbit
+    /// only repeats once within a few bits of the working precision, so
+    /// no point that would escape is ever taken to be inside
+    #[clap(long, alias = "periodicity")]
+    detect_periodicity: bool,
+
+    /// Supersampling antialiasing
+    /// Each pixel is rendered as an NxN grid of samples which are
+    /// averaged together
+    #[clap(long, default_value_t = 1)]
+    supersample: u32,
+
+    /// Which pixels are supersampled
+    /// Adaptive renders every pixel once, then only renders the grid of
+    /// `supersample` samples for those on edges, where the escape counts
+    /// around them vary by more than `variance_threshold`. Perceptual
+    /// does the same for those whose colors differ by more than
+    /// `delta_e_threshold`, which follows what the gradient makes of the
+    /// counts: it leaves smooth bands alone and catches its seams. Edges
+    /// only supersamples the boundary of the set
+    #[clap(long, arg_enum, default_value = "grid")]
+    anti_aliasing: AntiAliasing,
+
+    /// Only supersample the boundary of the set, where aliasing is worst.
+    /// Short for `--anti-aliasing edges`
+    /// Pixels next to one on the other side of it are found from a sample
+    /// of each, and those within `edge_dilation` of them supersampled
+    #[clap(long, conflicts_with = "anti-aliasing")]
+    antialias_edges_only: bool,
+
+    /// Pixels around the boundary of the set that edge anti-aliasing
+    /// supersamples too, so that thin filaments a sample misses still
+    /// get their grid
+    #[clap(long, default_value_t = 1)]
+    edge_dilation: u32,
+
+    /// Variance of escape counts that adaptive anti-aliasing supersamples
+    /// above, over each pixel and the 8 around it. Lower is smoother but
+    /// slower
+    #[clap(long, default_value_t = 0.1)]
+    variance_threshold: f64,
+
+    /// Color difference that perceptual anti-aliasing supersamples above
+    /// CIEDE2000 between each pixel and the 8 around it, where around 1
+    /// is just noticeable. Lower is smoother but slower
+    #[clap(long, default_value_t = 2_f64)]
+    delta_e_threshold: f64,
+
+    /// Render at this multiple of the resolution and shrink it
+    /// The larger image is shrunk with a Lanczos filter in linear light,
+    /// which anti-aliases more smoothly than `supersample`. It's held in
+    /// memory at 12 bytes a pixel, ie. 700MB for 2560x1440 at 4, so the
+    /// factor is at most 8 and the larger image at most 2^28 pixels
+    #[clap(long, default_value_t = 1, conflicts_with_all = &["tile-size", "tile-output", "save-state", "seed-image"])]
+    oversize: u32,
+
+    /// Order the rows of each image are rendered in
+    /// Golden fills them in outwards from the middle, a golden ratio of
+    /// the height apart, so that the `interactive` window shows the whole
+    /// view coarsely while it renders. The image is the same either way
+    #[clap(long, arg_enum, default_value = "rows")]
+    fill_order: FillOrder,
+
+    /// Equalize colors with a histogram of escape counts
+    /// Each count is colored by the share of pixels escaping before it,
+    /// so crowded bands of iterations get more of the gradient
+    #[clap(long)]
+    histogram: bool,
+
+    /// Orbit trap coloring
+    /// Colors every point, inside the set or not, by the closest its
+    /// orbit comes to the trap, from the trap itself to `bailout` away
+    #[clap(long, arg_enum, conflicts_with = "histogram")]
+    orbit_trap: Option<OrbitTrap>,
+
+    /// Position of the orbit trap
+    /// (real, imaginary): (0.0, 0.0)
+    #[clap(long, parse(try_from_str=parse_point), default_value = "(0,0)")]
+    trap_position: Complex,
+
+    /// Starting value of z, instead of 0
+    /// Perturbs every orbit of the Mandelbrot set by the same seed
+    /// (real, imaginary): (0.1, -0.2)
+    #[clap(long, parse(try_from_str=parse_point), default_value = "(0,0)", conflicts_with_all = &["julia", "newton"])]
+    z0: Complex,
+
+    /// Render the basins of Newton's method for z^n - 1 of this degree
+    /// Each pixel is colored by the root it converges to, darkening the
+    /// more iterations it takes
+    #[clap(long, parse(try_from_str=parse_degree), conflicts_with_all = &["julia", "fractal", "power", "orbit-trap", "stripe-density", "tia", "histogram", "distance-estimate", "shade"])]
+    newton: Option<u32>,
+
+    /// How `newton` finds the roots
+    #[clap(long, arg_enum, default_value = "newton", requires = "newton")]
+    method: RootMethod,
+
+    /// Stripe average coloring
+    /// Colors escaping points by the average of 0.5 + 0.5 * sin(n * arg z)
+    /// over their orbit, for flowing bands along the filaments. Looks
+    /// best with a large `bailout`, ie. 1000
+    #[clap(long, conflicts_with_all = &["histogram", "orbit-trap"])]
+    stripe_density: Option<f64>,
+
+    /// Triangle inequality average (TIA) coloring
+    /// Colors escaping points by the average over their orbit of where |z|
+    /// falls between the bounds ||z^n| - |c|| and |z^n| + |c|, for soft
+    /// detail around the set. Looks best with a large `bailout`, ie. 1000
+    #[clap(long, conflicts_with_all = &["histogram", "orbit-trap", "stripe-density"])]
+    tia: bool,
+
+    /// Perturbation rendering for deep zooms
+    /// Iterates one reference orbit through the center at full precision,
+    /// and every pixel as an offset from it in hardware floats. Pixels
+    /// where this loses precision are iterated at full precision instead.
+    /// Only applies to the Mandelbrot set with power 2
+    #[clap(long)]
+    perturbation: bool,
+
+    /// Terms of the series approximation used with `perturbation`
+    /// The first iterations of every pixel are approximated from the
+    /// reference orbit for as long as this stays accurate across the
+    /// image. 0 iterates every pixel in full
+    #[clap(long, default_value_t = 0, requires = "perturbation")]
+    series_terms: usize,
+
+    /// Fold the reference orbit of `perturbation` onto the real axis
+    /// When the axis runs within a pixel of the center of the image, the
+    /// reference is moved onto it and iterated in real numbers, for half
+    /// the work. Each pixel's orbit is then exactly the conjugate of its
+    /// mirror's across the axis, whose rows are copied rather than rendered
+    #[clap(long, requires = "perturbation")]
+    real_axis_fold: bool,
+
+    /// Keep the reference orbit of `perturbation` in this file
+    /// It's computed once, deep enough for the deepest frame of
+    /// `zoom_frames`, and used for every frame, rather than computed for
+    /// each. Renders after load it from the file, while their center is
+    /// within a quarter of the image's width of it, and compute a new one
+    /// over it otherwise
+    #[clap(long, requires = "perturbation")]
+    seed_from_reference_file: Option<String>,
+
+    /// Write the orbit of the center of the image to this CSV file
+    /// Each step's z, at the full precision of the render, and |z|, up to
+    /// the step it escapes on or `take`. The same point `perturbation`
+    /// takes its reference orbit at, to tell whether the precision or the
+    /// take is what a deep zoom is short of
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "plot-orbit"])]
+    reference_orbit_export: Option<String>,
+
+    /// Iterate every point on its own, without SIMD
+    /// Plain z^2 + c orbits in hardware floats are otherwise iterated
+    /// four points at a time. The image is the same either way, so this
+    /// is for checking that it is
+    #[clap(long)]
+    no_simd: bool,
+
+    /// Iterate in arbitrary precision floats even when the image needs
+    /// no more precision than hardware floats have
+    /// Much slower, and the two round every step the same way at 53
+    /// bits, so the image should be identical. This is for checking that
+    /// it is, should they ever disagree
+    #[clap(long)]
+    force_bignum: bool,
+
+    /// Render a zoom animation of this many frames
+    /// Frames zoom about `centered_around` from `zoom_start` to
+    /// `zoom_end`, and are saved as numbered files next to `output`
+    /// ie. zoom.png -> zoom_0001.png, zoom_0002.png, ... or all in
+    /// `output` with `animate`
+    #[clap(long)]
+    zoom_frames: Option<u32>,
+
+    /// Zoom level of the first frame of a zoom animation
+    #[clap(long)]
+    zoom_start: Option<f64>,
+
+    /// Zoom level of the last frame of a zoom animation
+    #[clap(long)]
+    zoom_end: Option<f64>,
+
+    /// How a zoom animation moves from `zoom_start` to `zoom_end`
+    /// Linear zooms in at a steady rate. Smoothstep (or ease-in-out)
+    /// speeds up out of the first frame and slows into the last, and
+    /// ease-in and ease-out only at one end
+    #[clap(long, arg_enum, default_value = "linear", requires = "zoom-frames")]
+    zoom_easing: Easing,
+
+    /// Save the zoom animation as one animated PNG at `output`
+    /// Instead of numbered files, looping forever. `output` must be a PNG
+    #[clap(long, requires = "zoom-frames", conflicts_with_all = &["tile-size", "tile-output"])]
+    animate: bool,
+
+    /// Milliseconds each frame of `animate` is shown for
+    #[clap(long, default_value_t = 40, requires = "animate")]
+    frame_delay: u16,
+
+    /// Render in square tiles of this many pixels
+    /// Each finished tile is saved to a `.checkpoint` directory named
+    /// after `output`, so an interrupted render can be picked up with
+    /// `resume`
+    #[clap(long, conflicts_with = "histogram")]
+    tile_size: Option<u32>,
+
+    /// Order the tiles are rendered in
+    /// Every thread renders one tile at a time in this order, or each
+    /// pool of `threads_per_tile` or `distributed` worker takes the next
+    /// tile in it. Only how the image fills in while it's rendered
+    /// differs; the image is the same. Morton keeps the tiles in progress
+    /// near each other, which can share caches better than whole rows
+    #[clap(long, arg_enum, default_value = "rows", requires = "tile-size")]
+    tile_order: TileOrder,
+
+    /// Render each tile with a pool of only this many threads
+    /// The threads are split into pools that take whole tiles, instead of
+    /// every thread taking rows from every tile, so that each tile stays
+    /// in the caches of the cores rendering it. Helps most on machines
+    /// with several sockets; compare the pixels per second of `verbose`
+    #[clap(long, requires = "tile-size")]
+    threads_per_tile: Option<NonZeroUsize>,
+
+    /// Render the tiles on the `worker`s at these comma separated
+    /// addresses, ie. 10.0.0.2:7878,10.0.0.3:7878
+    /// Each is sent the options of the command line, and takes the next
+    /// tile left once it sends one back. Files the options name, like a
+    /// `palette`, have to be at the same paths on the workers
+    #[clap(long, requires = "tile-size", conflicts_with_all = &["threads-per-tile", "tile-output", "zoom-frames", "render-region-list", "mask", "seed-from-reference-file", "stdin", "batch", "watch"])]
+    distributed: Option<String>,
+
+    /// Listen at this address, ie. 0.0.0.0:7878, and render tiles for
+    /// `distributed` renders until stopped
+    /// The options of each render come from its coordinator, so the rest
+    /// of this command line is ignored
+    #[clap(long)]
+    worker: Option<String>,
+
+    /// Resume a tiled render from its checkpoint, skipping finished tiles
+    #[clap(long, requires = "tile-size")]
+    resume: bool,
+
+    /// Stop rendering an image after this many seconds, save what was
+    /// finished and exit unsuccessfully
+    /// Rows that weren't finished are left black. A tiled render keeps
+    /// its checkpoint, so `resume` can carry on from the tiles it saved
+    #[clap(long, parse(try_from_str=parse_timeout))]
+    timeout: Option<Duration>,
+
+    /// Write the image in strips of this many rows as they're rendered
+    /// Only one strip is held in memory at a time, for images too large
+    /// to fit. `output` must be a PNG
+    #[clap(long, conflicts_with_all = &["tile-size", "histogram", "save-state", "seed-image"])]
+    tile_output: Option<NonZeroU32>,
+
+    /// Only render the pixels from (x0,y0) up to (x1,y1) of the image
+    /// x0,y0,x1,y1: pixels of the full `resolution`, ie. 0,0,640,360. The
+    /// crop is framed and iterated exactly as the same pixels of the full
+    /// image would be, so that it lines up with it
+    #[clap(long, parse(try_from_str=parse_crop), conflicts_with_all = &["zoom-frames", "line"])]
+    crop: Option<[u32; 4]>,
+
+    /// Render the regions listed in this file over the image
+    /// Each line is the pixel the region's top left corner goes at, ie.
+    /// 40,40, then the options that render it, given on their own as on the
+    /// command line: its `resolution` is its size. For insets of a zoomed
+    /// view over an overview, or comparisons of palettes side by side
+    #[clap(long, conflicts_with_all = &["zoom-frames", "tile-output", "save-state", "seed-image", "stdin", "batch"])]
+    render_region_list: Option<String>,
+
+    /// Most threads to render with, instead of one per core
+    /// The image is the same bit for bit however many there are, and from
+    /// one run to the next: threads only split up the rows and tiles, and
+    /// each pixel's samples are summed in the same order on one of them
+    #[clap(long)]
+    max_threads: Option<NonZeroUsize>,
+
+    /// Render a quick preview first, at 1/scale the resolution and take
+    /// Saved next to `output` (ie. deep.png -> deep.preview.png) along
+    /// with an estimate of how long the full render will take
+    #[clap(long, conflicts_with = "zoom-frames")]
+    preview: Option<NonZeroU32>,
+
+    /// Save where every orbit got to next to `output`
+    /// (ie. deep.png -> deep.state), so that the render can be continued
+    /// with a larger `take` by `seed_image`
+    #[clap(long, conflicts_with_all = STATE_CONFLICTS)]
+    save_state: bool,
+
+    /// Continue the render of this image with a larger `take`
+    /// It must have been rendered with `save_state` and otherwise the
+    /// same settings. Pixels that already escaped aren't iterated again
+    #[clap(long, conflicts_with_all = STATE_CONFLICTS)]
+    seed_image: Option<String>,
+
+    /// Only render the pixels that are white in this image
+    /// It must be the size of the image. The rest are left as the
+    /// background, or as they were in the `seed_image`, to render again
+    /// just a patch of it
+    #[clap(long, conflicts_with_all = &["zoom-frames", "line", "preview", "oversize", "plot-orbit", "batch"])]
+    mask: Option<String>,
+
+    /// Choose `take` automatically, starting from the given one
+    /// Doubles it while a significant part of the image stops at it,
+    /// until doubling lets few more pixels escape. The chosen `take` is
+    /// printed and recorded in the metadata
+    #[clap(long)]
+    auto_take: bool,
+
+    /// Load settings from a TOML file
+    /// Keys are the long names of these options, ie. `take = 1000`.
+    /// Options given on the command line override the file
+    #[clap(long)]
+    config: Option<String>,
+
+    /// Render the same part of the plane as a PNG rendered before
+    /// Its domain and range or center and zoom, resolution and take are
+    /// read from the settings recorded in it. Options given on the command
+    /// line override them, ie. to render it again at a higher resolution
+    #[clap(long)]
+    bounds_from: Option<String>,
+
+    /// Render a job for each line of stdin, until it is closed
+    /// Each line holds options as they are given on the command line, which
+    /// are added to those given here. Its output is numbered by the line,
+    /// ie. sweep.png -> sweep_0003.png for the third. A job that fails is
+    /// reported and the rest carry on
+    #[clap(long, conflicts_with = "read-metadata")]
+    stdin: bool,
+
+    /// Render a view for each row of this CSV file
+    /// Each row is center_real,center_imag,zoom,output_name, framed as by
+    /// `centered_around` and `zoom` with the rest of the options given
+    /// here. A first row that isn't numbers is skipped as a header. A row
+    /// that fails is reported and the rest carry on
+    #[clap(long, conflicts_with_all = &["read-metadata", "stdin", "domain", "range", "zoom-frames", "line"])]
+    batch: Option<String>,
+
+    /// Render again each time the `config` file changes, until stopped
+    /// Settings that don't parse are reported and left unrendered until
+    /// the file changes again
+    #[clap(long, requires = "config", conflicts_with_all = &["read-metadata", "stdin", "batch", "benchmark", "dry-run", "palette-preview"])]
+    watch: bool,
+
+    /// Explore the set in a window before rendering
+    /// Starts from the image as it's framed, or the whole set. Drag to zoom
+    /// in to a region, click to center on a point and Backspace to go
+    /// back. Enter renders the view to `output` at full resolution
+    #[cfg(feature = "interactive")]
+    #[clap(long, conflicts_with_all = &["read-metadata", "stdin", "batch", "zoom-frames", "line", "distributed"])]
+    interactive: bool,
+
+    /// Don't display the progress bar
+    #[clap(short = 'q', long)]
+    quiet: bool,
+
+    /// Report how long each part of the render took
+    /// Parsing the options, choosing the precision (and `take`), rendering
+    /// and encoding, with the pixels rendered per second and the fraction
+    /// of samples that were in the set
+    #[clap(short = 'v', long)]
+    verbose: bool,
+
+    /// Time a render of a fixed scene and print how fast it went
+    /// The seahorse valley at 640x360 and 1000 iterations, whatever the
+    /// options framing and coloring the image are, so that builds and
+    /// machines can be compared. `max_threads` and `no_simd` still apply
+    #[clap(long, conflicts_with_all = &["read-metadata", "stdin", "batch"])]
+    benchmark: bool,
+
+    /// Print the precision the image needs and exit without rendering it
+    /// With the bits of precision, the size of a pixel, `take` and a rough
+    /// estimate of the memory it takes, for the deepest frame of
+    /// `zoom_frames`
+    #[clap(long, conflicts_with_all = &["read-metadata", "stdin", "batch", "benchmark"])]
+    dry_run: bool,
+
+    /// Save an image of the gradient to `output` instead of rendering
+    /// It runs across the image from the first stop on the left to the
+    /// last on the right, to see a `palette` without rendering with it
+    #[clap(long, conflicts_with_all = &["read-metadata", "stdin", "batch", "benchmark", "dry-run"])]
+    palette_preview: bool,
+
+    /// Mark where each stop is along the bottom of the `palette_preview`
+    #[clap(long, requires = "palette-preview")]
+    palette_ticks: bool,
+
+    /// Write the stops of the gradient to this JSON file and exit
+    /// The built-in gradient, or that of `palette`, with sRGB encoded
+    /// channels from 0 to 1. A `palette` ending in .json is read back
+    /// from it, so a built-in can be a starting point to edit
+    #[clap(long, conflicts_with_all = &["read-metadata", "stdin", "batch", "benchmark", "dry-run", "palette-preview", "watch"])]
+    gradient_json: Option<String>,
+
+    /// Print the settings recorded in a rendered PNG and exit
+    /// Each is printed as the option that reproduces it
+    #[clap(long)]
+    read_metadata: Option<String>,
 }
 
-fn parse_resolution(resolution: &str) -> Result<(u32, u32), &'static str> {
-    let (x, y) = resolution
-        .split('x')
-        .map(|m| m.parse::<u32>().map_err(|_| "Invalid Resolution"))
-        .collect_tuple()
-        .expect("Resolution must be in the format 9999x9999");
-    Ok((x?, y?))
+fn parse_resolution(resolution: &str) -> Result<(u32, u32), String> {
+    let format = || "must be two whole numbers of pixels like 2560x1440".to_owned();
+    let (x, y) = resolution.split('x').collect_tuple().ok_or_else(format)?;
+    match (x.trim().parse::<u32>(), y.trim().parse::<u32>()) {
+        (Ok(x), Ok(y)) if x > 0 && y > 0 => Ok((x, y)),
+        _ => Err(format()),
+    }
+}
+
+fn parse_degree(degree: &str) -> Result<u32, &'static str> {
+    match degree.parse::<u32>() {
+        Ok(degree) if degree >= 2 => Ok(degree),
+        _ => Err("Degree must be a whole number of at least 2"),
+    }
+}
+
+fn parse_repeats(repeats: &str) -> Result<u32, &'static str> {
+    match repeats.parse::<u32>() {
+        Ok(repeats) if repeats >= 1 => Ok(repeats),
+        _ => Err("Repeats must be a whole number of at least 1"),
+    }
+}
+
+fn parse_color(color: &str) -> Result<[u8; 3], &'static str> {
+    let channels: Vec<u8> = color
+        .split(',')
+        .map(|c| c.trim().parse::<u8>().map_err(|_| "Channels must be 0-255"))
+        .collect::<Result<_, _>>()?;
+    channels
+        .try_into()
+        .map_err(|_| "Color must be in the format r,g,b")
+}
+
+/// A positive number as its digits times a power of ten, so that the
+/// values it is a multiple of can be written out exactly
+fn parse_grid_spacing(spacing: &str) -> Result<(Integer, i32), &'static str> {
+    const MESSAGE: &str = "Grid spacing must be a positive number, ie. 0.25 or 1e-20";
+    let spacing = spacing.trim();
+    let (mantissa, exponent) = match spacing.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().map_err(|_| MESSAGE)?),
+        None => (spacing, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", whole, fraction);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(MESSAGE);
+    }
+    let digits = Integer::from_str_radix(&digits, 10).map_err(|_| MESSAGE)?;
+    if digits == 0 {
+        return Err(MESSAGE);
+    }
+    let exponent = i32::try_from(fraction.len())
+        .ok()
+        .and_then(|places| exponent.checked_sub(places))
+        .ok_or(MESSAGE)?;
+    Ok((digits, exponent))
+}
+
+fn parse_segments(segments: &str) -> Result<u32, String> {
+    match segments.parse::<u32>() {
+        Ok(segments) if (1..=MAX_SEGMENTS).contains(&segments) => Ok(segments),
+        _ => Err(format!(
+            "Segments must be a whole number from 1 to {}",
+            MAX_SEGMENTS
+        )),
+    }
+}
+
+fn parse_sharpen(amount: &str) -> Result<f32, &'static str> {
+    match amount.parse::<f32>() {
+        Ok(amount) if amount > 0_f32 && amount.is_finite() => Ok(amount),
+        _ => Err("Must be a positive number"),
+    }
+}
+
+fn parse_timeout(timeout: &str) -> Result<Duration, &'static str> {
+    match timeout.parse::<f64>() {
+        Ok(seconds) if seconds > 0_f64 && seconds.is_finite() => {
+            Ok(Duration::from_secs_f64(seconds))
+        }
+        _ => Err("Timeout must be a positive number of seconds"),
+    }
+}
+
+fn parse_quality(quality: &str) -> Result<u8, &'static str> {
+    match quality.parse::<u8>() {
+        Ok(quality) if (1..=100).contains(&quality) => Ok(quality),
+        _ => Err("Quality must be a whole number from 1 to 100"),
+    }
+}
+
+fn parse_bit_depth(depth: &str) -> Result<u8, &'static str> {
+    match depth.parse::<u8>() {
+        Ok(depth @ (8 | 16)) => Ok(depth),
+        _ => Err("Bit depth must be 8 or 16"),
+    }
+}
+
+fn parse_color_count(colors: &str) -> Result<usize, &'static str> {
+    match colors.parse::<usize>() {
+        Ok(colors) if (2..=256).contains(&colors) => Ok(colors),
+        _ => Err("Color count must be a whole number from 2 to 256"),
+    }
+}
+
+fn parse_interpolation_steps(steps: &str) -> Result<usize, String> {
+    match steps.parse::<usize>() {
+        Ok(steps) if (2..=MAX_INTERPOLATION_STEPS).contains(&steps) => Ok(steps),
+        _ => Err(format!(
+            "Interpolation steps must be a whole number from 2 to {}",
+            MAX_INTERPOLATION_STEPS
+        )),
+    }
+}
+
+fn parse_gamma(gamma: &str) -> Result<f64, &'static str> {
+    match gamma.parse::<f64>() {
+        Ok(gamma) if gamma > 0_f64 && gamma.is_finite() => Ok(gamma),
+        _ => Err("Gamma must be a positive number"),
+    }
+}
+
+fn parse_cycle(cycle: &str) -> Result<f64, &'static str> {
+    match cycle.parse::<f64>() {
+        Ok(cycle) if (0_f64..1_f64).contains(&cycle) => Ok(cycle),
+        _ => Err("Palette cycle must be a fraction from 0 up to 1"),
+    }
+}
+
+fn parse_magnification(magnification: &str) -> Result<Float, String> {
+    match Float::parse(magnification) {
+        Ok(parsed) => match Float::with_val(53, parsed) {
+            magnification if magnification > 0 && magnification.is_finite() => Ok(magnification),
+            _ => Err("must be a positive number".to_owned()),
+        },
+        Err(e) => Err(format!("must be a number like 1e30: {}", e)),
+    }
+}
+
+fn parse_crop(crop: &str) -> Result<[u32; 4], &'static str> {
+    let format = "Crop must be four whole numbers of pixels like 0,0,640,360";
+    let corners: Vec<u32> = crop
+        .split(',')
+        .map(|s| s.trim().parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format)?;
+    match corners[..] {
+        [x0, y0, x1, y1] if x0 < x1 && y0 < y1 => Ok([x0, y0, x1, y1]),
+        [_, _, _, _] => Err("Crop must end below and to the right of where it starts"),
+        _ => Err(format),
+    }
 }
 
-fn parse_point(point: &str) -> Result<Complex, ParseComplexError> {
+fn parse_point(point: &str) -> Result<Complex, String> {
     let len = num_digits_log2_10(point.split(',').map(|s| {
         // get number of digits needed here as usize
         let mut d: usize = 0;
@@ -82,187 +1214,2472 @@ fn parse_point(point: &str) -> Result<Complex, ParseComplexError> {
 
         d + s.chars().filter(|c| c.is_digit(10)).count()
     }).max().unwrap());
-    let point = Complex::parse(point)?;
+    let point = Complex::parse(point).map_err(|e| {
+        format!(
+            "must be a point (real, imaginary) like (-0.8,0.156): {}",
+            e
+        )
+    })?;
     Ok(Complex::with_val(len as u32, point))
 }
 
-fn parse_range(range: &str) -> Result<(Float, Float), ParseFloatError> {
-    let (begin, end) = range.split(',').collect_tuple().unwrap();
-    let precision = num_digits_log2_10(begin.len().max(end.len()));
+fn parse_coordinate(coordinate: &str) -> Result<Float, String> {
+    let precision = num_digits_log2_10(coordinate.len());
+    parse_float(coordinate, precision)
+}
+
+/// A number at `precision`, with the text it was parsed from in any error
+fn parse_float(value: &str, precision: u32) -> Result<Float, String> {
+    Float::parse(value.trim())
+        .map(|value| Float::with_val(precision, value))
+        .map_err(|e| format!("{:?} isn't a number: {}", value, e))
+}
 
-    let begin = Float::parse(begin)?;
-    let end = Float::parse(end)?;
+fn parse_range(range: &str) -> Result<(Float, Float), String> {
+    let (begin, end) = range
+        .split(',')
+        .collect_tuple()
+        .ok_or_else(|| "must be two comma-separated values like -0.5575,-0.55".to_owned())?;
+    let precision = num_digits_log2_10(begin.len().max(end.len()));
 
-    Ok((
-        Float::with_val(precision, begin),
-        Float::with_val(precision, end),
-    ))
+    Ok((parse_float(begin, precision)?, parse_float(end, precision)?))
 }
 
+/// Bits to hold a number of `d` decimal digits, plus `PARSE_GUARD_BITS`
 fn num_digits_log2_10(d: usize) -> u32 {
     let log2_10: f64 = 10_f64.log2();
     let d = d as f64;
     let d = (d * log2_10).ceil().min(u32::MAX as f64);
-    // Always keep 4 bits beyond the requested length
-    4 + unsafe { d.to_int_unchecked::<u32>() }
+    PARSE_GUARD_BITS + unsafe { d.to_int_unchecked::<u32>() }
 }
 
-struct SquaresComplex {
-    z: Complex,
-    c: Complex,
+/// Working precision and the position of the top left pixel and
+/// spacing between pixels
+struct Bounds {
+    prec: u32,
+    /// How far the image is zoomed in, where it spans 1/(2 ^ zoom) across
+    zoom: f64,
+    x_begin: Float,
+    y_begin: Float,
+    x_step: Float,
+    y_step: Float,
 }
 
-impl Iterator for SquaresComplex {
-    type Item = ();
+/// Bits to tell apart every pixel along the longer side of the image
+fn resolution_prec(resolution: (u32, u32)) -> u32 {
+    (resolution.0.max(resolution.1).log2() + 1) as u32
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.z.square_mut();
-        self.z += &self.c;
+/// Bits for the position of an image zoomed in to `zoom`, one decimal
+/// digit per level
+fn zoom_prec(zoom: f64) -> u32 {
+    let zoom_p = zoom * 10_f64.log2();
+    let zoom_p = zoom_p.ceil().clamp(0_f64, u32::MAX as f64);
+    unsafe { zoom_p.to_int_unchecked::<u32>() }
+}
 
-        let dist = Float::with_val(5, self.z.abs_ref());
-        if dist > Float::with_val(5, 4_f32) {
-            None
-        } else {
-            Some(())
-        }
-    }
+/// Working precision of an image spanning `domain` and `range`: the bits
+/// they were given in, the pixels across them and `DOMAIN_GUARD_BITS`,
+/// plus `extra` bits
+fn domain_prec(
+    domain: &(Float, Float),
+    range: &(Float, Float),
+    resolution: (u32, u32),
+    extra: u32,
+) -> u32 {
+    let given = domain.0.prec().max(range.0.prec());
+    (resolution_prec(resolution) + DOMAIN_GUARD_BITS)
+        .saturating_add(given)
+        .saturating_add(extra)
 }
 
-fn square_iter(c: Complex) -> SquaresComplex {
-    SquaresComplex {
-        z: Complex::with_val(c.prec(), (0_f32, 0_f32)),
-        c,
-    }
+/// Working precision of an image zoomed in to `zoom`: its zoom bits, the
+/// pixels across it and `ZOOM_GUARD_BITS`, plus `extra` bits
+fn centered_prec(zoom: f64, resolution: (u32, u32), extra: u32) -> u32 {
+    zoom_prec(zoom)
+        .saturating_add(ZOOM_GUARD_BITS + resolution_prec(resolution))
+        .saturating_add(extra)
 }
 
-fn main() {
-    let args = Args::parse();
-
-    let resolution_prec: u32 = (args.resolution.0.max(args.resolution.1).log2() + 1) as u32;
-
-    let take = args.take;
-    let prec: u32;
-    let x_begin: Float;
-    let y_begin: Float;
-    let x_step: Float;
-    let y_step: Float;
-
-    if let Some((domain_start, domain_end)) = args.domain {
-        let (range_start, range_end) = args.range.expect("Domain and Range are both required");
-        prec = resolution_prec + domain_start.prec().max(range_start.prec()) + 4;
-        x_step = Float::with_val(
-            prec,
-            Float::with_val(prec, &domain_start - &domain_end)
-                / Float::with_val(prec, args.resolution.0),
-        );
-        y_step = Float::with_val(
-            prec,
-            Float::with_val(prec, &range_start - &range_end)
-                / Float::with_val(prec, args.resolution.1),
-        );
+fn domain_bounds(
+    domain: (Float, Float),
+    range: (Float, Float),
+    resolution: (u32, u32),
+    extra_precision: u32,
+) -> Bounds {
+    let prec = domain_prec(&domain, &range, resolution, extra_precision);
+    let (domain_start, domain_end) = domain;
+    let (range_start, range_end) = range;
+    let x_step = Float::with_val(
+        prec,
+        Float::with_val(prec, &domain_end - &domain_start) / Float::with_val(prec, resolution.0),
+    );
+    let y_step = Float::with_val(
+        prec,
+        Float::with_val(prec, &range_end - &range_start) / Float::with_val(prec, resolution.1),
+    );
 
-        x_begin = Float::with_val(prec, domain_start);
-        y_begin = Float::with_val(prec, range_start);
-    } else {
-        let zoom = args
-            .zoom
-            .expect("If Domain and Range are not specified, Zoom and Point are required");
+    let width = Float::with_val(53, &domain_end - &domain_start).abs();
+    Bounds {
+        prec,
+        zoom: -width.to_f64().log2(),
+        x_begin: Float::with_val(prec, domain_start),
+        y_begin: Float::with_val(prec, range_start),
+        x_step,
+        y_step,
+    }
+}
 
-        let zoom_p = zoom as f64 * 10_f64.log2();
-        let zoom_p = zoom_p.ceil().min(u32::MAX as f64);
-        let zoom_p = unsafe { zoom_p.to_int_unchecked::<u32>() };
-        prec = zoom_p + 3 + resolution_prec;
+/// Bounds of an image about `center` spanning 1/(2 ^ zoom) along each
+/// axis, or along the longer one with `aspect_lock`
+fn centered_bounds(args: &Args, center: &Complex, zoom: f64, resolution: (u32, u32)) -> Bounds {
+    let prec = centered_prec(zoom, resolution, args.extra_precision);
 
-        let step = Float::i_pow_u(2, zoom);
-        let step = Float::with_val(prec, step);
-        let step = step.recip();
+    let step = Float::with_val(prec, -zoom);
+    let step = step.exp2();
 
-        x_step = Float::with_val(prec, &step / args.resolution.0);
-        y_step = Float::with_val(prec, &step / args.resolution.1);
+    let (x_step, y_step) = if args.aspect_lock {
+        let step = Float::with_val(prec, &step / resolution.0.max(resolution.1));
+        (step.clone(), step)
+    } else {
+        (
+            Float::with_val(prec, &step / resolution.0),
+            Float::with_val(prec, &step / resolution.1),
+        )
+    };
 
-        let center = args
-            .centered_around
-            .expect("If Domain and Range are not specified, Zoom and Point are required");
+    Bounds {
+        prec,
+        zoom,
+        x_begin: center.real() - Float::with_val(prec, &x_step * (resolution.0 / 2)),
+        y_begin: center.imag() - Float::with_val(prec, &y_step * (resolution.1 / 2)),
+        x_step,
+        y_step,
+    }
+}
 
-        println!("Center: ({:?})", center);
+/// `take` for an image zoomed in to `zoom` when none is given, which
+/// grows with the zoom as deeper images need more iterations to show
+/// their detail
+fn default_take(zoom: f64) -> usize {
+    let levels = zoom.max(0_f64) / TAKE_ZOOM_LEVELS;
+    (DEFAULT_TAKE as f64 * (1_f64 + levels)) as usize
+}
 
-        x_begin = center.real() - Float::with_val(prec, &x_step * (args.resolution.0 / 2));
-        y_begin = center.imag() - Float::with_val(prec, &y_step * (args.resolution.1 / 2));
+/// Stops of the gradient escaping points are colored with
+fn gradient_stops(args: &Args) -> Stops {
+    let stops = match (&args.palette, &args.palette_from_image) {
+        (Some(path), _) => load_palette(path).unwrap_or_else(|e| {
+            fail(
+                ErrorKind::Io,
+                format!("Unable to load palette {}: {}", path, e),
+            )
+        }),
+        (None, Some(path)) => {
+            palette_from_image(path, args.palette_samples.get()).unwrap_or_else(|e| {
+                fail(
+                    ErrorKind::Io,
+                    format!("Unable to take a palette from {}: {}", path, e),
+                )
+            })
+        }
+        (None, None) => builtin_gradient(coloring_mode(args) == ColoringMode::Exponential),
+    };
+    let stops = if args.palette_reverse {
+        reverse_stops(stops)
+    } else {
+        stops
+    };
+    match args.palette_rotate_stops {
+        Some(n) => rotate_stops(stops, n),
+        None => stops,
     }
+}
 
-    println!("Bits of precision: {}", prec);
+fn render_config(args: &Args, bounds: Bounds) -> RenderConfig {
+    let lookup = |gradient: ColorGradient| match args.palette_interpolation_steps {
+        Some(steps) => gradient.with_lookup(steps),
+        None => gradient,
+    };
+    let gradient = lookup(
+        ColorGradient::new(gradient_stops(args), args.color_space).with_easing(args.palette_easing),
+    );
+    let prec = bounds.prec;
+    let zoom = bounds.zoom;
+    let (bounds, rotation) = orient(args, bounds);
 
-    let gradient = if args.exponential_gradient {
-        Gradient::with_domain([
-            (0_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
-            (0.5_f64, LinSrgb::new(0.5_f64, 0_f64, 0_f64)),
-            (1_f64, LinSrgb::new(1_f64, 0_f64, 0_f64)),
-            (2_f64, LinSrgb::new(1_f64, 0.5_f64, 0_f64)),
-            (4_f64, LinSrgb::new(0.5_f64, 1_f64, 0.5_f64)),
-            (8_f64, LinSrgb::new(0_f64, 1_f64, 1_f64)),
-            (16_f64, LinSrgb::new(0_f64, 0.5_f64, 1_f64)),
-            (32_f64, LinSrgb::new(0_f64, 0_f64, 1_f64)),
-            (64_f64, LinSrgb::new(0.25_f64, 0_f64, 1_f64)),
-            (128_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
-        ])
-    } else {
-        Gradient::with_domain([
-            (0_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
-            (0.5_f64, LinSrgb::new(0.5_f64, 0_f64, 0_f64)),
-            (1.5_f64, LinSrgb::new(1_f64, 0_f64, 0_f64)),
-            (2.5_f64, LinSrgb::new(1_f64, 0.5_f64, 0_f64)),
-            (3.5_f64, LinSrgb::new(0.5_f64, 1_f64, 0.5_f64)),
-            (4.5_f64, LinSrgb::new(0_f64, 1_f64, 1_f64)),
-            (5.5_f64, LinSrgb::new(0_f64, 0.5_f64, 1_f64)),
-            (6.5_f64, LinSrgb::new(0_f64, 0_f64, 1_f64)),
-            (7.5_f64, LinSrgb::new(0.25_f64, 0_f64, 1_f64)),
-            (8_f64, LinSrgb::new(1_f64, 1_f64, 1_f64)),
-        ])
-    };
-
-    let mut img = ImageBuffer::new(args.resolution.0, args.resolution.1);
-
-    for (x, y, p) in (0..args.resolution.0)
-        .into_par_iter()
-        .flat_map(move |x| (0..args.resolution.1).into_par_iter().map(move |y| (x, y)))
-        .filter_map(|(x, y)| {
-            let x_val = &x_begin + Float::with_val(prec, x * &x_step);
-            let y_val = &y_begin + Float::with_val(prec, y * &y_step);
-
-            let i = square_iter(Complex::with_val(prec, (x_val, y_val)))
-                .take(take)
-                .count();
-
-            let color = if args.exponential_gradient {
-                let pos = i as f64 / take as f64;
-                gradient.get(pos * 128_f64)
-            } else {
-                let pos = i % args.gradient_interval;
-                let pos = pos as f64 / args.gradient_interval as f64;
-                gradient.get(pos * 8_f64)
-            };
+    RenderConfig {
+        resolution: args.resolution,
+        precision: prec,
+        x_begin: bounds.x_begin,
+        y_begin: bounds.y_begin,
+        origin: (0, 0),
+        x_step: bounds.x_step,
+        y_step: bounds.y_step,
+        take: args.take.unwrap_or_else(|| default_take(zoom)),
+        gradient,
+        gradient_interval: args.gradient_interval,
+        repeat_palette: args.repeat_palette,
+        coloring: coloring_mode(args),
+        palette_cycle: args.palette_cycle,
+        gradient_mode: args.gradient_mode.unwrap_or(match coloring_mode(args) {
+            ColoringMode::Exponential => GradientMode::Clamp,
+            _ => GradientMode::Wrap,
+        }),
+        background: args.background,
+        invert: args.invert,
+        interior: args.interior_coloring,
+        interior_gradient: lookup(ColorGradient::new(interior_gradient(), args.color_space)),
+        dither: args.dither,
+        gamma: args.gamma,
+        log_scale: args.log_scale,
+        fractal: args.fractal,
+        power: args.power,
+        bailout: bailout(args),
+        julia: args.julia.clone(),
+        smooth: args.smooth,
+        continue_past_bailout: args.continue_past_bailout,
+        distance_estimate: args.distance_estimate,
+        shade: args.shade,
+        light_angle: args.light_angle,
+        detect_periodicity: args.detect_periodicity,
+        supersample: args.supersample,
+        anti_aliasing: anti_aliasing_mode(args),
+        edge_dilation: args.edge_dilation,
+        variance_threshold: args.variance_threshold,
+        delta_e_threshold: args.delta_e_threshold,
+        fill_order: args.fill_order,
+        orbit_trap: args.orbit_trap,
+        trap_position: args.trap_position.clone(),
+        z0: args.z0.clone(),
+        newton: args.newton,
+        root_method: args.method,
+        stripe_density: args.stripe_density,
+        triangle_inequality: args.tia,
+        perturbation: args.perturbation,
+        series_terms: args.series_terms,
+        real_axis_fold: args.real_axis_fold,
+        reference: None,
+        rotation,
+        mask: None,
+        simd: !args.no_simd,
+        force_bignum: args.force_bignum,
+    }
+}
 
-            if i < take {
-                Some((
-                    x,
-                    y,
-                    Rgb(unsafe {
-                        [
-                            (color.red * 255_f64).to_int_unchecked::<u8>(),
-                            (color.green * 255_f64).to_int_unchecked::<u8>(),
-                            (color.blue * 255_f64).to_int_unchecked::<u8>(),
-                        ]
-                    }),
-                ))
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>()
-    {
-        img.put_pixel(x, y, p);
+/// `bounds` flipped and transposed as `args` asks, and the turn of the
+/// grid they start from
+fn orient(args: &Args, mut bounds: Bounds) -> (Bounds, Option<(Complex, f64)>) {
+    let prec = bounds.prec;
+    let mut rotation = args
+        .rotate
+        .filter(|&degrees| degrees != 0_f64)
+        .map(|degrees| {
+            let center = args
+                .centered_around
+                .as_ref()
+                .expect("Rotation is about a Point, which is required");
+            (Complex::with_val(prec, center), degrees)
+        });
+
+    if args.transpose {
+        // A quarter turn of a grid with its steps swapped and one of them
+        // reversed, about the point the image starts from, so that the
+        // real part moves with the row and not the column. Turned further
+        // about the view's center if it's rotated
+        let (center, degrees) = rotation.take().unwrap_or_else(|| {
+            (
+                Complex::with_val(prec, (&bounds.x_begin, &bounds.y_begin)),
+                0_f64,
+            )
+        });
+        let (re, im) = (center.real(), center.imag());
+        let x_begin = Float::with_val(prec, &bounds.y_begin + re) - im;
+        let y_begin = Float::with_val(prec, re + im) - &bounds.x_begin;
+        let x_step = bounds.y_step.clone();
+        let y_step = -Float::with_val(prec, &bounds.x_step);
+        bounds = Bounds {
+            x_begin,
+            y_begin,
+            x_step,
+            y_step,
+            ..bounds
+        };
+        rotation = Some((center, degrees + 90_f64));
     }
 
-    img.save(&args.output).ok();
-    println!("Output saved to: {}", args.output);
+    // The last pixel starts where the first did, so that each pixel is
+    // sampled where its mirror image was
+    let flip = |begin: &mut Float, step: &mut Float, pixels: u32| {
+        *begin += Float::with_val(prec, &*step * pixels.saturating_sub(1));
+        *step = -Float::with_val(prec, &*step);
+    };
+    if args.flip_x {
+        flip(&mut bounds.x_begin, &mut bounds.x_step, args.resolution.0);
+    }
+    if args.flip_y {
+        flip(&mut bounds.y_begin, &mut bounds.y_step, args.resolution.1);
+    }
+    (bounds, rotation)
+}
+
+/// `coloring`, or the coloring its shorthands stand for
+fn coloring_mode(args: &Args) -> ColoringMode {
+    match args.coloring {
+        Some(coloring) => coloring,
+        None if args.histogram => ColoringMode::Histogram,
+        None if args.exponential_gradient => ColoringMode::Exponential,
+        None => ColoringMode::EscapeCount,
+    }
+}
+
+/// `anti_aliasing`, or edges for its shorthand
+fn anti_aliasing_mode(args: &Args) -> AntiAliasing {
+    if args.antialias_edges_only {
+        AntiAliasing::Edges
+    } else {
+        args.anti_aliasing
+    }
+}
+
+/// Exit if `--coloring histogram` is given with an option that conflicts
+/// with `histogram`, which clap only checks for the shorthand
+fn check_coloring(args: &Args) {
+    if args.coloring != Some(ColoringMode::Histogram) {
+        return;
+    }
+    let conflicts = [
+        ("log-scale", args.log_scale),
+        ("orbit-trap", args.orbit_trap.is_some()),
+        ("newton", args.newton.is_some()),
+        ("stripe-density", args.stripe_density.is_some()),
+        ("tia", args.tia),
+        ("tile-size", args.tile_size.is_some()),
+        ("tile-output", args.tile_output.is_some()),
+    ];
+    if let Some((name, _)) = conflicts.iter().find(|(_, set)| *set) {
+        fail(
+            ErrorKind::ArgumentConflict,
+            format!("`--coloring histogram` can't be used with `--{}`", name),
+        )
+    }
+}
+
+/// Exit if `--oversize` would render too large an image, or if
+/// `--anti-aliasing adaptive` is given without samples to add or with an
+/// option it can't be used with
+fn check_anti_aliasing(args: &Args) {
+    if args.oversize == 0 || args.oversize > MAX_OVERSIZE {
+        fail(
+            ErrorKind::InvalidValue,
+            format!("`--oversize` must be from 1 to {}", MAX_OVERSIZE),
+        )
+    }
+    let (width, height) = args.resolution;
+    let pixels = width as u64 * height as u64 * args.oversize as u64 * args.oversize as u64;
+    if args.oversize > 1 && pixels > MAX_OVERSIZE_PIXELS {
+        fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "`--oversize {}` would render {} pixels, over the {} it's limited to. Lower it \
+                 or the resolution",
+                args.oversize, pixels, MAX_OVERSIZE_PIXELS
+            ),
+        )
+    }
+
+    let mode = match anti_aliasing_mode(args) {
+        AntiAliasing::Grid => return,
+        AntiAliasing::Adaptive => "adaptive",
+        AntiAliasing::Perceptual => "perceptual",
+        AntiAliasing::Edges => "edges",
+    };
+    if args.supersample < 2 {
+        fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "`--anti-aliasing {}` needs a `--supersample` of at least 2",
+                mode
+            ),
+        )
+    }
+    let conflicts = [
+        (
+            "coloring histogram",
+            coloring_mode(args) == ColoringMode::Histogram,
+        ),
+        ("save-state", args.save_state),
+        ("seed-image", args.seed_image.is_some()),
+        ("mask", args.mask.is_some()),
+    ];
+    if let Some((name, _)) = conflicts.iter().find(|(_, set)| *set) {
+        fail(
+            ErrorKind::ArgumentConflict,
+            format!("`--anti-aliasing {}` can't be used with `--{}`", mode, name),
+        )
+    }
+}
+
+/// Bits of precision the deepest image framed by `args` is rendered at,
+/// worked out without making any numbers that precise
+fn deepest_prec(args: &Args) -> Option<u32> {
+    if let (Some(domain), Some(range)) = (&args.domain, &args.range) {
+        return Some(domain_prec(
+            domain,
+            range,
+            args.resolution,
+            args.extra_precision,
+        ));
+    }
+    let zoom = match args.zoom_frames {
+        Some(_) => args.zoom_start?.max(args.zoom_end?),
+        None => zoom_level(args)?,
+    };
+    Some(centered_prec(zoom, args.resolution, args.extra_precision))
+}
+
+/// Reject bounds that leave the image a single color or are too deep to
+/// render within `max_precision`, and images too large to hold in memory,
+/// and warn when the precision chosen can't tell its pixels apart or the
+/// center file has too few digits to place the center within a pixel
+fn check_bounds(args: &Args) {
+    let spans = [("domain", &args.domain), ("range", &args.range)];
+    for (name, span) in spans {
+        if let Some((start, end)) = span {
+            if start == end {
+                fail(
+                    ErrorKind::InvalidValue,
+                    format!("`--{}` must start and end at different values", name),
+                )
+            }
+        }
+    }
+    if args.take == Some(0) {
+        fail(ErrorKind::InvalidValue, "`--take` must be at least 1")
+    }
+    // Strips are written as they're rendered, without the whole image
+    if args.tile_output.is_none() {
+        let (width, height) = args.resolution;
+        let hdr = matches!(
+            args.output
+                .as_deref()
+                .map(|output| output_format(args, output)),
+            Some(Some(ImageFormat::OpenExr))
+        );
+        let channel = if hdr { mem::size_of::<f32>() } else { 1 };
+        let bytes = width as u128 * height as u128 * 3 * channel as u128;
+        if bytes > isize::MAX as u128 {
+            fail(
+                ErrorKind::InvalidValue,
+                format!(
+                    "A {}x{} image is {} bytes, too large to hold in memory. Write it in strips \
+                     with `--tile-output` instead",
+                    width, height, bytes
+                ),
+            )
+        }
+    }
+    if args.smooth && bailout(args) <= 1_f64 {
+        fail(
+            ErrorKind::InvalidValue,
+            "Smooth coloring needs a `--bailout` above 1",
+        )
+    }
+    // z escapes at up to around bailout^2, then each step past it raises
+    // it to the power, and it has to stay within an f64
+    let growth = 2_f64
+        * bailout(args).max(std::f64::consts::E).ln()
+        * (args.power as f64).powf(args.continue_past_bailout as f64);
+    if growth > f64::MAX.ln() {
+        fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "{} steps past a bailout of {} would grow |z| too large to measure, lower \
+                 `--continue-past-bailout` or `--bailout`",
+                args.continue_past_bailout,
+                bailout(args)
+            ),
+        )
+    }
+    if let Some(prec) = deepest_prec(args).filter(|&prec| prec > args.max_precision) {
+        fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "The image needs {} bits of precision, over the `--max-precision` of {}. It's \
+                 zoomed in too deep for these settings, raise `--max-precision` to render it \
+                 anyway",
+                prec, args.max_precision
+            ),
+        )
+    }
+
+    if let (Some(path), Some(center), Some(zoom)) =
+        (&args.center_file, &args.centered_around, zoom_level(args))
+    {
+        let (real, imag) = center.prec();
+        let given = (real.max(imag) - PARSE_GUARD_BITS) as f64 / 10_f64.log2();
+        let needed = (zoom.max(0_f64) + resolution_prec(args.resolution) as f64) / 10_f64.log2();
+        if given.floor() < needed.ceil() {
+            eprintln!(
+                "Warning: the center in {} has {} digits, but zoom {} needs {} to place it \
+                 within a pixel. Was the file cut short?",
+                path,
+                given.floor(),
+                zoom,
+                needed.ceil()
+            );
+        }
+    }
+
+    let framed = (args.domain.is_some() && args.range.is_some())
+        || (args.domain.is_none() && args.centered_around.is_some() && zoom_level(args).is_some());
+    if !framed || args.zoom_frames.is_some() {
+        return;
+    }
+    // Bits past the leading one of the largest coordinate that it takes
+    // to step from one pixel to the next
+    let bounds = image_bounds(args, args.resolution);
+    let end = |begin: &Float, step: &Float, pixels: u32| {
+        let end = Float::with_val(53, step * pixels) + begin.to_f64();
+        begin.to_f64().abs().max(end.to_f64().abs())
+    };
+    let largest = end(&bounds.x_begin, &bounds.x_step, args.resolution.0).max(end(
+        &bounds.y_begin,
+        &bounds.y_step,
+        args.resolution.1,
+    ));
+    let step = bounds
+        .x_step
+        .to_f64()
+        .abs()
+        .min(bounds.y_step.to_f64().abs());
+    let needed = (largest / step).log2().ceil() + 1_f64;
+    if (bounds.prec as f64) < needed {
+        eprintln!(
+            "Warning: {} bits of precision can't tell every pixel apart, which needs {}. \
+             Try `--extra-precision {}`",
+            bounds.prec,
+            needed,
+            needed - bounds.prec as f64
+        );
+    }
+}
+
+/// Report how long `phase` has taken since `start`, when `verbose`
+fn timing(args: &Args, phase: &str, start: Instant) {
+    if args.verbose {
+        status!(args, "{}: {:.3?}", phase, start.elapsed());
+    }
+}
+
+/// `config` with the `take` chosen by `auto_take`, when asked for
+fn choose_take(args: &Args, config: RenderConfig) -> RenderConfig {
+    if args.auto_take {
+        let start = Instant::now();
+        let take = auto_take(&config);
+        timing(args, "Choosing take", start);
+        status!(args, "Take: {}", take);
+        RenderConfig { take, ..config }
+    } else {
+        if args.take.is_none() {
+            status!(args, "Take: {}", config.take);
+        }
+        config
+    }
+}
+
+/// Settings to record alongside the image, keyed by the option that
+/// sets them. Frames of a zoom animation also record their number
+fn metadata(args: &Args, config: &RenderConfig, frame: Option<u32>) -> Vec<(&'static str, String)> {
+    // Enough digits that the value parses back exactly
+    fn exact(f: &Float) -> String {
+        f.to_string_radix(10, Some(f.prec() as usize))
+    }
+    fn point(c: &Complex) -> String {
+        format!("({},{})", exact(c.real()), exact(c.imag()))
+    }
+
+    let mut text = vec![(
+        "Software",
+        format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+    )];
+
+    if let Some(frame) = frame {
+        text.push(("Frame", frame.to_string()));
+        if let (Some(center), Some(frames), Some(start), Some(end)) = (
+            &args.centered_around,
+            args.zoom_frames,
+            args.zoom_start,
+            args.zoom_end,
+        ) {
+            text.push(("centered-around", point(center)));
+            text.push(("zoom-frames", frames.to_string()));
+            text.push(("zoom-start", start.to_string()));
+            text.push(("zoom-end", end.to_string()));
+            if let Some(easing) = args.zoom_easing.to_possible_value() {
+                text.push(("zoom-easing", easing.get_name().to_owned()));
+            }
+        }
+    } else if let (Some(domain), Some(range)) = (&args.domain, &args.range) {
+        text.push((
+            "domain",
+            format!("{},{}", exact(&domain.0), exact(&domain.1)),
+        ));
+        text.push(("range", format!("{},{}", exact(&range.0), exact(&range.1))));
+    } else if let (Some(domain), Some(line)) = (&args.domain, &args.line) {
+        text.push((
+            "domain",
+            format!("{},{}", exact(&domain.0), exact(&domain.1)),
+        ));
+        text.push(("line", exact(line)));
+    } else if let (Some(center), Some(zoom)) = (&args.centered_around, zoom_level(args)) {
+        text.push(("centered-around", point(center)));
+        text.push(("zoom", zoom.to_string()));
+    }
+    if let Some(degrees) = args.rotate {
+        text.push(("rotate", degrees.to_string()));
+    }
+    // Flags are recorded without a value
+    for (flag, set) in [
+        ("flip-x", args.flip_x),
+        ("flip-y", args.flip_y),
+        ("transpose", args.transpose),
+    ] {
+        if set {
+            text.push((flag, String::new()));
+        }
+    }
+
+    text.push((
+        "resolution",
+        format!("{}x{}", args.resolution.0, args.resolution.1),
+    ));
+    if let Some([x0, y0, x1, y1]) = args.crop {
+        text.push(("crop", format!("{},{},{},{}", x0, y0, x1, y1)));
+    }
+    text.push(("take", config.take.to_string()));
+    if let Some(fractal) = args.fractal.to_possible_value() {
+        text.push(("fractal", fractal.get_name().to_owned()));
+    }
+    text.push(("power", args.power.to_string()));
+    text.push(("bailout", bailout(args).to_string()));
+    if let Some(julia) = &args.julia {
+        text.push(("julia", point(julia)));
+    }
+    if !args.z0.is_zero() {
+        text.push(("z0", point(&args.z0)));
+    }
+    if let Some(degree) = args.newton {
+        text.push(("newton", degree.to_string()));
+        if let Some(method) = args.method.to_possible_value() {
+            text.push(("method", method.get_name().to_owned()));
+        }
+    }
+    if args.extra_precision > 0 {
+        text.push(("extra-precision", args.extra_precision.to_string()));
+    }
+
+    text
+}
+
+fn has_extension(output: &str, extension: &str) -> bool {
+    matches!(
+        Path::new(output).extension().and_then(|e| e.to_str()),
+        Some(e) if e.eq_ignore_ascii_case(extension)
+    )
+}
+
+/// Format `output` is saved in, from `output_format` or its extension
+fn output_format(args: &Args, output: &str) -> Option<ImageFormat> {
+    match args.output_format {
+        Some(format) => Some(format.image_format()),
+        None if output == STDOUT => Some(ImageFormat::Png),
+        None => ImageFormat::from_path(output).ok(),
+    }
+}
+
+/// Encode `img` in `format` with the encoder settings in `args`, with
+/// `text` recorded alongside it in PNGs
+fn encode(
+    args: &Args,
+    img: &DynamicImage,
+    writer: &mut (impl Write + Seek),
+    format: ImageFormat,
+    text: &[(&str, String)],
+) -> Result<(), String> {
+    // HDR images are saved in full
+    let quantized = match (args.color_count, img) {
+        (Some(colors), DynamicImage::ImageRgb8(img)) => Some(quantize(img, colors, args.quantizer)),
+        _ => None,
+    };
+    let reduced;
+    let img = match &quantized {
+        Some(quantized) => {
+            reduced = DynamicImage::ImageRgb8(quantized.to_rgb());
+            &reduced
+        }
+        None => img,
+    };
+
+    match (img, format) {
+        (_, ImageFormat::Png) if quantized.is_some() => write_indexed_png(
+            quantized.as_ref().expect("Checked by the guard"),
+            writer,
+            args.png_compression.compression(),
+            text,
+        )
+        .map_err(|e| e.to_string()),
+        (DynamicImage::ImageRgb8(img), ImageFormat::Png) => {
+            write_png(img, writer, args.png_compression.compression(), text)
+                .map_err(|e| e.to_string())
+        }
+        (DynamicImage::ImageRgb16(img), ImageFormat::Png) => {
+            write_png16(img, writer, args.png_compression.compression(), text)
+                .map_err(|e| e.to_string())
+        }
+        (DynamicImage::ImageRgba8(img), ImageFormat::Png) => {
+            write_png_rgba(img, writer, args.png_compression.compression(), text)
+                .map_err(|e| e.to_string())
+        }
+        (img, ImageFormat::Jpeg) => img
+            .write_to(writer, ImageOutputFormat::Jpeg(args.jpeg_quality))
+            .map_err(|e| e.to_string()),
+        (img, format) => img.write_to(writer, format).map_err(|e| e.to_string()),
+    }
+}
+
+fn save(
+    args: &Args,
+    img: &DynamicImage,
+    output: &str,
+    format: Option<ImageFormat>,
+    text: &[(&str, String)],
+) {
+    let saved = match format {
+        Some(format) if output == STDOUT => {
+            let mut bytes = Cursor::new(Vec::new());
+            encode(args, img, &mut bytes, format, text).and_then(|()| {
+                let mut stdout = io::stdout().lock();
+                stdout
+                    .write_all(bytes.get_ref())
+                    .and_then(|()| stdout.flush())
+                    .map_err(|e| e.to_string())
+            })
+        }
+        // Flushed here rather than on drop, which would lose any error
+        // writing the end of the file
+        Some(format) => File::create(output)
+            .map(BufWriter::new)
+            .map_err(|e| e.to_string())
+            .and_then(|mut file| {
+                encode(args, img, &mut file, format, text)?;
+                file.flush().map_err(|e| e.to_string())
+            }),
+        None => Err("the format isn't known from its extension, see --output-format".to_owned()),
+    };
+    if let Err(e) = saved {
+        fail(ErrorKind::Io, format!("Unable to save {}: {}", output, e))
+    }
+}
+
+/// Render the image to `output`, returning the fraction of its samples
+/// that were in the set. `results` is handed the result of each pixel
+/// when the render can find them as it goes
+fn render_to_file(
+    args: &Args,
+    config: &RenderConfig,
+    output: &str,
+    text: &[(&str, String)],
+    state: Option<&mut RenderState>,
+    results: Option<&ResultSink<'_>>,
+) -> f64 {
+    let format = output_format(args, output);
+    let (mut img, inside, timed_out) = render_image(args, config, output, text, state, results);
+    if let Some(img) = img.as_mut() {
+        post_process(args, config, img);
+    }
+
+    // Strips were written as they were rendered
+    if let Some(img) = img {
+        let start = Instant::now();
+        save(args, &img, output, format, text);
+        timing(args, "Encoding", start);
+    }
+    status!(args, "Output saved to: {}", output);
+    if timed_out {
+        stop_timed_out(args, output)
+    }
+    inside
+}
+
+/// Fail for rendering past `timeout`, once what was finished is saved
+fn stop_timed_out(args: &Args, output: &str) -> ! {
+    fail(
+        ErrorKind::Io,
+        format!(
+            "Rendering took longer than the {:?} timeout, so {} wasn't finished",
+            args.timeout.unwrap_or_default(),
+            output
+        ),
+    )
+}
+
+/// The finishing touches to the rendered `img` that were asked for
+fn post_process(args: &Args, config: &RenderConfig, img: &mut DynamicImage) {
+    if let Some(segments) = args.kaleidoscope {
+        kaleidoscope(args, segments, img);
+    }
+    if let Some(amount) = args.sharpen {
+        let start = Instant::now();
+        sharpen(img, amount, args.sharpen_radius);
+        timing(args, "Sharpening", start);
+    }
+    // Drawn last so that nothing else blurs or folds their lines
+    if args.grid {
+        draw_grid(args, config, img);
+    }
+    if let Some(corner) = args.scale_bar {
+        draw_scale_bar(args, config, corner, img);
+    }
+}
+
+/// Fold `img` into a kaleidoscope of `segments` wedges
+fn kaleidoscope(args: &Args, segments: u32, img: &mut DynamicImage) {
+    let start = Instant::now();
+    match img {
+        DynamicImage::ImageRgb8(img) => kaleidoscope::kaleidoscope(img, segments),
+        DynamicImage::ImageRgb16(img) => kaleidoscope::kaleidoscope(img, segments),
+        DynamicImage::ImageRgb32F(img) => kaleidoscope::kaleidoscope(img, segments),
+        _ => {}
+    }
+    timing(args, "Folding the kaleidoscope", start);
+}
+
+/// Draw the grid over `img`, in its color at the image's bit depth
+fn draw_grid(args: &Args, config: &RenderConfig, img: &mut DynamicImage) {
+    let start = Instant::now();
+    let [red, green, blue] = args.grid_color;
+    let spacing = args.grid_spacing.as_ref();
+    let drawn = match img {
+        DynamicImage::ImageRgb8(img) => {
+            plot::draw_grid(img, config, spacing, Rgb([red, green, blue]))
+        }
+        DynamicImage::ImageRgb16(img) => {
+            let color = Rgb(args.grid_color.map(|channel| channel as u16 * 257));
+            plot::draw_grid(img, config, spacing, color)
+        }
+        DynamicImage::ImageRgb32F(img) => {
+            plot::draw_grid(img, config, spacing, linear_color(args.grid_color))
+        }
+        _ => Ok(()),
+    };
+    if let Err(e) = drawn {
+        fail(ErrorKind::ValueValidation, e)
+    }
+    timing(args, "Drawing the grid", start);
+}
+
+/// Draw the scale bar in `corner` of `img`, in its color at the image's
+/// bit depth
+fn draw_scale_bar(args: &Args, config: &RenderConfig, corner: Corner, img: &mut DynamicImage) {
+    let color = args.scale_bar_color;
+    match img {
+        DynamicImage::ImageRgb8(img) => plot::draw_scale_bar(img, config, corner, Rgb(color)),
+        DynamicImage::ImageRgb16(img) => {
+            let color = Rgb(color.map(|channel| channel as u16 * 257));
+            plot::draw_scale_bar(img, config, corner, color)
+        }
+        DynamicImage::ImageRgb32F(img) => {
+            plot::draw_scale_bar(img, config, corner, linear_color(color))
+        }
+        _ => {}
+    }
+}
+
+/// `color` in the linear light EXR holds
+fn linear_color(color: [u8; 3]) -> Rgb<f32> {
+    let [red, green, blue] = color.map(|channel| channel as f32 / 255_f32);
+    let linear = Srgb::new(red, green, blue).into_linear();
+    Rgb([linear.red, linear.green, linear.blue])
+}
+
+/// The reference orbit kept in `seed_from_reference_file`, if it's been
+/// computed before
+fn load_reference(args: &Args) -> Option<Arc<ReferenceOrbit>> {
+    let path = args.seed_from_reference_file.as_ref()?;
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => fail(ErrorKind::Io, format!("Unable to read {}: {}", path, e)),
+    };
+    match ReferenceOrbit::read_from(BufReader::new(file)) {
+        Ok(reference) => Some(Arc::new(reference)),
+        Err(e) => fail(ErrorKind::Io, format!("Unable to read {}: {}", path, e)),
+    }
+}
+
+/// `config` with the reference orbit kept in `seed_from_reference_file`,
+/// `reference`. When it doesn't serve `config`, a new one is computed for
+/// the image `deepest` frames and saved over it
+fn with_reference(
+    args: &Args,
+    config: RenderConfig,
+    reference: &mut Option<Arc<ReferenceOrbit>>,
+    deepest: impl FnOnce(&RenderConfig) -> RenderConfig,
+) -> RenderConfig {
+    let path = match &args.seed_from_reference_file {
+        Some(path) => path,
+        None => return config,
+    };
+    if !matches!(reference, Some(reference) if reference.serves(&config)) {
+        let start = Instant::now();
+        let deepest = deepest(&config);
+        let orbit = reference_orbit(&RenderConfig {
+            take: deepest.take.max(config.take),
+            ..deepest
+        });
+        let saved = File::create(path).map(BufWriter::new).and_then(|mut file| {
+            orbit.write_to(&mut file)?;
+            file.flush()
+        });
+        if let Err(e) = saved {
+            fail(ErrorKind::Io, format!("Unable to save {}: {}", path, e))
+        }
+        timing(args, "Computing the reference orbit", start);
+        status!(args, "Reference orbit saved to: {}", path);
+        *reference = Some(Arc::new(orbit));
+    }
+    RenderConfig {
+        reference: reference.clone(),
+        ..config
+    }
+}
+
+/// Render the image with the regions listed in the file at `path`
+/// rendered over it, and save it to `output`
+fn render_composite(
+    args: &Args,
+    config: &RenderConfig,
+    output: &str,
+    text: &[(&str, String)],
+    path: &str,
+) {
+    let list = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| fail(ErrorKind::Io, format!("Unable to read {}: {}", path, e)));
+    let (img, _, mut timed_out) = render_image(args, config, output, text, None, None);
+    let mut img = img.expect("Composited images are rendered whole");
+
+    for (number, line) in list.lines().enumerate() {
+        if timed_out {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let number = number + 1;
+        let words = split_words(line).unwrap_or_else(|e| fail(ErrorKind::InvalidValue, e));
+        let (x, y) = match words.first().map(|at| parse_region_at(at)) {
+            Some(Ok(at)) => at,
+            Some(Err(e)) => fail(
+                ErrorKind::InvalidValue,
+                format!("Region on line {} of {}: {}", number, path, e),
+            ),
+            None => unreachable!("Blank lines are skipped"),
+        };
+
+        let start = Instant::now();
+        let argv = [String::new()]
+            .into_iter()
+            .chain(words[1..].iter().cloned())
+            .chain([format!("--output={}", output)])
+            .collect();
+        let mut region = Args::try_parse_from(with_settings(argv)).unwrap_or_else(|e| abort(e));
+        // Reported on like the rest of the image
+        region.quiet |= args.quiet;
+        region.verbose |= args.verbose;
+        check_coloring(&region);
+        check_anti_aliasing(&region);
+        check_bounds(&region);
+        if region.domain.is_none()
+            && (region.centered_around.is_none() || zoom_level(&region).is_none())
+        {
+            fail(
+                ErrorKind::MissingRequiredArgument,
+                format!(
+                    "Region on line {} of {} isn't framed, give it `--domain` and `--range` or \
+                     `--centered-around` and `--zoom`",
+                    number, path
+                ),
+            )
+        }
+        let (width, height) = region.resolution;
+        if x as u64 + width as u64 > img.width() as u64
+            || y as u64 + height as u64 > img.height() as u64
+        {
+            fail(
+                ErrorKind::InvalidValue,
+                format!(
+                    "Region on line {} of {} doesn't fit: {}x{} at {},{} runs off the {}x{} image",
+                    number,
+                    path,
+                    width,
+                    height,
+                    x,
+                    y,
+                    img.width(),
+                    img.height()
+                ),
+            )
+        }
+
+        status!(
+            args,
+            "Region {}: {}x{} at {},{}",
+            number,
+            width,
+            height,
+            x,
+            y
+        );
+        let bounds = image_bounds(&region, region.resolution);
+        let region_config = choose_take(&region, render_config(&region, bounds));
+        timing(args, "Choosing precision", start);
+        let (region_img, _, region_timed_out) =
+            render_image(&region, &region_config, output, text, None, None);
+        let region_img = region_img.expect("Regions are rendered whole");
+        timed_out |= region_timed_out;
+        match (&mut img, region_img) {
+            // Kept in linear light, rather than through 8-bit RGBA
+            (DynamicImage::ImageRgb32F(img), DynamicImage::ImageRgb32F(region_img)) => {
+                imageops::replace(img, &region_img, x as i64, y as i64)
+            }
+            (DynamicImage::ImageRgb16(img), region_img) => {
+                imageops::replace(img, &region_img.into_rgb16(), x as i64, y as i64)
+            }
+            (img, region_img) => imageops::replace(img, &region_img, x as i64, y as i64),
+        }
+    }
+
+    post_process(args, config, &mut img);
+    let start = Instant::now();
+    save(args, &img, output, output_format(args, output), text);
+    timing(args, "Encoding", start);
+    status!(args, "Output saved to: {}", output);
+    if timed_out {
+        stop_timed_out(args, output)
+    }
+}
+
+/// The pixel a listed region goes at, from x,y
+fn parse_region_at(at: &str) -> Result<(u32, u32), &'static str> {
+    let format = "it must start with the pixel it goes at, like 40,40";
+    match at.split_once(',') {
+        Some((x, y)) => Ok((
+            x.trim().parse().map_err(|_| format)?,
+            y.trim().parse().map_err(|_| format)?,
+        )),
+        None => Err(format),
+    }
+}
+
+/// Render the image for `output` with a progress bar, returning it and
+/// the fraction of its samples that were in the set. None when it was
+/// written to `output` in strips as it was rendered. Only plain renders
+/// hand `results` anything. Also whether it was stopped by `timeout`
+fn render_image(
+    args: &Args,
+    config: &RenderConfig,
+    output: &str,
+    text: &[(&str, String)],
+    state: Option<&mut RenderState>,
+    results: Option<&ResultSink<'_>>,
+) -> (Option<DynamicImage>, f64, bool) {
+    let bar = if args.quiet {
+        ProgressBar::hidden()
+    } else {
+        let oversize = args.oversize as u64 * args.oversize as u64;
+        ProgressBar::new(args.resolution.0 as u64 * args.resolution.1 as u64 * oversize)
+    };
+    bar.set_style(
+        ProgressStyle::with_template("[{elapsed_precise}] {wide_bar} {percent}% (eta {eta}) {msg}")
+            .expect("Progress bar template is valid"),
+    );
+
+    // EXR holds the linear light values as they were rendered
+    let format = output_format(args, output);
+    let hdr = format == Some(ImageFormat::OpenExr);
+    if hdr && args.tile_size.is_some() {
+        fail(
+            ErrorKind::ArgumentConflict,
+            "EXR output can't be rendered in tiles",
+        )
+    }
+    let deep = args.bit_depth == 16;
+    if deep && !matches!(format, Some(ImageFormat::Png | ImageFormat::Tiff)) {
+        fail(
+            ErrorKind::ArgumentConflict,
+            "Only PNG and TIFF output can be 16-bit",
+        )
+    }
+    if args.alpha && (deep || !matches!(format, Some(ImageFormat::Png | ImageFormat::Tiff))) {
+        fail(
+            ErrorKind::ArgumentConflict,
+            "Only 8-bit PNG and TIFF output can have an alpha channel",
+        )
+    }
+    if args.tile_output.is_some() && format != Some(ImageFormat::Png) {
+        fail(
+            ErrorKind::ArgumentConflict,
+            "Only PNG output can be written in strips",
+        )
+    }
+
+    let addresses: Vec<String> = args
+        .distributed
+        .iter()
+        .flat_map(|addresses| addresses.split(','))
+        .map(|address| address.trim().to_owned())
+        .filter(|address| !address.is_empty())
+        .collect();
+    let options = if addresses.is_empty() {
+        Vec::new()
+    } else {
+        worker_options(config)
+    };
+
+    let progress = Progress::default();
+    let done = AtomicBool::new(false);
+    let deadline = args.timeout.map(|timeout| Instant::now() + timeout);
+    let (img, elapsed) = thread::scope(|s| {
+        s.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                    progress.cancelled.store(true, Ordering::Relaxed);
+                }
+                bar.set_position(progress.pixels.load(Ordering::Relaxed));
+                thread::sleep(Duration::from_millis(100));
+            }
+        });
+
+        let start = Instant::now();
+        let render = || match (args.tile_output, args.tile_size) {
+            (Some(rows), _) => write_strips(args, config, rows.get(), output, text, &progress)
+                .map(|()| None)
+                .map_err(|e| format!("Unable to save {}: {}", output, e)),
+            (None, Some(tile_size)) => render_tiled(
+                config,
+                Tiling {
+                    size: tile_size,
+                    order: args.tile_order,
+                },
+                match args.distributed {
+                    Some(_) => Renderers::Workers {
+                        addresses: &addresses,
+                        options: &options,
+                    },
+                    None => Renderers::Threads(args.threads_per_tile),
+                },
+                &checkpoint_dir(output),
+                args.resume,
+                text,
+                &progress,
+            )
+            .map(|img| Some(DynamicImage::ImageRgb8(img)))
+            .map_err(|e| format!("Unable to render in tiles: {}", e)),
+            (None, None) => Ok(Some(match (state, hdr) {
+                (Some(state), true) => {
+                    DynamicImage::ImageRgb32F(render_hdr_with_state(config, state, &progress))
+                }
+                (Some(state), false) if deep => {
+                    DynamicImage::ImageRgb16(render_16_with_state(config, state, &progress))
+                }
+                (Some(state), false) => {
+                    DynamicImage::ImageRgb8(render_with_state(config, state, &progress))
+                }
+                (None, true) if args.oversize > 1 => DynamicImage::ImageRgb32F(
+                    render_oversized_hdr(config, args.oversize, &progress),
+                ),
+                (None, false) if args.oversize > 1 && deep => {
+                    DynamicImage::ImageRgb16(render_oversized_16(config, args.oversize, &progress))
+                }
+                (None, false) if args.oversize > 1 => {
+                    DynamicImage::ImageRgb8(render_oversized(config, args.oversize, &progress))
+                }
+                (None, true) => {
+                    DynamicImage::ImageRgb32F(render_hdr_with_progress(config, &progress))
+                }
+                (None, false) if args.alpha => {
+                    DynamicImage::ImageRgba8(render_rgba_with_progress(config, &progress))
+                }
+                (None, false) if deep => {
+                    DynamicImage::ImageRgb16(render_16_with_progress(config, &progress))
+                }
+                (None, false) => DynamicImage::ImageRgb8(match results {
+                    Some(results) => render_with_results(config, &progress, results),
+                    None => render_with_progress(config, &progress),
+                }),
+            })),
+        };
+        // The progress thread has to be stopped even if the render panics,
+        // as `fail` does for a bad job in a batch, or the scope waits on it
+        // forever
+        let img = panic::catch_unwind(AssertUnwindSafe(render));
+        done.store(true, Ordering::Relaxed);
+        let img = img.unwrap_or_else(|cause| panic::resume_unwind(cause));
+        // Timed before the progress thread wakes up to finish
+        (img, start.elapsed())
+    });
+    let img = img.unwrap_or_else(|e| fail(ErrorKind::Io, e));
+    bar.set_position(progress.pixels.load(Ordering::Relaxed));
+    bar.finish_with_message(format!("Rendered in {}", HumanDuration(bar.elapsed())));
+    let inside = progress.inside_fraction();
+    if args.verbose {
+        let pixels = config.resolution.0 as f64 * config.resolution.1 as f64;
+        status!(
+            args,
+            "Rendering: {:.3?}, {:.0} pixels per second, {:.1}% of samples in the set",
+            elapsed,
+            pixels / elapsed.as_secs_f64(),
+            inside * 100_f64
+        );
+        let non_finite = progress.non_finite.load(Ordering::Relaxed);
+        if non_finite > 0 {
+            status!(
+                args,
+                "{} samples colored NaN or infinite were given the background",
+                non_finite
+            );
+        }
+    }
+    (img, inside, progress.cancelled.load(Ordering::Relaxed))
+}
+
+/// Render the image to `output` with `render_strips`, `rows` at a time
+fn write_strips(
+    args: &Args,
+    config: &RenderConfig,
+    rows: u32,
+    output: &str,
+    text: &[(&str, String)],
+    progress: &Progress,
+) -> Result<(), String> {
+    let writer: Box<dyn Write> = if output == STDOUT {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(File::create(output).map_err(|e| e.to_string())?)
+    };
+    let compression = args.png_compression.compression();
+    let mut writer = BufWriter::new(writer);
+    render_strips(config, rows, &mut writer, compression, text, progress)
+        .map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Start the animated PNG of `frames` zoom frames at `output`, with the
+/// settings of the animation from `text`, those of its first frame
+fn start_animation(
+    args: &Args,
+    output: &str,
+    frames: u32,
+    text: &[(&str, String)],
+) -> png::Writer<BufWriter<Box<dyn Write>>> {
+    let writer: Box<dyn Write> = if output == STDOUT {
+        Box::new(io::stdout().lock())
+    } else {
+        match File::create(output) {
+            Ok(file) => Box::new(file),
+            Err(e) => fail(ErrorKind::Io, format!("Unable to save {}: {}", output, e)),
+        }
+    };
+    let text: Vec<_> = text
+        .iter()
+        .filter(|(keyword, _)| *keyword != "Frame")
+        .cloned()
+        .collect();
+    apng_writer(
+        BufWriter::new(writer),
+        args.resolution,
+        frames,
+        args.frame_delay,
+        args.png_compression.compression(),
+        &text,
+    )
+    .unwrap_or_else(|e| fail(ErrorKind::Io, format!("Unable to save {}: {}", output, e)))
+}
+
+/// The options a `distributed` render's workers render its tiles with:
+/// those of the command line, with the take `config` was given
+fn worker_options(config: &RenderConfig) -> Vec<String> {
+    let mut options = with_settings(std::env::args().collect());
+    options.remove(0);
+    options.push(format!("--take={}", config.take));
+    options.push(String::from("--quiet"));
+    options
+}
+
+/// Render tiles for the coordinators of `distributed` renders that
+/// connect to `address`, with the options each of them sends
+fn serve_tiles(args: &Args, address: &str) -> ! {
+    let listener = TcpListener::bind(address).unwrap_or_else(|e| {
+        fail(
+            ErrorKind::Io,
+            format!("Unable to listen at {}: {}", address, e),
+        )
+    });
+    status!(args, "Listening at {}", address);
+    // A job whose options fail only ends that job
+    IN_JOB.store(true, Ordering::Relaxed);
+    distributed::serve(listener, |options| {
+        let argv: Vec<String> = iter::once(String::from(env!("CARGO_PKG_NAME")))
+            .chain(options)
+            .collect();
+        let config = panic::catch_unwind(|| {
+            let args = Args::try_parse_from(&argv).unwrap_or_else(|e| abort(e));
+            check_coloring(&args);
+            check_anti_aliasing(&args);
+            check_bounds(&args);
+            let config = render_config(&args, image_bounds(&args, args.resolution));
+            match args.crop {
+                Some(crop) => crop_config(config, crop),
+                None => config,
+            }
+        })
+        .map_err(|e| match e.downcast_ref::<clap::Error>() {
+            Some(e) => e.to_string().trim_end().to_owned(),
+            None => String::from("The worker was unable to render the options"),
+        })?;
+        Ok(move |x, y, width, height| {
+            render_with_progress(&config.tile(x, y, width, height), &Progress::default())
+        })
+    })
+}
+
+/// `output` with the frame number appended to the file name,
+/// ie. zoom.png -> zoom_0001.png
+fn frame_path(output: &str, frame: u32) -> String {
+    let path = Path::new(output);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    path.with_file_name(format!("{}_{:04}.{}", stem, frame, extension))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn main() {
+    let start = Instant::now();
+    let argv = with_settings(std::env::args().collect());
+    let args = Args::parse_from(&argv);
+    check_coloring(&args);
+    check_anti_aliasing(&args);
+    check_bounds(&args);
+    timing(&args, "Parsing arguments", start);
+
+    if let Some(address) = &args.worker {
+        serve_tiles(&args, address)
+    }
+
+    if let Some(path) = &args.read_metadata {
+        let text = read_png_text(path).unwrap_or_else(|e| {
+            fail(
+                ErrorKind::Io,
+                format!("Unable to read metadata from {}: {}", path, e),
+            )
+        });
+        for (keyword, value) in text {
+            if keyword
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            {
+                if value.is_empty() {
+                    println!("--{}", keyword);
+                } else {
+                    println!("--{}={}", keyword, value);
+                }
+            } else {
+                println!("{}: {}", keyword, value);
+            }
+        }
+        return;
+    }
+
+    if args.dry_run {
+        dry_run(&args);
+        return;
+    }
+
+    if let Some(path) = &args.gradient_json {
+        if let Err(e) = fs::write(path, stops_json(&gradient_stops(&args))) {
+            fail(ErrorKind::Io, format!("Unable to write {}: {}", path, e))
+        }
+        status!(args, "Gradient saved to: {}", path);
+        return;
+    }
+
+    let output = args.output.as_deref().unwrap_or_default();
+    if args.palette_preview {
+        let img = render_swatch(
+            &gradient_stops(&args),
+            args.color_space,
+            args.palette_easing,
+            args.resolution,
+            args.palette_ticks,
+        );
+        let text = [(
+            "Software",
+            format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        )];
+        let img = DynamicImage::ImageRgb8(img);
+        save(&args, &img, output, output_format(&args, output), &text);
+        status!(args, "Palette saved to: {}", output);
+        return;
+    }
+    if args.stdin && output == STDOUT {
+        fail(
+            ErrorKind::ArgumentConflict,
+            "Jobs from stdin can't all be written to stdout",
+        )
+    }
+
+    let render = || {
+        if args.benchmark {
+            benchmark(&args);
+            return;
+        }
+        #[cfg(feature = "interactive")]
+        if args.interactive {
+            if let Err(e) = interactive::explore(&args, output) {
+                fail(
+                    ErrorKind::Io,
+                    format!("Unable to explore in a window: {}", e),
+                )
+            }
+            return;
+        }
+        if args.stdin {
+            render_jobs(&argv, output)
+        } else if args.watch {
+            watch(args.config.as_deref().expect("Watching requires a config"))
+        } else if let Some(path) = &args.batch {
+            render_batch(&argv, path)
+        } else {
+            render_outputs(&args, output)
+        }
+    };
+    match args.max_threads {
+        Some(threads) => ThreadPoolBuilder::new()
+            .num_threads(threads.get())
+            .build()
+            .unwrap_or_else(|e| {
+                fail(
+                    ErrorKind::Io,
+                    format!("Unable to start {} threads: {}", threads, e),
+                )
+            })
+            .install(render),
+        None => render(),
+    }
+}
+
+/// Print the precision, pixel size, `take` and memory the image framed by
+/// `args` needs, or the deepest frame of a zoom
+fn dry_run(args: &Args) {
+    let bounds = match (args.zoom_frames, &args.centered_around) {
+        (Some(_), Some(center)) => {
+            let zoom = args
+                .zoom_start
+                .expect("Zoom start is required for zoom frames")
+                .max(args.zoom_end.expect("Zoom end is required for zoom frames"));
+            centered_bounds(args, center, zoom, args.resolution)
+        }
+        _ => image_bounds(args, args.resolution),
+    };
+    let prec = bounds.prec;
+    let config = render_config(args, bounds);
+
+    println!(
+        "Bits of precision: {}{}",
+        prec,
+        if config.in_hardware() {
+            " (in hardware floats)"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "Pixel size: {:.4e} by {:.4e}",
+        config.x_step.as_abs(),
+        config.y_step.as_abs()
+    );
+    println!("Take: {}", config.take);
+
+    // Each number holds its digits in 64-bit limbs, besides its own 32
+    // bytes, and an orbit keeps a handful of complex numbers at a time
+    let number = (prec as u64).div_ceil(64) * 8 + 32;
+    println!(
+        "Memory per thread: about {} bytes",
+        ORBIT_NUMBERS * 2 * number
+    );
+    let (width, height) = args.resolution;
+    let oversize = args.oversize as u64 * args.oversize as u64;
+    println!(
+        "Image memory: about {} bytes",
+        width as u64 * height as u64 * oversize * 3
+    );
+    if args.perturbation && prec > f64::MANTISSA_DIGITS {
+        println!(
+            "Reference orbit memory: about {} bytes",
+            (config.take as u64 + 1) * 16
+        );
+    }
+}
+
+/// Render the scene of `benchmark_config` in hardware floats and print
+/// the pixels rendered per second
+fn benchmark(args: &Args) {
+    let mut config = benchmark_config(BENCHMARK_RESOLUTION, f64::MANTISSA_DIGITS);
+    config.simd = !args.no_simd;
+    let start = Instant::now();
+    render(&config);
+    let elapsed = start.elapsed();
+    let (width, height) = config.resolution;
+    println!(
+        "Benchmark: {}x{} in {:.3?}, {:.0} pixels per second",
+        width,
+        height,
+        elapsed,
+        (width * height) as f64 / elapsed.as_secs_f64()
+    );
+}
+
+/// `argv` with the settings of `config` and `bounds_from` merged in
+/// underneath the options given with them
+fn with_settings(mut argv: Vec<String>) -> Vec<String> {
+    let mut file_args = 0;
+    if let Some(path) = early_value(&argv, "config").map(str::to_owned) {
+        let file = ConfigFile::load(&path).unwrap_or_else(|e| {
+            fail(
+                ErrorKind::Io,
+                format!("Unable to load config {}: {}", path, e),
+            )
+        });
+        // Later occurrences of an option override earlier ones, so the
+        // file goes first
+        let file = file.to_args();
+        file_args = file.len();
+        argv.splice(1..1, file);
+    }
+    if let Some(path) = early_value(&argv, "bounds-from").map(str::to_owned) {
+        // Between the config file and the command line, so that it
+        // overrides the file and the command line overrides it
+        argv.splice(1 + file_args..1 + file_args, bounds_from(&path));
+    }
+    if let Some(path) = early_value(&argv, "center-file").map(str::to_owned) {
+        // Last, so that it overrides every other center
+        argv.push(format!("--centered-around={}", read_center(&path)));
+    }
+    argv
+}
+
+/// The point in the file at `path`, as `parse_point` takes it, with the
+/// spaces and line breaks its digits were wrapped with taken out
+fn read_center(path: &str) -> String {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| fail(ErrorKind::Io, format!("Unable to read {}: {}", path, e)));
+    let point: String = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(str::chars)
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let point = point
+        .strip_prefix('(')
+        .and_then(|point| point.strip_suffix(')'))
+        .unwrap_or(&point);
+    match point.split(',').collect_tuple() {
+        Some((real, imag)) if !real.is_empty() && !imag.is_empty() => {
+            format!("({},{})", real, imag)
+        }
+        _ => fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "{} must hold a center as its real and imaginary parts, split by a comma",
+                path
+            ),
+        ),
+    }
+}
+
+/// The options that frame the image at `path` as it was rendered, from
+/// the settings recorded in it
+fn bounds_from(path: &str) -> Vec<String> {
+    let text = read_png_text(path).unwrap_or_else(|e| {
+        fail(
+            ErrorKind::Io,
+            format!("Unable to read metadata from {}: {}", path, e),
+        )
+    });
+    let bounds: Vec<String> = text
+        .into_iter()
+        .filter(|(keyword, _)| BOUNDS_KEYS.contains(&keyword.as_str()))
+        .map(|(keyword, value)| {
+            if value.is_empty() {
+                format!("--{}", keyword)
+            } else {
+                format!("--{}={}", keyword, value)
+            }
+        })
+        .collect();
+
+    let framed = |key: &str| {
+        bounds
+            .iter()
+            .any(|arg| arg.starts_with(&format!("--{}=", key)))
+    };
+    // Frames of a zoom animation only record the zoom of the whole animation
+    if !framed("domain") && !(framed("centered-around") && framed("zoom")) {
+        fail(
+            ErrorKind::InvalidValue,
+            format!("{} doesn't record the bounds it was rendered with", path),
+        )
+    }
+    bounds
+}
+
+/// Render the image, or every frame of the zoom animation
+fn render_outputs(args: &Args, output: &str) {
+    if auto_bailout(args) && args.bailout.is_none() {
+        status!(args, "Bailout: {}", bailout(args));
+    }
+
+    if let Some(frames) = args.zoom_frames {
+        let center = args
+            .centered_around
+            .as_ref()
+            .expect("Zoom frames are rendered about a Point, which is required");
+        let zoom_start = args
+            .zoom_start
+            .expect("Zoom start is required for zoom frames");
+        let zoom_end = args.zoom_end.expect("Zoom end is required for zoom frames");
+
+        status!(args, "Center: ({:?})", center);
+
+        if args.animate && output_format(args, output) != Some(ImageFormat::Png) {
+            fail(
+                ErrorKind::ArgumentConflict,
+                "Only PNG output can be animated",
+            )
+        }
+        let mut animation = None;
+        let mut reference = load_reference(args);
+
+        let mut peak = 0_f64;
+        for frame in 0..frames {
+            // The zoom level is an exponent, so stepping it linearly
+            // interpolates the magnification geometrically
+            let t = if frames > 1 {
+                args.zoom_easing.ease(frame as f64 / (frames - 1) as f64)
+            } else {
+                0_f64
+            };
+            let zoom = zoom_start + (zoom_end - zoom_start) * t;
+
+            let start = Instant::now();
+            let bounds = centered_bounds(args, center, zoom, args.resolution);
+            status!(
+                args,
+                "Frame {}/{}: zoom {}, bits of precision: {}",
+                frame + 1,
+                frames,
+                zoom,
+                bounds.prec
+            );
+            let config = render_config(args, bounds);
+            timing(args, "Choosing precision", start);
+
+            let config = choose_take(args, config);
+            let config = with_reference(args, config, &mut reference, |_| {
+                // The deepest frame's reference serves every frame before it
+                let zoom = zoom_start.max(zoom_end);
+                render_config(args, centered_bounds(args, center, zoom, args.resolution))
+            });
+            let text = metadata(args, &config, Some(frame + 1));
+            let inside = if args.animate {
+                let (img, inside, timed_out) =
+                    render_image(args, &config, output, &text, None, None);
+                let start = Instant::now();
+                let writer = match &mut animation {
+                    Some(writer) => writer,
+                    None => animation.insert(start_animation(args, output, frames, &text)),
+                };
+                let mut img = img.expect("Animation frames are rendered whole");
+                post_process(args, &config, &mut img);
+                if let Err(e) = writer.write_image_data(img.as_bytes()) {
+                    fail(ErrorKind::Io, format!("Unable to save {}: {}", output, e))
+                }
+                timing(args, "Encoding", start);
+                if timed_out {
+                    stop_timed_out(args, output)
+                }
+                inside
+            } else {
+                render_to_file(
+                    args,
+                    &config,
+                    &frame_path(output, frame + 1),
+                    &text,
+                    None,
+                    None,
+                )
+            };
+            peak = peak.max(inside);
+        }
+        if let Some(writer) = animation {
+            if let Err(e) = writer.finish() {
+                fail(ErrorKind::Io, format!("Unable to save {}: {}", output, e))
+            }
+            status!(args, "Output saved to: {}", output);
+        }
+        if args.verbose {
+            status!(args, "Peak in the set: {:.1}% of samples", peak * 100_f64);
+        }
+        return;
+    }
+
+    if let Some(line) = &args.line {
+        render_line(args, line, output);
+        return;
+    }
+
+    if let Some(point) = &args.plot_orbit {
+        render_orbit(args, point, output);
+        return;
+    }
+
+    if let (None, Some(center)) = (&args.domain, &args.centered_around) {
+        status!(args, "Center: ({:?})", center);
+    }
+
+    if let Some(scale) = args.preview {
+        render_preview(args, scale.get(), output);
+    }
+
+    let start = Instant::now();
+    let bounds = image_bounds(args, args.resolution);
+    status!(args, "Bits of precision: {}", bounds.prec);
+    let config = render_config(args, bounds);
+    timing(args, "Choosing precision", start);
+
+    let config = choose_take(args, config);
+    let config = with_reference(args, config, &mut load_reference(args), RenderConfig::clone);
+    let config = RenderConfig {
+        mask: load_mask(args),
+        ..config
+    };
+    let config = match args.crop {
+        Some(crop) => crop_config(config, crop),
+        None => config,
+    };
+    let text = metadata(args, &config, None);
+    if let Some(path) = &args.reference_orbit_export {
+        export_reference_orbit(args, &config, path);
+    }
+    let mut state = if args.save_state || args.seed_image.is_some() {
+        Some(load_state(args, &config, &text))
+    } else {
+        None
+    };
+    // The results are kept from the render where it can, rather than
+    // iterating every pixel again for them
+    let wanted = args.iterations_output.is_some() || args.profile || args.profile_output.is_some();
+    let rows = Mutex::new(vec![Vec::new(); config.resolution.1 as usize]);
+    let keep = |y: u32, results: &[PixelResult]| {
+        rows.lock().expect("No render panicked holding the results")[y as usize] = results.to_vec();
+    };
+    match &args.render_region_list {
+        Some(path) => render_composite(args, &config, output, &text, path),
+        None => {
+            let keep: Option<&ResultSink> = if wanted { Some(&keep) } else { None };
+            render_to_file(args, &config, output, &text, state.as_mut(), keep);
+        }
+    }
+
+    let results = wanted.then(|| {
+        let rows = rows
+            .into_inner()
+            .expect("No render panicked holding the results");
+        if rows
+            .iter()
+            .all(|row| row.len() == config.resolution.0 as usize)
+        {
+            rows.concat()
+        } else {
+            render_iterations(&config)
+        }
+    });
+    if let (Some(path), Some(results)) = (&args.iterations_output, &results) {
+        if let Err(e) = write_iterations(path, results, config.resolution, config.take) {
+            fail(ErrorKind::Io, format!("Unable to write {}: {}", path, e))
+        }
+        status!(args, "Iterations saved to: {}", path);
+    }
+    if let Some(results) = &results {
+        let profile = Profile::new(results, config.take);
+        if args.profile {
+            for line in profile.lines() {
+                status!(args, "{}", line);
+            }
+        }
+        if let Some(path) = &args.profile_output {
+            if let Err(e) = profile.write_json(path) {
+                fail(ErrorKind::Io, format!("Unable to write {}: {}", path, e))
+            }
+            status!(args, "Profile saved to: {}", path);
+        }
+    }
+
+    if let Some(reference) = &args.compare {
+        compare(args, output, reference);
+    }
+
+    if let (true, Some(state)) = (args.save_state, &state) {
+        let path = state_path(output);
+        let saved = File::create(&path)
+            .map(BufWriter::new)
+            .and_then(|mut file| {
+                state.write_to(&mut file)?;
+                file.flush()
+            });
+        if let Err(e) = saved {
+            fail(
+                ErrorKind::Io,
+                format!("Unable to save the state to {}: {}", path.display(), e),
+            )
+        }
+        status!(args, "State saved to: {}", path.display());
+    }
+}
+
+/// Write the orbit of the center of the image `config` renders to the CSV
+/// file at `path`
+fn export_reference_orbit(args: &Args, config: &RenderConfig, path: &str) {
+    let start = Instant::now();
+    let written = File::create(path).map(BufWriter::new).and_then(|mut file| {
+        writeln!(file, "iteration,real,imaginary,modulus")?;
+        for (step, z) in reference_steps(config) {
+            let modulus = Float::with_val(53, z.abs_ref());
+            writeln!(file, "{},{},{},{}", step, z.real(), z.imag(), modulus)?;
+        }
+        file.flush()
+    });
+    if let Err(e) = written {
+        fail(ErrorKind::Io, format!("Unable to write {}: {}", path, e))
+    }
+    timing(args, "Exporting the reference orbit", start);
+    status!(args, "Reference orbit saved to: {}", path);
+}
+
+/// Report how the image saved to `output` differs from `reference`, and
+/// fail if it's by more than `compare_tolerance`
+fn compare(args: &Args, output: &str, reference: &str) {
+    if output == STDOUT {
+        fail(
+            ErrorKind::ArgumentConflict,
+            "The image is read back from `--output` to compare it, so it can't be stdout",
+        )
+    }
+    let open = |path: &str| {
+        image::open(path)
+            .unwrap_or_else(|e| fail(ErrorKind::Io, format!("Unable to read {}: {}", path, e)))
+    };
+    let difference = Difference::new(&open(output), &open(reference))
+        .unwrap_or_else(|e| fail(ErrorKind::InvalidValue, e));
+    for line in difference.lines() {
+        status!(args, "{}", line);
+    }
+    if difference.max > args.compare_tolerance {
+        fail(
+            ErrorKind::ValueValidation,
+            format!(
+                "{} differs from {} by up to {:.2}, over the tolerance of {}",
+                output, reference, difference.max, args.compare_tolerance
+            ),
+        )
+    }
+}
+
+/// The `mask` to render the image through, if one is given
+fn load_mask(args: &Args) -> Option<Arc<GrayImage>> {
+    let path = args.mask.as_ref()?;
+    let mask = image::open(path)
+        .unwrap_or_else(|e| fail(ErrorKind::Io, format!("Unable to read {}: {}", path, e)))
+        .into_luma8();
+    if mask.dimensions() != args.resolution {
+        fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "The {}x{} mask {} isn't the size of the {}x{} image",
+                mask.width(),
+                mask.height(),
+                path,
+                args.resolution.0,
+                args.resolution.1
+            ),
+        )
+    }
+    Some(Arc::new(mask))
+}
+
+/// The pixels of `config` from (x0, y0) up to (x1, y1), at the
+/// precision and take of the full image
+fn crop_config(config: RenderConfig, [x0, y0, x1, y1]: [u32; 4]) -> RenderConfig {
+    let (width, height) = config.resolution;
+    if x1 > width || y1 > height {
+        fail(
+            ErrorKind::ValueValidation,
+            format!(
+                "Crop {},{},{},{} doesn't fit in the {}x{} image",
+                x0, y0, x1, y1, width, height
+            ),
+        )
+    }
+    config.tile(x0, y0, x1 - x0, y1 - y0)
+}
+
+/// Render a job for each line of stdin with the options in `argv` and
+/// those on the line, exiting unsuccessfully if any of them failed
+fn render_jobs(argv: &[String], output: &str) {
+    IN_JOB.store(true, Ordering::Relaxed);
+
+    let mut jobs = 0;
+    let mut failed = 0;
+    for (number, line) in io::stdin().lock().lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Unable to read a job from stdin: {}", e);
+                failed += 1;
+                break;
+            }
+        };
+        // Blank lines and comments keep the numbering of a file of jobs
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        jobs += 1;
+        let job = number as u32 + 1;
+        let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+            render_job(argv, line, output, job);
+        }));
+        if rendered.is_err() {
+            eprintln!("Job {} failed: {}", job, line);
+            failed += 1;
+        }
+    }
+
+    IN_JOB.store(false, Ordering::Relaxed);
+    if failed > 0 {
+        eprintln!("{} of {} jobs failed", failed, jobs);
+        std::process::exit(1);
+    }
+}
+
+/// Render with the settings of the command line and the config file at
+/// `path`, and again each time the file changes
+fn watch(path: &str) -> ! {
+    IN_JOB.store(true, Ordering::Relaxed);
+    let modified = || fs::metadata(path).and_then(|file| file.modified()).ok();
+
+    let mut seen = modified();
+    loop {
+        let rendered = panic::catch_unwind(|| {
+            let argv = with_settings(std::env::args().collect());
+            let args = Args::try_parse_from(&argv).unwrap_or_else(|e| abort(e));
+            check_coloring(&args);
+            check_anti_aliasing(&args);
+            check_bounds(&args);
+            render_outputs(&args, args.output.as_deref().unwrap_or_default());
+        });
+        if rendered.is_err() {
+            eprintln!("Not rendered, waiting for {} to change", path);
+        }
+
+        // Until the file has changed and then stayed the same for a while
+        let mut changed = seen;
+        while changed == seen {
+            thread::sleep(WATCH_INTERVAL);
+            changed = modified();
+        }
+        loop {
+            thread::sleep(WATCH_SETTLE);
+            let latest = modified();
+            if latest == changed {
+                break;
+            }
+            changed = latest;
+        }
+        seen = changed;
+    }
+}
+
+/// Render the job on `line` of stdin, the `job`th
+fn render_job(argv: &[String], line: &str, output: &str, job: u32) {
+    let start = Instant::now();
+    let words = split_words(line).unwrap_or_else(|e| fail(ErrorKind::InvalidValue, e));
+    let args = job_args(argv, words);
+
+    let output = frame_path(args.output.as_deref().unwrap_or(output), job);
+    status!(args, "Job {}: {}", job, output);
+    timing(&args, "Parsing arguments", start);
+    render_outputs(&args, &output);
+}
+
+/// The options in `argv` with `words` added over them
+fn job_args(argv: &[String], words: Vec<String>) -> Args {
+    // The job's own settings go underneath its words, but over `argv`
+    let words = with_settings(argv[..1].iter().cloned().chain(words).collect());
+    let args = Args::try_parse_from(argv.iter().chain(&words[1..])).unwrap_or_else(|e| abort(e));
+    check_coloring(&args);
+    check_anti_aliasing(&args);
+    check_bounds(&args);
+    args
+}
+
+/// Render a view for each row of the CSV file at `path` with the options
+/// in `argv`, exiting unsuccessfully if any of them failed
+fn render_batch(argv: &[String], path: &str) {
+    let file = File::open(path)
+        .unwrap_or_else(|e| fail(ErrorKind::Io, format!("Unable to open {}: {}", path, e)));
+    IN_JOB.store(true, Ordering::Relaxed);
+
+    let mut rows = 0;
+    let mut failed = 0;
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Unable to read a row from {}: {}", path, e);
+                failed += 1;
+                break;
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if rows == 0 && matches!(fields.get(2), Some(zoom) if zoom.parse::<f64>().is_err()) {
+            continue;
+        }
+
+        rows += 1;
+        let row = number as u32 + 1;
+        let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+            render_row(argv, &fields, row);
+        }));
+        if rendered.is_err() {
+            eprintln!("Row {} failed: {}", row, line);
+            failed += 1;
+        }
+    }
+
+    IN_JOB.store(false, Ordering::Relaxed);
+    if failed > 0 {
+        eprintln!("{} of {} rows failed", failed, rows);
+        std::process::exit(1);
+    }
+}
+
+/// Render the view `fields` of the `row`th row of a batch
+fn render_row(argv: &[String], fields: &[&str], row: u32) {
+    let start = Instant::now();
+    let (real, imag, zoom, output) = match fields {
+        [real, imag, zoom, output] => (real, imag, zoom, output),
+        _ => fail(
+            ErrorKind::InvalidValue,
+            format!(
+                "Row {} has {} fields, not center_real,center_imag,zoom,output_name",
+                row,
+                fields.len()
+            ),
+        ),
+    };
+    let words = vec![
+        format!("--centered-around=({},{})", real, imag),
+        format!("--zoom={}", zoom),
+        format!("--output={}", output),
+    ];
+    let args = job_args(argv, words);
+
+    status!(args, "Row {}: {}", row, output);
+    timing(&args, "Parsing arguments", start);
+    render_outputs(&args, output);
+}
+
+/// `line` split into words at whitespace, like a shell would. Quotes keep
+/// whitespace in a word, ie. -c "(-0.75, 0.1)", and a backslash keeps the
+/// character after it
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err(format!("Trailing backslash in: {}", line)),
+            },
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("Unclosed quote in: {}", line));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Where the state of the render of `output` is saved,
+/// ie. deep.png -> deep.state
+fn state_path(output: &str) -> PathBuf {
+    Path::new(output).with_extension("state")
+}
+
+/// The state to continue the render from: that of `seed_image` if it has
+/// the same settings (`text`) apart from a smaller take, otherwise a fresh
+/// one
+fn load_state(args: &Args, config: &RenderConfig, text: &[(&str, String)]) -> RenderState {
+    let seed = match &args.seed_image {
+        Some(seed) => seed,
+        None => return RenderState::new(config),
+    };
+    let fail = |message: String| -> ! { fail(ErrorKind::Io, message) };
+
+    let recorded = read_png_text(seed)
+        .unwrap_or_else(|e| fail(format!("Unable to read metadata from {}: {}", seed, e)));
+    let settings = |keyword: &str| keyword != "take" && keyword != "Software";
+    let same = recorded
+        .iter()
+        .filter(|(keyword, _)| settings(keyword))
+        .map(|(keyword, value)| (keyword.as_str(), value.as_str()))
+        .eq(text
+            .iter()
+            .filter(|(keyword, _)| settings(keyword))
+            .map(|(keyword, value)| (*keyword, value.as_str())));
+    if !same {
+        fail(format!("{} was rendered with different settings", seed));
+    }
+
+    let seed_take = recorded
+        .iter()
+        .find(|(keyword, _)| keyword == "take")
+        .and_then(|(_, take)| take.parse::<usize>().ok());
+    if let Some(seed_take) = seed_take.filter(|&seed_take| seed_take > config.take) {
+        fail(format!(
+            "{} was rendered with a take of {}, which can't be reduced",
+            seed, seed_take
+        ));
+    }
+
+    let path = state_path(seed);
+    let state = File::open(&path)
+        .map(BufReader::new)
+        .and_then(RenderState::read_from)
+        .unwrap_or_else(|e| {
+            fail(format!(
+                "Unable to load the state of {} from {}: {}",
+                seed,
+                path.display(),
+                e
+            ))
+        });
+    if !state.fits(config) {
+        fail(format!(
+            "{} has a different number of samples to this render",
+            path.display()
+        ));
+    }
+    state
+}
+
+/// Render the image faintly, with the orbit of `point` drawn over it
+fn render_orbit(args: &Args, point: &Complex, output: &str) {
+    let start = Instant::now();
+    let bounds = image_bounds(args, args.resolution);
+    status!(args, "Bits of precision: {}", bounds.prec);
+    let config = render_config(args, bounds);
+    timing(args, "Choosing precision", start);
+
+    let config = choose_take(args, config);
+    let text = metadata(args, &config, None);
+    let (img, _, timed_out) = render_image(args, &config, output, &text, None, None);
+    let mut img = img
+        .expect("Orbits are drawn over a whole image")
+        .into_rgb8();
+
+    let start = Instant::now();
+    let steps: Vec<(usize, Complex)> = orbit(&config, point).collect();
+    // Before the first step, Julia orbits start from the point itself
+    let z0 = if config.julia.is_some() {
+        point
+    } else {
+        &config.z0
+    };
+    let path: Vec<(f64, f64)> = [pixel_of(&config, z0)]
+        .into_iter()
+        .chain(steps.iter().map(|(_, z)| pixel_of(&config, z)))
+        .collect();
+    plot::fade(&mut img);
+    plot::draw_path(&mut img, &path);
+    timing(args, "Drawing the orbit", start);
+    match steps.last() {
+        Some((step, z)) if Float::with_val(53, z.abs_ref()) > config.bailout => {
+            status!(args, "The orbit escapes on step {}", step)
+        }
+        _ => status!(args, "The orbit doesn't escape in {} steps", steps.len()),
+    }
+
+    let start = Instant::now();
+    let format = output_format(args, output);
+    save(args, &DynamicImage::ImageRgb8(img), output, format, &text);
+    timing(args, "Encoding", start);
+    status!(args, "Output saved to: {}", output);
+    if timed_out {
+        stop_timed_out(args, output)
+    }
+}
+
+/// Plot the cross-section of `domain` at the imaginary part `line`, as a
+/// CSV of escape counts or a strip one pixel high
+fn render_line(args: &Args, line: &Float, output: &str) {
+    let domain = args
+        .domain
+        .clone()
+        .expect("Domain is required for a cross-section");
+    let resolution = (args.resolution.0, 1);
+    let start = Instant::now();
+    let bounds = domain_bounds(
+        domain,
+        (line.clone(), line.clone()),
+        resolution,
+        args.extra_precision,
+    );
+    status!(args, "Bits of precision: {}", bounds.prec);
+    let config = RenderConfig {
+        resolution,
+        ..render_config(args, bounds)
+    };
+    timing(args, "Choosing precision", start);
+
+    let config = choose_take(args, config);
+
+    if !has_extension(output, "csv") {
+        render_to_file(
+            args,
+            &config,
+            output,
+            &metadata(args, &config, None),
+            None,
+            None,
+        );
+        return;
+    }
+
+    let written = File::create(output)
+        .map(BufWriter::new)
+        .and_then(|mut file| {
+            writeln!(file, "real,iterations,escaped")?;
+            for (x, result) in render_iterations(&config).iter().enumerate() {
+                let real = Float::with_val(
+                    config.precision,
+                    &config.x_begin + Float::with_val(config.precision, x * &config.x_step),
+                );
+                writeln!(file, "{},{},{}", real, result.iterations, result.escaped)?;
+            }
+            file.flush()
+        });
+    if let Err(e) = written {
+        fail(ErrorKind::Io, format!("Unable to write {}: {}", output, e))
+    }
+    status!(args, "Output saved to: {}", output);
+}
+
+/// Whether the escape radius is chosen for the coloring when none is
+/// given, as smooth coloring always wants a large one
+fn auto_bailout(args: &Args) -> bool {
+    args.auto_bailout || args.smooth
+}
+
+/// The escape radius given, or the one that suits the fractal, and with
+/// `auto_bailout` the coloring
+fn bailout(args: &Args) -> f64 {
+    if let Some(bailout) = args.bailout {
+        return bailout;
+    }
+    // Colorings measured relative to the escape radius, rather than
+    // improved by a larger one, keep the usual radius
+    let relative = args.invert
+        || args.orbit_trap.is_some()
+        || args.interior_coloring == Some(InteriorColoring::Modulus);
+    let automatic = auto_bailout(args)
+        && !relative
+        && !args.fractal.is_transcendental()
+        && args.newton.is_none();
+    if automatic && (args.stripe_density.is_some() || args.tia) {
+        AVERAGE_BAILOUT
+    } else if automatic && (args.smooth || args.distance_estimate) {
+        SMOOTH_BAILOUT
+    } else {
+        args.fractal.bailout()
+    }
+}
+
+/// The zoom level of a centered image, given as `zoom` or `magnification`
+fn zoom_level(args: &Args) -> Option<f64> {
+    match &args.magnification {
+        Some(magnification) => Some(magnification.clone().log2().to_f64()),
+        None => args.zoom,
+    }
+}
+
+/// Bounds of the single image described by `args`, at `resolution`
+fn image_bounds(args: &Args, resolution: (u32, u32)) -> Bounds {
+    if let Some(domain) = args.domain.clone() {
+        let range = args
+            .range
+            .clone()
+            .expect("Domain and Range are both required");
+        domain_bounds(domain, range, resolution, args.extra_precision)
+    } else {
+        let zoom = zoom_level(args)
+            .expect("If Domain and Range are not specified, Zoom and Point are required");
+
+        let center = args
+            .centered_around
+            .as_ref()
+            .expect("If Domain and Range are not specified, Zoom and Point are required");
+
+        centered_bounds(args, center, zoom, resolution)
+    }
+}
+
+/// Render the image at 1/`scale` the resolution and take next to
+/// `output`, and estimate the full render from how long it took
+fn render_preview(args: &Args, scale: u32, output: &str) {
+    let resolution = (
+        (args.resolution.0 / scale).max(1),
+        (args.resolution.1 / scale).max(1),
+    );
+    let config = render_config(args, image_bounds(args, resolution));
+    let full_take = config.take;
+    let take = (full_take / scale as usize).max(1);
+    let config = RenderConfig {
+        resolution,
+        take,
+        ..config
+    };
+
+    let start = Instant::now();
+    let img = render(&config);
+    let elapsed = start.elapsed();
+
+    let path = Path::new(output).with_extension("preview.png");
+    let path = path.to_string_lossy();
+    save(
+        args,
+        &DynamicImage::ImageRgb8(img),
+        &path,
+        Some(ImageFormat::Png),
+        &[],
+    );
+
+    // Every pixel iterates at most `take` times, so the full render takes
+    // at most about this much longer
+    let pixels = |(x, y): (u32, u32)| x as f64 * y as f64;
+    let factor = pixels(args.resolution) / pixels(resolution) * full_take as f64 / take as f64;
+    status!(
+        args,
+        "Preview saved to: {} in {}, full render estimated to take {}",
+        path,
+        HumanDuration(elapsed),
+        HumanDuration(elapsed.mul_f64(factor))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mandelbrot::point_of;
+
+    fn args(options: &[&str]) -> Args {
+        Args::try_parse_from(["mandelbrot", "-o", "test.png"].iter().chain(options)).unwrap()
+    }
+
+    /// What's rendered of the image `options` describe
+    fn config(options: &[&str]) -> RenderConfig {
+        let args = args(options);
+        render_config(&args, image_bounds(&args, args.resolution))
+    }
+
+    #[test]
+    fn domain_and_range_run_from_start_to_end() {
+        let config = config(&["-x", "140x80", "-d=-2.5,1.0", "-r=-1.0,1.0"]);
+        let (re, im) = point_of(&config, (70_f64, 40_f64)).into_real_imag();
+        assert_eq!((re.to_f64(), im.to_f64()), (-0.75, 0_f64));
+
+        let results = render_iterations(&config);
+        let escaped = |x: usize| results[40 * 140 + x].escaped;
+        // The middle of the image, where the main cardioid meets the
+        // period 2 bulb, and the middle of the cardioid, at -0.25
+        assert!(!escaped(70) && !escaped(90));
+        assert!(escaped(0) && escaped(139));
+    }
+
+    #[test]
+    fn precision_adds_up_its_terms() {
+        // 4 digits need 13.3 bits
+        assert_eq!(num_digits_log2_10(4), 14 + PARSE_GUARD_BITS);
+        assert_eq!(resolution_prec((1920, 1080)), 11);
+        assert_eq!(resolution_prec((1024, 1024)), 11);
+        // 10^-3 across is 9.97 zoom levels
+        assert_eq!(zoom_prec(3_f64), 10);
+        assert_eq!(zoom_prec(-1_f64), 0);
+
+        assert_eq!(
+            centered_prec(3_f64, (1920, 1080), 5),
+            10 + 11 + ZOOM_GUARD_BITS + 5
+        );
+        let range = parse_range("-1.25,1.25").unwrap();
+        assert_eq!(
+            domain_prec(&range, &range, (1920, 1080), 0),
+            num_digits_log2_10(5) + 11 + DOMAIN_GUARD_BITS
+        );
+    }
+
+    #[test]
+    fn extra_precision_adds_bits() {
+        let view = ["-c=(-0.75,0.1)", "-z", "3"];
+        let extra = [&view[..], &["--extra-precision", "7"]].concat();
+        assert_eq!(config(&extra).precision, config(&view).precision + 7);
+    }
+
+    #[test]
+    fn interpolation_steps_default_to_4096() {
+        let steps = |options: &[&str]| args(options).palette_interpolation_steps;
+        assert_eq!(steps(&[]), None);
+        assert_eq!(steps(&["--palette-interpolation-steps"]), Some(4096));
+        assert_eq!(steps(&["--palette-interpolation-steps", "-q"]), Some(4096));
+        assert_eq!(steps(&["--palette-interpolation-steps", "256"]), Some(256));
+    }
 }
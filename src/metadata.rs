@@ -0,0 +1,143 @@
+use image::{ImageBuffer, Rgb, Rgba};
+use mandelbrot::Quantized;
+use std::{
+    fs::File,
+    io::{BufReader, Write},
+};
+
+/// Start an 8-bit RGB PNG of `width` by `height`, with each
+/// `(keyword, text)` pair stored in a `tEXt` chunk ahead of the image data
+pub fn png_writer<W: Write>(
+    writer: W,
+    width: u32,
+    height: u32,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<png::Writer<W>, png::EncodingError> {
+    png_encoder(writer, width, height, compression, text)?.write_header()
+}
+
+/// Start an animated PNG of `frames` frames, each shown for `delay`
+/// milliseconds, with `text` as in `png_writer`. Each frame is written
+/// with `write_image_data`, in order
+pub fn apng_writer<W: Write>(
+    writer: W,
+    (width, height): (u32, u32),
+    frames: u32,
+    delay: u16,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<png::Writer<W>, png::EncodingError> {
+    let mut encoder = png_encoder(writer, width, height, compression, text)?;
+    // Looping forever
+    encoder.set_animated(frames, 0)?;
+    encoder.set_frame_delay(delay, 1000)?;
+    encoder.write_header()
+}
+
+fn png_encoder<W: Write>(
+    writer: W,
+    width: u32,
+    height: u32,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<png::Encoder<'static, W>, png::EncodingError> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(compression);
+    for (keyword, value) in text {
+        encoder.add_text_chunk(keyword.to_string(), value.clone())?;
+    }
+    Ok(encoder)
+}
+
+/// Encode `img` as a PNG, with `text` as in `png_writer`
+pub fn write_png(
+    img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    writer: impl Write,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<(), png::EncodingError> {
+    let mut writer = png_writer(writer, img.width(), img.height(), compression, text)?;
+    writer.write_image_data(img)?;
+    writer.finish()
+}
+
+/// Encode `img` as a 16-bit PNG, with `text` as in `png_writer`
+pub fn write_png16(
+    img: &ImageBuffer<Rgb<u16>, Vec<u16>>,
+    writer: impl Write,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<(), png::EncodingError> {
+    let mut encoder = png_encoder(writer, img.width(), img.height(), compression, text)?;
+    encoder.set_depth(png::BitDepth::Sixteen);
+    let mut writer = encoder.write_header()?;
+    // PNG stores samples most significant byte first
+    let bytes: Vec<u8> = img.iter().flat_map(|sample| sample.to_be_bytes()).collect();
+    writer.write_image_data(&bytes)?;
+    writer.finish()
+}
+
+/// Encode `img` as a PNG with an alpha channel, with `text` as in
+/// `png_writer`
+pub fn write_png_rgba(
+    img: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    writer: impl Write,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<(), png::EncodingError> {
+    let mut encoder = png_encoder(writer, img.width(), img.height(), compression, text)?;
+    encoder.set_color(png::ColorType::Rgba);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(img)?;
+    writer.finish()
+}
+
+/// Encode `img` as a PNG of indices into its palette, with `text` as in
+/// `png_writer`
+pub fn write_indexed_png(
+    img: &Quantized,
+    writer: impl Write,
+    compression: png::Compression,
+    text: &[(&str, String)],
+) -> Result<(), png::EncodingError> {
+    let (width, height) = img.indices.dimensions();
+    let mut encoder = png_encoder(writer, width, height, compression, text)?;
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_palette(
+        img.palette
+            .iter()
+            .flat_map(|color| color.0)
+            .collect::<Vec<u8>>(),
+    );
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&img.indices)?;
+    writer.finish()
+}
+
+/// Every text chunk in the PNG at `path`, in the order they were found
+pub fn read_png_text(path: &str) -> Result<Vec<(String, String)>, png::DecodingError> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let reader = decoder.read_info()?;
+    let info = reader.info();
+
+    let mut text: Vec<(String, String)> = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+    for chunk in &info.compressed_latin1_text {
+        if let Ok(value) = chunk.get_text() {
+            text.push((chunk.keyword.clone(), value));
+        }
+    }
+    for chunk in &info.utf8_text {
+        if let Ok(value) = chunk.get_text() {
+            text.push((chunk.keyword.clone(), value));
+        }
+    }
+
+    Ok(text)
+}
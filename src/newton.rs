@@ -0,0 +1,118 @@
+use crate::scalar::ComplexScalar;
+use clap::ArgEnum;
+use rug::{float::Constant, Complex, Float};
+
+/// Distance from a root within which an orbit has converged to it
+pub const TOLERANCE: f64 = 1e-6;
+
+/// Iteration that finds the roots of z^n - 1
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootMethod {
+    /// z - p / p', which converges quadratically
+    Newton,
+    /// z - 2 p p' / (2 p'^2 - p p''), which converges cubically, into
+    /// basins of a different shape
+    Halley,
+}
+
+impl RootMethod {
+    /// How many times over the digits of the distance to the root grow
+    /// with each iteration near it
+    pub fn order(self) -> f64 {
+        match self {
+            RootMethod::Newton => 2_f64,
+            RootMethod::Halley => 3_f64,
+        }
+    }
+}
+
+/// The roots of z^n - 1, the nth roots of unity, in order of angle from
+/// the positive real axis
+pub fn roots(degree: u32, prec: u32) -> Vec<Complex> {
+    (0..degree)
+        .map(|k| {
+            let angle = Float::with_val(prec, Constant::Pi) * 2_u32 * k / degree;
+            let (sin, cos) = angle.sin_cos(Float::new(prec));
+            Complex::with_val(prec, (cos, sin))
+        })
+        .collect()
+}
+
+/// Newton's method for z^n - 1, or another `RootMethod`, which ends once
+/// the orbit is within `TOLERANCE` of one of `roots`
+pub struct NewtonIter<'a, T> {
+    z: T,
+    degree: u32,
+    method: RootMethod,
+    roots: &'a [T],
+    /// Index of the root that was converged to
+    pub root: Option<usize>,
+    /// Distance to that root on the final iteration
+    pub distance: f64,
+}
+
+impl<'a, T: ComplexScalar> NewtonIter<'a, T> {
+    pub fn new(z: T, degree: u32, method: RootMethod, roots: &'a [T]) -> Self {
+        NewtonIter {
+            z,
+            degree,
+            method,
+            roots,
+            root: None,
+            distance: 0_f64,
+        }
+    }
+}
+
+impl<'a, T: ComplexScalar> Iterator for NewtonIter<'a, T> {
+    type Item = ();
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.degree;
+        self.z = match self.method {
+            RootMethod::Newton => {
+                // z - (z^n - 1) / (n * z^(n - 1)) = ((n - 1) * z^n + 1) / (n * z^(n - 1))
+                let mut z_pow = self.z.clone();
+                z_pow.pow_mut(n - 1);
+                let mut numerator = z_pow.clone();
+                numerator.mul_mut(&self.z);
+                numerator.scale_mut(n - 1);
+                numerator.add_one_mut();
+                z_pow.scale_mut(n);
+                numerator.div_mut(&z_pow);
+                numerator
+            }
+            RootMethod::Halley => {
+                // With p' = n * z^(n - 1) and p'' = n * (n - 1) * z^(n - 2),
+                // z - 2 p p' / (2 p'^2 - p p'') reduces to
+                // z * ((n - 1) * z^n + (n + 1)) / ((n + 1) * z^n + (n - 1))
+                let mut z_n = self.z.clone();
+                z_n.pow_mut(n);
+                // a * z^n + b
+                let scaled = |a: u32, b: u32| {
+                    let mut constant = z_n.zero_like();
+                    constant.add_one_mut();
+                    constant.scale_mut(b);
+                    let mut sum = z_n.clone();
+                    sum.scale_mut(a);
+                    sum.add_mut(&constant);
+                    sum
+                };
+                let mut numerator = scaled(n - 1, n + 1);
+                numerator.mul_mut(&self.z);
+                numerator.div_mut(&scaled(n + 1, n - 1));
+                numerator
+            }
+        };
+
+        for (index, root) in self.roots.iter().enumerate() {
+            let distance = self.z.distance(root);
+            if distance < TOLERANCE {
+                self.root = Some(index);
+                self.distance = distance.to_f64();
+                return None;
+            }
+        }
+        Some(())
+    }
+}
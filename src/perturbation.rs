@@ -0,0 +1,466 @@
+use crate::{
+    reference_center, scalar::ComplexScalar, Average, Averaged, OrbitEnd, OrbitTrap, RenderConfig,
+    Trap,
+};
+use rug::{Complex, Float};
+use std::io::{self, BufRead, Write};
+
+/// Below this fraction of the reference orbit's modulus, a pixel's delta
+/// has lost too much precision relative to the reference to be trusted
+/// (Pauldelbrot's glitch criterion)
+const GLITCH_TOLERANCE: f64 = 1e-3;
+
+/// Largest error, relative to the iterated delta, that the series
+/// approximation may have at a probe point
+const SERIES_TOLERANCE: f64 = 1e-9;
+
+/// Furthest the reference orbit of another image may be from the point
+/// this one's would go through and still be used for it, as a fraction of
+/// the image's width
+const REFERENCE_DRIFT: f64 = 0.25;
+
+/// Orbit of a single point iterated at full precision, which nearby
+/// points are iterated relative to in hardware floats (z^2 + c only)
+#[derive(Clone)]
+pub struct ReferenceOrbit {
+    center: Complex,
+    take: usize,
+    bailout: f64,
+    /// z_n rounded to f64, from z_0 until the reference escapes or `take`
+    orbit: Vec<(f64, f64)>,
+    series: Option<Series>,
+    /// Steps a pixel is iterated past escaping before it is measured
+    past_bailout: u32,
+}
+
+/// dz_n approximated as a polynomial in dc, so that the first `skip`
+/// iterations can be skipped for every pixel
+#[derive(Clone)]
+struct Series {
+    skip: usize,
+    /// Coefficient of dc^(k + 1) at index k
+    coefficients: Vec<(f64, f64)>,
+}
+
+impl Series {
+    /// The coefficients one iteration on, where the reference is at `z`:
+    /// A_1' = 2 * Z * A_1 + 1, A_k' = 2 * Z * A_k + sum(A_j * A_(k - j))
+    fn step(&self, z: (f64, f64)) -> Vec<(f64, f64)> {
+        let mut double_z = z;
+        double_z.scale_mut(2);
+
+        (0..self.coefficients.len())
+            .map(|k| {
+                let mut next = self.coefficients[k];
+                next.mul_mut(&double_z);
+                if k == 0 {
+                    next.add_one_mut();
+                }
+                for j in 0..k {
+                    let mut product = self.coefficients[j];
+                    product.mul_mut(&self.coefficients[k - 1 - j]);
+                    next.add_mut(&product);
+                }
+                next
+            })
+            .collect()
+    }
+
+    /// dz at `dc` after `skip` iterations
+    fn delta(coefficients: &[(f64, f64)], dc: (f64, f64)) -> (f64, f64) {
+        let mut dz = (0_f64, 0_f64);
+        for a in coefficients.iter().rev() {
+            dz.add_mut(a);
+            dz.mul_mut(&dc);
+        }
+        dz
+    }
+
+    /// d(dz)/dc, the derivative for distance estimation, after `skip`
+    /// iterations
+    fn derivative(&self, dc: (f64, f64)) -> (f64, f64) {
+        let mut der = (0_f64, 0_f64);
+        for (k, a) in self.coefficients.iter().enumerate().rev() {
+            der.mul_mut(&dc);
+            let mut term = *a;
+            term.scale_mut(k as u32 + 1);
+            der.add_mut(&term);
+        }
+        der
+    }
+}
+
+impl ReferenceOrbit {
+    pub(crate) fn new(center: Complex, take: usize, bailout: f64) -> Self {
+        let mut z = center.zero_like();
+        let mut orbit = Vec::with_capacity(take + 1);
+        orbit.push(<(f64, f64)>::from_complex(&z));
+
+        // An orbit on the real axis never leaves it, so only the real part
+        // has to be iterated
+        if center.imag().is_zero() {
+            let c = center.real();
+            let mut x = Float::new(c.prec());
+            for _ in 0..take {
+                x.square_mut();
+                x += c;
+                orbit.push((x.to_f64(), 0_f64));
+                if *x.as_abs() > bailout {
+                    break;
+                }
+            }
+            return ReferenceOrbit {
+                center,
+                take,
+                bailout,
+                orbit,
+                series: None,
+                past_bailout: 0,
+            };
+        }
+
+        for _ in 0..take {
+            z.square_mut();
+            z += &center;
+            orbit.push(<(f64, f64)>::from_complex(&z));
+            if z.exceeds(bailout) {
+                break;
+            }
+        }
+
+        ReferenceOrbit {
+            center,
+            take,
+            bailout,
+            orbit,
+            series: None,
+            past_bailout: 0,
+        }
+    }
+
+    /// Whether this orbit, computed for another image, can be used to
+    /// render the image `config` frames: it is at least as precise, runs
+    /// as long, escapes the same way and goes through a point close to the
+    /// one that image's reference would
+    pub fn serves(&self, config: &RenderConfig) -> bool {
+        let (real_prec, imag_prec) = self.center.prec();
+        let escaped = self.orbit.len() <= self.take;
+        if real_prec.min(imag_prec) < config.precision
+            || (self.take < config.take && !escaped)
+            || self.bailout != config.bailout
+        {
+            return false;
+        }
+        let drift = Complex::with_val(self.center.prec(), &self.center - reference_center(config));
+        let width = Float::with_val(53, &config.x_step * config.resolution.0).abs();
+        let limit = width * REFERENCE_DRIFT;
+        *drift.real().as_abs() <= limit && *drift.imag().as_abs() <= limit
+    }
+
+    /// Write the center, in exact hexadecimal, with `take` and the
+    /// bailout, then z_n one per line
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let (real_prec, imag_prec) = self.center.prec();
+        writeln!(
+            writer,
+            "{} {} {}",
+            real_prec.max(imag_prec),
+            self.center.real().to_string_radix(16, None),
+            self.center.imag().to_string_radix(16, None)
+        )?;
+        writeln!(writer, "{} {}", self.take, self.bailout)?;
+        for (re, im) in &self.orbit {
+            writeln!(writer, "{} {}", re, im)?;
+        }
+        Ok(())
+    }
+
+    /// Read the orbit written by `write_to`
+    pub fn read_from(reader: impl BufRead) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid reference orbit: {}", line),
+            )
+        };
+
+        let mut lines = reader.lines();
+        let mut next_line = || {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(invalid("it ends early")))
+        };
+        let line = next_line()?;
+        let center = match line.split(' ').collect::<Vec<_>>()[..] {
+            [prec, real, imag] => {
+                let prec: u32 = prec.parse().map_err(|_| invalid(&line))?;
+                let parse = |part: &str| {
+                    Float::parse_radix(part, 16)
+                        .map(|part| Float::with_val(prec, part))
+                        .map_err(|_| invalid(&line))
+                };
+                Complex::with_val(prec, (parse(real)?, parse(imag)?))
+            }
+            _ => return Err(invalid(&line)),
+        };
+        let line = next_line()?;
+        let (take, bailout) = match line.split(' ').collect::<Vec<_>>()[..] {
+            [take, bailout] => (
+                take.parse().map_err(|_| invalid(&line))?,
+                bailout.parse().map_err(|_| invalid(&line))?,
+            ),
+            _ => return Err(invalid(&line)),
+        };
+
+        let mut orbit = Vec::new();
+        for line in lines {
+            let line = line?;
+            let z = match line.split(' ').collect::<Vec<_>>()[..] {
+                [re, im] => (
+                    re.parse().map_err(|_| invalid(&line))?,
+                    im.parse().map_err(|_| invalid(&line))?,
+                ),
+                _ => return Err(invalid(&line)),
+            };
+            orbit.push(z);
+        }
+        // Pixels are perturbed from each step to the next, up to `take` or
+        // the step the reference escapes on
+        if orbit.len() < 2 {
+            return Err(invalid("it has no steps"));
+        }
+        let steps = orbit.len() - 1;
+        if steps > take {
+            return Err(invalid(&format!("it has more than {} steps", take)));
+        }
+        if steps < take && !orbit[steps].exceeds(bailout) {
+            return Err(invalid(&format!(
+                "it stops after {} of {} steps without escaping",
+                steps, take
+            )));
+        }
+
+        Ok(ReferenceOrbit {
+            center,
+            take,
+            bailout,
+            orbit,
+            series: None,
+            past_bailout: 0,
+        })
+    }
+
+    /// Carry on iterating escaped pixels for `steps` more steps, from
+    /// where they escaped, before measuring them
+    pub(crate) fn with_steps_past_bailout(mut self, steps: u32) -> Self {
+        self.past_bailout = steps;
+        self
+    }
+
+    fn delta_c(&self, point: &Complex) -> (f64, f64) {
+        <(f64, f64)>::from_complex(&Complex::with_val(point.prec(), point - &self.center))
+    }
+
+    /// Approximate the first iterations of every pixel with a series of
+    /// `terms` terms, skipping for as long as it stays accurate at each of
+    /// the `probes` (points spread across the image)
+    pub(crate) fn with_series(mut self, terms: usize, probes: &[Complex], bailout: f64) -> Self {
+        let probes: Vec<_> = probes.iter().map(|probe| self.delta_c(probe)).collect();
+        let mut deltas = vec![(0_f64, 0_f64); probes.len()];
+        let mut series = Series {
+            skip: 0,
+            coefficients: vec![(0_f64, 0_f64); terms],
+        };
+
+        for n in 0..self.orbit.len() - 1 {
+            let reference = self.orbit[n];
+            let next = self.orbit[n + 1];
+            let coefficients = series.step(reference);
+
+            let accurate = probes.iter().zip(&mut deltas).all(|(dc, dz)| {
+                let mut factor = reference;
+                factor.scale_mut(2);
+                factor.add_mut(dz);
+                dz.mul_mut(&factor);
+                dz.add_mut(dc);
+
+                let mut z = next;
+                z.add_mut(dz);
+
+                let approximate = Series::delta(&coefficients, *dc);
+                let mut error = approximate;
+                error.0 -= dz.0;
+                error.1 -= dz.1;
+
+                // Pixels that escape within the skipped iterations would
+                // be given the wrong count
+                !z.exceeds(bailout)
+                    && approximate.0.is_finite()
+                    && approximate.1.is_finite()
+                    && norm(error) <= SERIES_TOLERANCE * SERIES_TOLERANCE * norm(*dz)
+            });
+            if !accurate {
+                break;
+            }
+
+            series.coefficients = coefficients;
+            series.skip = n + 1;
+        }
+
+        self.series = Some(series);
+        self
+    }
+
+    /// Iterate `point` as a delta from the reference orbit, or None if it
+    /// glitched and has to be iterated at full precision instead
+    pub(crate) fn iterate(
+        &self,
+        point: &Complex,
+        take: usize,
+        bailout: f64,
+        derivative: bool,
+        trap: Option<(OrbitTrap, (f64, f64))>,
+        averaged: Option<Averaged>,
+    ) -> Option<OrbitEnd> {
+        let dc = self.delta_c(point);
+        let (start, mut dz, mut der) = match &self.series {
+            Some(series) => (
+                series.skip,
+                Series::delta(&series.coefficients, dc),
+                derivative.then(|| series.derivative(dc)),
+            ),
+            None => {
+                let der = if derivative {
+                    Some((0_f64, 0_f64))
+                } else {
+                    None
+                };
+                (0, (0_f64, 0_f64), der)
+            }
+        };
+        let mut trap = trap.map(|(kind, position)| Trap::new(kind, position));
+        let c = <(f64, f64)>::from_complex(point);
+        let mut average = averaged.map(|averaged| Average::new(averaged, &c));
+
+        // Position along the reference orbit, which restarts on rebasing
+        let mut m = start;
+        let mut z = (0_f64, 0_f64);
+        for n in start..take {
+            if m + 1 == self.orbit.len() {
+                // The reference escaped first, so carry on from its start
+                dz.add_mut(&self.orbit[m]);
+                m = 0;
+            }
+            let reference = self.orbit[m];
+            let next = self.orbit[m + 1];
+
+            if let Some(der) = &mut der {
+                // d/dc (z^2 + c) = 2 * z * dc + 1
+                let mut z = reference;
+                z.add_mut(&dz);
+                der.mul_mut(&z);
+                der.scale_mut(2);
+                der.add_one_mut();
+            }
+
+            // dz' = 2 * Z * dz + dz^2 + dc = dz * (2 * Z + dz) + dc
+            let mut factor = reference;
+            factor.scale_mut(2);
+            factor.add_mut(&dz);
+            dz.mul_mut(&factor);
+            dz.add_mut(&dc);
+
+            z = next;
+            z.add_mut(&dz);
+
+            if let Some(trap) = &mut trap {
+                trap.update(&z);
+            }
+            if let Some(average) = &mut average {
+                average.update(&z, &c);
+            }
+
+            if z.exceeds(bailout) {
+                // Past the bailout the delta is no longer needed, so z is
+                // iterated directly
+                for _ in 0..self.past_bailout {
+                    if let Some(der) = &mut der {
+                        der.mul_mut(&z);
+                        der.scale_mut(2);
+                        der.add_one_mut();
+                    }
+                    z.square_mut();
+                    z.add_mut(&c);
+                }
+                return Some(OrbitEnd {
+                    iterations: n,
+                    escape_modulus: z.modulus().to_f64(),
+                    escape_angle: z.arg(),
+                    derivative_modulus: der.map_or_else(|| Float::new(53), |der| der.modulus()),
+                    normal_angle: der.map_or(0_f64, |der| z.arg() - der.arg()),
+                    period: None,
+                    trap_distance: trap.map(|trap| trap.min_distance),
+                    average: average.map(|average| average.averages()),
+                    root: None,
+                });
+            }
+
+            if norm(z) < norm(dz) {
+                // Rebase onto the start of the reference, where the delta
+                // is the whole of z, once the orbit is closer to zero than
+                // to the reference (Zhuoran's rebasing)
+                dz = z;
+                m = 0;
+            } else if norm(z) < GLITCH_TOLERANCE * GLITCH_TOLERANCE * norm(next) {
+                return None;
+            } else {
+                m += 1;
+            }
+        }
+
+        Some(OrbitEnd {
+            iterations: take,
+            escape_modulus: z.modulus().to_f64(),
+            escape_angle: 0_f64,
+            derivative_modulus: Float::new(53),
+            normal_angle: 0_f64,
+            period: None,
+            trap_distance: trap.map(|trap| trap.min_distance),
+            average: None,
+            root: None,
+        })
+    }
+}
+
+/// |z|^2
+fn norm((re, im): (f64, f64)) -> f64 {
+    re * re + im * im
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `read_from` makes of the first `lines` lines `orbit` writes
+    fn read_lines(orbit: &ReferenceOrbit, lines: usize) -> io::Result<ReferenceOrbit> {
+        let mut written = Vec::new();
+        orbit.write_to(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        let kept: Vec<&str> = written.lines().take(lines).collect();
+        ReferenceOrbit::read_from(kept.join("\n").as_bytes())
+    }
+
+    #[test]
+    fn cut_short_orbits_are_refused() {
+        // Escaping within `take`, and running all of it
+        for center in [(0.5_f64, 0.5_f64), (-0.1_f64, 0.1_f64)] {
+            let orbit = ReferenceOrbit::new(Complex::with_val(64, center), 100, 2_f64);
+            let lines = orbit.orbit.len() + 2;
+            assert_eq!(read_lines(&orbit, lines).unwrap().orbit, orbit.orbit);
+            for lines in [3, lines - 1] {
+                let error = read_lines(&orbit, lines).err().unwrap();
+                assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use clap::ArgEnum;
+use image::{ImageBuffer, Pixel, Rgb, RgbImage};
+use mandelbrot::{pixel_of, point_of, RenderConfig};
+use rug::{ops::Pow, Complex, Float, Integer};
+
+/// Color the orbit's path is drawn in
+const PATH: Rgb<u8> = Rgb([20, 20, 20]);
+/// Color each step of the orbit is marked with
+const STEP: Rgb<u8> = Rgb([220, 30, 30]);
+
+/// Most lines the grid draws along either axis, well past where their
+/// labels would all run into each other
+const MAX_GRID_LINES: usize = 500;
+/// Lines the grid is spaced for across the wider of the image's spans,
+/// by default
+const GRID_LINES: f64 = 8_f64;
+
+/// Share of the image's width the scale bar is sized to, before it's
+/// rounded to a length that's easy to read
+const SCALE_BAR_WIDTH: f64 = 0.2;
+
+/// Powers of ten the scale bar's length is written out in full for,
+/// rather than as an exponent
+const SCALE_BAR_DECIMALS: i32 = 4;
+
+/// Digits, '-', '.', 'i' and 'e', 3 pixels wide and 5 high, a row of bits
+/// at a time from the top
+const GLYPHS: [(char, [u8; 5]); 14] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b011, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('i', [0b010, 0b000, 0b010, 0b010, 0b010]),
+    ('e', [0b111, 0b101, 0b111, 0b100, 0b111]),
+];
+
+/// Corner of the image
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Lighten `img` most of the way to white, so that a path drawn over it
+/// stands out
+pub fn fade(img: &mut RgbImage) {
+    for pixel in img.pixels_mut() {
+        for channel in &mut pixel.0 {
+            *channel = 255 - (255 - *channel) / 4;
+        }
+    }
+}
+
+/// Draw the path through `points`, in pixels, over `img`, with a mark at
+/// each of them. Any of it outside of the image is left off
+pub fn draw_path(img: &mut RgbImage, points: &[(f64, f64)]) {
+    for pair in points.windows(2) {
+        if let Some((from, to)) = clip(img, pair[0], pair[1]) {
+            draw_line(img, from, to, PATH);
+        }
+    }
+    for &(x, y) in points {
+        for (dx, dy) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] {
+            put(img, x.floor() as i64 + dx, y.floor() as i64 + dy, STEP);
+        }
+    }
+}
+
+/// Draw lines over `img` at every multiple of `spacing` of the real and
+/// imaginary parts in view, each labeled with its value, in `color`.
+/// `spacing` is digits times a power of ten, so the labels are exact at
+/// any zoom, and is picked to put about `GRID_LINES` across if not given
+pub fn draw_grid<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    config: &RenderConfig,
+    spacing: Option<&(Integer, i32)>,
+    color: P,
+) -> Result<(), String> {
+    let prec = config.precision + 32;
+    let (width, height) = (img.width() as f64, img.height() as f64);
+    let corners: Vec<Complex> = [
+        (0_f64, 0_f64),
+        (width, 0_f64),
+        (0_f64, height),
+        (width, height),
+    ]
+    .into_iter()
+    .map(|corner| point_of(config, corner))
+    .collect();
+    let span = |part: fn(&Complex) -> &Float| {
+        let low = corners.iter().map(part).min_by(|a, b| a.total_cmp(b));
+        let high = corners.iter().map(part).max_by(|a, b| a.total_cmp(b));
+        let low = Float::with_val(prec, low.expect("The image has corners"));
+        let high = Float::with_val(prec, high.expect("The image has corners"));
+        (low, high)
+    };
+    let reals = span(Complex::real);
+    let imags = span(Complex::imag);
+
+    let (digits, exponent) = match spacing {
+        Some((digits, exponent)) => (digits.clone(), *exponent),
+        None => {
+            let widest = Float::with_val(prec, &reals.1 - &reals.0)
+                .max(&Float::with_val(prec, &imags.1 - &imags.0));
+            round_spacing(&(widest / GRID_LINES))
+        }
+    };
+    let step = Float::with_val(prec, &digits) * Float::with_val(prec, 10).pow(exponent);
+
+    // Multiples of the spacing from `low` up to `high`
+    let multiples = |(low, high): &(Float, Float)| -> Result<Vec<Integer>, String> {
+        let first = Float::with_val(prec, low / &step).ceil().to_integer();
+        let last = Float::with_val(prec, high / &step).floor().to_integer();
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(Vec::new()),
+        };
+        let count = Integer::from(&last - &first) + 1;
+        if count > MAX_GRID_LINES {
+            return Err(format!(
+                "A grid spacing of {} draws more than {} lines along an axis",
+                decimal(&digits, exponent),
+                MAX_GRID_LINES
+            ));
+        }
+        let mut k = first;
+        let mut multiples = Vec::new();
+        while k <= last {
+            multiples.push(k.clone());
+            k += 1;
+        }
+        Ok(multiples)
+    };
+
+    let scale = (img.height() / 540).max(1) as i64;
+    let mut labels: Vec<[i64; 4]> = Vec::new();
+    for (imaginary, bounds, across) in [(false, &reals, &imags), (true, &imags, &reals)] {
+        for k in multiples(bounds)? {
+            let value = Float::with_val(prec, &k) * &step;
+            let along = |t: &Float| {
+                let point = if imaginary {
+                    (t.clone(), value.clone())
+                } else {
+                    (value.clone(), t.clone())
+                };
+                pixel_of(config, &Complex::with_val(prec, point))
+            };
+            let (from, to) = match clip(img, along(&across.0), along(&across.1)) {
+                Some(line) => line,
+                None => continue,
+            };
+            draw_line(img, from, to, color);
+
+            // Real parts are labeled where their lines meet the bottom,
+            // and imaginary parts where theirs meet the left
+            let mut label = decimal(&Integer::from(&k * &digits), exponent);
+            let (x, y) = if imaginary {
+                label.push('i');
+                let (x, y) = if from.0 <= to.0 { from } else { to };
+                (x as i64 + 2 * scale, y as i64 - 6 * scale)
+            } else {
+                let (x, y) = if from.1 >= to.1 { from } else { to };
+                let width = label.len() as i64 * 4 * scale;
+                (x as i64 - width / 2, y as i64 - 7 * scale)
+            };
+            let width = label.len() as i64 * 4 * scale - scale;
+            let x = x.clamp(1, (img.width() as i64 - width - 1).max(1));
+            let y = y.clamp(1, (img.height() as i64 - 6 * scale).max(1));
+            let rect = [x - scale, y - scale, x + width + scale, y + 6 * scale];
+            let overlaps = labels.iter().any(|other| {
+                rect[0] < other[2] && other[0] < rect[2] && rect[1] < other[3] && other[1] < rect[3]
+            });
+            if !overlaps {
+                draw_text(img, &label, (x, y), scale, color);
+                labels.push(rect);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draw a bar in `corner` of `img` as long as a round distance across the
+/// plane, 1, 2 or 5 times a power of ten near a fifth of the image's
+/// width, labeled with that distance, in `color`
+pub fn draw_scale_bar<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    config: &RenderConfig,
+    corner: Corner,
+    color: P,
+) {
+    let prec = config.precision + 32;
+    // Turning the view doesn't change how far apart its pixels are
+    let pixel = Float::with_val(prec, config.x_step.abs_ref());
+    let (digits, exponent) =
+        round_spacing(&(Float::with_val(prec, &pixel * img.width()) * SCALE_BAR_WIDTH));
+    let length = Float::with_val(prec, &digits) * Float::with_val(prec, 10).pow(exponent);
+    let pixels = Float::with_val(prec, length / &pixel).to_f64().round() as i64;
+    let label = if exponent.abs() <= SCALE_BAR_DECIMALS {
+        decimal(&digits, exponent)
+    } else {
+        format!("{}e{}", digits, exponent)
+    };
+
+    let scale = (img.height() / 540).max(1) as i64;
+    let (width, height) = (img.width() as i64, img.height() as i64);
+    let margin = 4 * scale;
+    let left = match corner {
+        Corner::TopLeft | Corner::BottomLeft => margin,
+        Corner::TopRight | Corner::BottomRight => width - margin - pixels,
+    };
+    // The label sits on the side of the bar away from the edge
+    let (bar, text) = match corner {
+        Corner::TopLeft | Corner::TopRight => (margin + 7 * scale, margin),
+        Corner::BottomLeft | Corner::BottomRight => {
+            let bar = height - margin - scale;
+            (bar, bar - 7 * scale)
+        }
+    };
+
+    fill(img, (left, bar), (left + pixels, bar + scale), color);
+    // Ticks at either end, up or down towards the label
+    let (top, bottom) = if text < bar {
+        (bar - 2 * scale, bar + scale)
+    } else {
+        (bar, bar + 3 * scale)
+    };
+    for x in [left, left + pixels - scale] {
+        fill(img, (x, top), (x + scale, bottom), color);
+    }
+
+    let text_width = label.len() as i64 * 4 * scale - scale;
+    let x = left + pixels / 2 - text_width / 2;
+    let x = x.clamp(1, (width - text_width - 1).max(1));
+    draw_text(img, &label, (x, text), scale, color);
+}
+
+/// A round spacing near `spacing`, 1, 2 or 5 times a power of ten, as its
+/// digits and that power
+fn round_spacing(spacing: &Float) -> (Integer, i32) {
+    let log = Float::with_val(spacing.prec(), spacing.log10_ref());
+    let exponent = log.to_f64().floor() as i32;
+    let fraction = Float::with_val(
+        spacing.prec(),
+        spacing / Float::with_val(spacing.prec(), 10).pow(exponent),
+    );
+    match fraction.to_f64() {
+        f if f < 1.5_f64 => (Integer::from(1), exponent),
+        f if f < 3.5_f64 => (Integer::from(2), exponent),
+        f if f < 7.5_f64 => (Integer::from(5), exponent),
+        _ => (Integer::from(1), exponent + 1),
+    }
+}
+
+/// `digits` times ten to the `exponent`, written out in full
+fn decimal(digits: &Integer, exponent: i32) -> String {
+    let sign = if *digits < 0 { "-" } else { "" };
+    let mut text = Integer::from(digits.abs_ref()).to_string();
+    if exponent >= 0 {
+        if text != "0" {
+            text.push_str(&"0".repeat(exponent as usize));
+        }
+        return format!("{}{}", sign, text);
+    }
+    let places = exponent.unsigned_abs() as usize;
+    if text.len() <= places {
+        text = format!("{}{}", "0".repeat(places + 1 - text.len()), text);
+    }
+    text.insert(text.len() - places, '.');
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "0" {
+        return text.to_string();
+    }
+    format!("{}{}", sign, text)
+}
+
+/// Write `text` with its top left at (`x`, `y`), each pixel of a glyph
+/// `scale` pixels across
+fn draw_text<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    text: &str,
+    (x, y): (i64, i64),
+    scale: i64,
+    color: P,
+) {
+    for (n, c) in text.chars().enumerate() {
+        let rows = match GLYPHS.iter().find(|(glyph, _)| *glyph == c) {
+            Some((_, rows)) => rows,
+            None => continue,
+        };
+        let left = x + n as i64 * 4 * scale;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = left + column * scale + dx;
+                        put(img, px, y + row as i64 * scale + dy, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Color the pixels from `from` up to but not including `to`
+fn fill<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    from: (i64, i64),
+    to: (i64, i64),
+    color: P,
+) {
+    for y in from.1..to.1 {
+        for x in from.0..to.0 {
+            put(img, x, y, color);
+        }
+    }
+}
+
+fn put<P: Pixel>(img: &mut ImageBuffer<P, Vec<P::Subpixel>>, x: i64, y: i64, color: P) {
+    if x >= 0 && y >= 0 && x < img.width() as i64 && y < img.height() as i64 {
+        img.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// One pixel at a time from `from` to `to`, both inside of the image
+fn draw_line<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    from: (f64, f64),
+    to: (f64, f64),
+    color: P,
+) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs()).ceil().max(1_f64);
+    for step in 0..=steps as u64 {
+        let t = step as f64 / steps;
+        let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+        put(img, x.floor() as i64, y.floor() as i64, color);
+    }
+}
+
+/// The part of the line from `from` to `to` inside of the image, if any.
+/// Escaping orbits leave it by far more pixels than could be stepped over
+fn clip<P: Pixel>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    from: (f64, f64),
+    to: (f64, f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    if ![from.0, from.1, to.0, to.1].iter().all(|v| v.is_finite()) {
+        return None;
+    }
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut enter, mut leave) = (0_f64, 1_f64);
+    // Each edge as how far past it the line moves, and how far inside of
+    // it the line starts
+    let edges = [
+        (-dx, from.0),
+        (dx, img.width() as f64 - from.0),
+        (-dy, from.1),
+        (dy, img.height() as f64 - from.1),
+    ];
+    for (towards, inside) in edges {
+        if towards == 0_f64 {
+            if inside < 0_f64 {
+                return None;
+            }
+            continue;
+        }
+        let t = inside / towards;
+        if towards < 0_f64 {
+            enter = enter.max(t);
+        } else {
+            leave = leave.min(t);
+        }
+    }
+    if enter > leave {
+        return None;
+    }
+    Some((
+        (from.0 + dx * enter, from.1 + dy * enter),
+        (from.0 + dx * leave, from.1 + dy * leave),
+    ))
+}
@@ -0,0 +1,134 @@
+use mandelbrot::PixelResult;
+use std::{fmt::Write as _, fs, io};
+
+/// How many equal ranges of escape counts the histogram splits `take` into
+const BUCKETS: usize = 10;
+
+/// Statistics of the escape counts of an image's pixels, to tell whether
+/// `take` is too low (lots inside the set) or wastefully high (nothing
+/// escapes near it)
+pub struct Profile {
+    pixels: usize,
+    inside: usize,
+    take: usize,
+    /// Min, max, mean and median escape count of the pixels that escaped,
+    /// `None` if none did
+    escaped: Option<(u32, u32, f64, f64)>,
+    /// Escaped pixels with counts in each of `BUCKETS` equal ranges up to
+    /// `take`
+    histogram: Vec<usize>,
+}
+
+impl Profile {
+    pub fn new(results: &[PixelResult], take: usize) -> Self {
+        let mut counts: Vec<u32> = results
+            .iter()
+            .filter(|result| result.escaped)
+            .map(|result| result.iterations)
+            .collect();
+        counts.sort_unstable();
+
+        let mut histogram = vec![0; BUCKETS];
+        for &count in &counts {
+            histogram[(count as usize * BUCKETS / take.max(1)).min(BUCKETS - 1)] += 1;
+        }
+
+        let escaped = match (counts.first(), counts.last()) {
+            (Some(&min), Some(&max)) => {
+                let mean = counts.iter().map(|&c| c as f64).sum::<f64>() / counts.len() as f64;
+                // The same count twice for an odd number of them
+                let len = counts.len();
+                let median = (counts[(len - 1) / 2] as f64 + counts[len / 2] as f64) / 2_f64;
+                Some((min, max, mean, median))
+            }
+            _ => None,
+        };
+
+        Profile {
+            pixels: results.len(),
+            inside: results.len() - counts.len(),
+            take,
+            escaped,
+            histogram,
+        }
+    }
+
+    fn inside_fraction(&self) -> f64 {
+        self.inside as f64 / self.pixels.max(1) as f64
+    }
+
+    /// The counts each histogram bucket covers, inclusive
+    fn bucket_range(&self, bucket: usize) -> (usize, usize) {
+        let from = (bucket * self.take).div_ceil(BUCKETS);
+        let to = ((bucket + 1) * self.take).div_ceil(BUCKETS);
+        (from, to.saturating_sub(1).max(from))
+    }
+
+    /// A human readable summary, one line at a time
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "In the set: {:.2}% of {} pixels",
+            self.inside_fraction() * 100_f64,
+            self.pixels
+        )];
+        match self.escaped {
+            Some((min, max, mean, median)) => lines.push(format!(
+                "Escaped after: min {}, max {}, mean {:.1}, median {}",
+                min, max, mean, median
+            )),
+            None => lines.push(String::from("No pixels escaped")),
+        }
+
+        let escaped = (self.pixels - self.inside).max(1) as f64;
+        let width = self.take.to_string().len();
+        for (bucket, &count) in self.histogram.iter().enumerate() {
+            let (from, to) = self.bucket_range(bucket);
+            let share = count as f64 / escaped;
+            let line = format!(
+                "{:>width$}-{:<width$} {:>6.2}% {}",
+                from,
+                to,
+                share * 100_f64,
+                "#".repeat((share * 40_f64).round() as usize),
+                width = width
+            );
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+
+    /// Write the profile to `path` as JSON
+    pub fn write_json(&self, path: &str) -> io::Result<()> {
+        let mut json = format!(
+            "{{\n  \"pixels\": {},\n  \"inside\": {},\n  \"inside_fraction\": {},\n  \"take\": {},\n",
+            self.pixels,
+            self.inside,
+            self.inside_fraction(),
+            self.take
+        );
+        match self.escaped {
+            Some((min, max, mean, median)) => {
+                let _ = writeln!(
+                    json,
+                    "  \"min\": {},\n  \"max\": {},\n  \"mean\": {},\n  \"median\": {},",
+                    min, max, mean, median
+                );
+            }
+            None => json.push_str(
+                "  \"min\": null,\n  \"max\": null,\n  \"mean\": null,\n  \"median\": null,\n",
+            ),
+        }
+        json.push_str("  \"histogram\": [\n");
+        for (bucket, &count) in self.histogram.iter().enumerate() {
+            let (from, to) = self.bucket_range(bucket);
+            let separator = if bucket + 1 < BUCKETS { "," } else { "" };
+            let _ = writeln!(
+                json,
+                "    {{ \"from\": {}, \"to\": {}, \"pixels\": {} }}{}",
+                from, to, count, separator
+            );
+        }
+        json.push_str("  ]\n}\n");
+        fs::write(path, json)
+    }
+}
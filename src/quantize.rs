@@ -0,0 +1,181 @@
+use clap::ArgEnum;
+use image::{GrayImage, ImageBuffer, Luma, Rgb};
+use std::collections::HashMap;
+
+/// Most rounds of k-means refinement, which usually settles well before
+const KMEANS_ROUNDS: usize = 16;
+
+/// How the colors of a quantized image are chosen
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quantizer {
+    /// Split the colors in half along their widest channel until there
+    /// are enough groups, and use the average of each
+    MedianCut,
+    /// Start from the median cut colors and move each to the average of
+    /// the pixels nearest it, for colors closer to the image's
+    KMeans,
+}
+
+/// An image made up of a palette of colors, with the index of its color
+/// in the palette for each pixel
+pub struct Quantized {
+    pub palette: Vec<Rgb<u8>>,
+    pub indices: GrayImage,
+}
+
+impl Quantized {
+    /// The image in RGB, with each pixel the palette color it was given
+    pub fn to_rgb(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let (width, height) = self.indices.dimensions();
+        ImageBuffer::from_fn(width, height, |x, y| {
+            self.palette[self.indices.get_pixel(x, y).0[0] as usize]
+        })
+    }
+}
+
+/// A distinct color of the image, with how many pixels are that color
+type Count = ([u8; 3], u64);
+
+/// `img` in at most `colors` colors, from 1 to 256
+pub fn quantize(
+    img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    colors: usize,
+    method: Quantizer,
+) -> Quantized {
+    let mut counts: HashMap<[u8; 3], u64> = HashMap::new();
+    for pixel in img.pixels() {
+        *counts.entry(pixel.0).or_default() += 1;
+    }
+    let mut counts: Vec<Count> = counts.into_iter().collect();
+    // Sorted so that the same image is always given the same palette
+    counts.sort_unstable();
+
+    let mut palette = median_cut(&counts, colors.clamp(1, 256));
+    if method == Quantizer::KMeans {
+        palette = k_means(&counts, palette);
+    }
+
+    let nearest: HashMap<[u8; 3], u8> = counts
+        .iter()
+        .map(|&(color, _)| (color, nearest(&palette, color) as u8))
+        .collect();
+    let indices = ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        Luma([nearest[&img.get_pixel(x, y).0]])
+    });
+    Quantized {
+        palette: palette.into_iter().map(|color| Rgb(round(color))).collect(),
+        indices,
+    }
+}
+
+/// The average color of each of up to `colors` groups of the image's
+fn median_cut(counts: &[Count], colors: usize) -> Vec<[f64; 3]> {
+    let mut boxes: Vec<Vec<Count>> = vec![counts.to_vec()];
+    while boxes.len() < colors {
+        // The group spread widest along any one channel is split next
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range);
+        let (i, channel, _) = match widest {
+            Some(widest) => widest,
+            None => break,
+        };
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|&(color, _)| color[channel]);
+        // Split where half of the pixels are on either side, keeping at
+        // least one color in each half
+        let total: u64 = colors.iter().map(|&(_, count)| count).sum();
+        let mut seen = 0;
+        let mut split = colors.len() - 1;
+        for (n, &(_, count)) in colors.iter().enumerate() {
+            seen += count;
+            if seen * 2 >= total {
+                split = n + 1;
+                break;
+            }
+        }
+        let upper = colors.split_off(split.clamp(1, colors.len() - 1));
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|colors| mean(colors)).collect()
+}
+
+/// The channel `colors` spread furthest along, and how far
+fn widest_channel(colors: &[Count]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = colors.iter().map(|(color, _)| color[channel]);
+            let low = values.clone().min().unwrap_or_default();
+            let high = values.max().unwrap_or_default();
+            (channel, high - low)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or_default()
+}
+
+/// Move each of `palette` to the average of the colors nearest it, until
+/// none of them move
+fn k_means(counts: &[Count], mut palette: Vec<[f64; 3]>) -> Vec<[f64; 3]> {
+    for _ in 0..KMEANS_ROUNDS {
+        let mut groups: Vec<Vec<Count>> = vec![Vec::new(); palette.len()];
+        for &(color, count) in counts {
+            groups[nearest(&palette, color)].push((color, count));
+        }
+        let moved: Vec<[f64; 3]> = groups
+            .iter()
+            .zip(&palette)
+            // A color nearest to none of the image's keeps its place
+            .map(|(group, &color)| if group.is_empty() { color } else { mean(group) })
+            .collect();
+        let settled = moved
+            .iter()
+            .zip(&palette)
+            .all(|(moved, color)| round(*moved) == round(*color));
+        palette = moved;
+        if settled {
+            break;
+        }
+    }
+    palette
+}
+
+/// Average of `colors`, weighted by how many pixels are each
+fn mean(colors: &[Count]) -> [f64; 3] {
+    let total: u64 = colors.iter().map(|&(_, count)| count).sum();
+    let mut sum = [0_f64; 3];
+    for &(color, count) in colors {
+        for (sum, channel) in sum.iter_mut().zip(color) {
+            *sum += channel as f64 * count as f64;
+        }
+    }
+    sum.map(|sum| sum / total.max(1) as f64)
+}
+
+/// Index of the color of `palette` closest to `color`
+fn nearest(palette: &[[f64; 3]], color: [u8; 3]) -> usize {
+    let distance = |entry: &[f64; 3]| -> f64 {
+        entry
+            .iter()
+            .zip(color)
+            .map(|(entry, channel)| (entry - channel as f64).powi(2))
+            .sum()
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map(|(i, _)| i)
+        .unwrap_or_default()
+}
+
+fn round(color: [f64; 3]) -> [u8; 3] {
+    color.map(|channel| channel.round().clamp(0_f64, 255_f64) as u8)
+}
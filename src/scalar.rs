@@ -0,0 +1,306 @@
+use rug::{ops::PowAssign, Assign, Complex, Float};
+
+/// Complex number the orbit is iterated in: hardware floats when the
+/// image fits in 53 bits of precision, or `rug::Complex` beyond that
+pub trait ComplexScalar: Clone + Sync {
+    /// `c` at the precision this scalar works in
+    fn from_complex(c: &Complex) -> Self;
+    /// `self` as a `rug::Complex`, exactly
+    fn to_complex(&self) -> Complex;
+    /// Zero at the same precision as `self`
+    fn zero_like(&self) -> Self;
+    fn prec(&self) -> u32;
+
+    fn abs_parts_mut(&mut self);
+    fn abs_real_mut(&mut self);
+    fn conj_mut(&mut self);
+    fn square_mut(&mut self);
+    fn pow_mut(&mut self, n: u32);
+    fn mul_mut(&mut self, other: &Self);
+    fn div_mut(&mut self, other: &Self);
+    fn scale_mut(&mut self, n: u32);
+    fn add_mut(&mut self, other: &Self);
+    fn add_one_mut(&mut self);
+    fn neg_mut(&mut self);
+    fn exp_mut(&mut self);
+    fn sin_mut(&mut self);
+    fn cos_mut(&mut self);
+
+    /// Whether |self| exceeds `bailout`
+    fn exceeds(&self, bailout: f64) -> bool;
+    /// The real and imaginary parts, as f64s
+    fn parts(&self) -> (f64, f64);
+    /// |self|, which isn't limited to the range of an f64
+    fn modulus(&self) -> Float;
+    /// Angle of self from the positive real axis, in radians
+    fn arg(&self) -> f64;
+    /// |self - other|
+    fn distance(&self, other: &Self) -> Float;
+    /// Whether |self - other|^2 is under `epsilon_squared`, checked
+    /// every step of an orbit so worked out in `scratch` rather than
+    /// allocating as `distance` does
+    fn within(&self, other: &Self, epsilon_squared: &Float, scratch: &mut Self) -> bool;
+    /// Distance to the nearer of the horizontal and vertical lines
+    /// through `other`
+    fn axis_distance(&self, other: &Self) -> f64;
+}
+
+impl ComplexScalar for Complex {
+    fn from_complex(c: &Complex) -> Self {
+        c.clone()
+    }
+
+    fn to_complex(&self) -> Complex {
+        self.clone()
+    }
+
+    fn zero_like(&self) -> Self {
+        Complex::with_val(self.prec(), (0_f32, 0_f32))
+    }
+
+    fn prec(&self) -> u32 {
+        Complex::prec(self).0
+    }
+
+    fn abs_parts_mut(&mut self) {
+        self.mut_real().abs_mut();
+        self.mut_imag().abs_mut();
+    }
+
+    fn abs_real_mut(&mut self) {
+        self.mut_real().abs_mut();
+    }
+
+    fn conj_mut(&mut self) {
+        Complex::conj_mut(self);
+    }
+
+    fn square_mut(&mut self) {
+        // (x + y)(x - y) and 2xy, rounding after each step as the
+        // hardware floats do, so that at 53 bits the two agree exactly
+        let prec = ComplexScalar::prec(self);
+        let (re, im) = self.as_mut_real_imag();
+        let sum = Float::with_val(prec, &*re + &*im);
+        let product = Float::with_val(prec, &*re * &*im);
+        *re -= &*im;
+        *re *= &sum;
+        *im = product;
+        *im <<= 1;
+    }
+
+    fn pow_mut(&mut self, n: u32) {
+        self.pow_assign(n);
+    }
+
+    fn mul_mut(&mut self, other: &Self) {
+        *self *= other;
+    }
+
+    fn div_mut(&mut self, other: &Self) {
+        *self /= other;
+    }
+
+    fn scale_mut(&mut self, n: u32) {
+        *self *= n;
+    }
+
+    fn add_mut(&mut self, other: &Self) {
+        *self += other;
+    }
+
+    fn add_one_mut(&mut self) {
+        *self += 1;
+    }
+
+    fn neg_mut(&mut self) {
+        *self *= -1_i32;
+    }
+
+    fn exp_mut(&mut self) {
+        // exp(z) is 1 + z to within the precision once |z| is below
+        // 2^-prec, which MPC takes far longer to work out for the tiny z
+        // the exp of a large negative number leaves behind
+        let prec = self.real().prec() as i32;
+        let tiny = |x: &Float| x.is_zero() || matches!(x.get_exp(), Some(exp) if exp < -prec);
+        if tiny(self.real()) && tiny(self.imag()) {
+            *self += 1;
+        } else {
+            Complex::exp_mut(self);
+        }
+    }
+
+    fn sin_mut(&mut self) {
+        Complex::sin_mut(self);
+    }
+
+    fn cos_mut(&mut self) {
+        Complex::cos_mut(self);
+    }
+
+    fn exceeds(&self, bailout: f64) -> bool {
+        // |z|^2 in hardware floats, which is plenty to compare with the
+        // bailout, and the same check the hardware float orbits make
+        let (re, im) = self.parts();
+        re * re + im * im > bailout * bailout
+    }
+
+    fn parts(&self) -> (f64, f64) {
+        (self.real().to_f64(), self.imag().to_f64())
+    }
+
+    fn modulus(&self) -> Float {
+        Float::with_val(53, self.abs_ref())
+    }
+
+    fn arg(&self) -> f64 {
+        Float::with_val(53, self.arg_ref()).to_f64()
+    }
+
+    fn distance(&self, other: &Self) -> Float {
+        let diff = Complex::with_val(self.prec(), self - other);
+        Float::with_val(53, diff.abs_ref())
+    }
+
+    fn within(&self, other: &Self, epsilon_squared: &Float, scratch: &mut Self) -> bool {
+        scratch.assign(self - other);
+        let (re, im) = scratch.as_mut_real_imag();
+        re.square_mut();
+        im.square_mut();
+        *re += &*im;
+        *re < *epsilon_squared
+    }
+
+    fn axis_distance(&self, other: &Self) -> f64 {
+        let diff = Complex::with_val(self.prec(), self - other);
+        diff.real().to_f64().abs().min(diff.imag().to_f64().abs())
+    }
+}
+
+impl ComplexScalar for (f64, f64) {
+    fn from_complex(c: &Complex) -> Self {
+        (c.real().to_f64(), c.imag().to_f64())
+    }
+
+    fn to_complex(&self) -> Complex {
+        Complex::with_val(f64::MANTISSA_DIGITS, *self)
+    }
+
+    fn zero_like(&self) -> Self {
+        (0_f64, 0_f64)
+    }
+
+    fn prec(&self) -> u32 {
+        f64::MANTISSA_DIGITS
+    }
+
+    fn abs_parts_mut(&mut self) {
+        self.0 = self.0.abs();
+        self.1 = self.1.abs();
+    }
+
+    fn abs_real_mut(&mut self) {
+        self.0 = self.0.abs();
+    }
+
+    fn conj_mut(&mut self) {
+        self.1 = -self.1;
+    }
+
+    fn square_mut(&mut self) {
+        let (re, im) = *self;
+        *self = ((re + im) * (re - im), 2_f64 * re * im);
+    }
+
+    fn pow_mut(&mut self, mut n: u32) {
+        // Exponentiation by squaring
+        let mut base = *self;
+        *self = (1_f64, 0_f64);
+        while n > 0 {
+            if n & 1 == 1 {
+                self.mul_mut(&base);
+            }
+            base.square_mut();
+            n >>= 1;
+        }
+    }
+
+    fn mul_mut(&mut self, other: &Self) {
+        let (re, im) = *self;
+        *self = (re * other.0 - im * other.1, re * other.1 + im * other.0);
+    }
+
+    fn div_mut(&mut self, other: &Self) {
+        let (re, im) = *self;
+        let norm = other.0 * other.0 + other.1 * other.1;
+        *self = (
+            (re * other.0 + im * other.1) / norm,
+            (im * other.0 - re * other.1) / norm,
+        );
+    }
+
+    fn scale_mut(&mut self, n: u32) {
+        self.0 *= n as f64;
+        self.1 *= n as f64;
+    }
+
+    fn add_mut(&mut self, other: &Self) {
+        self.0 += other.0;
+        self.1 += other.1;
+    }
+
+    fn add_one_mut(&mut self) {
+        self.0 += 1_f64;
+    }
+
+    fn neg_mut(&mut self) {
+        *self = (-self.0, -self.1);
+    }
+
+    fn exp_mut(&mut self) {
+        let (re, im) = *self;
+        let (sin, cos) = im.sin_cos();
+        let scale = re.exp();
+        *self = (scale * cos, scale * sin);
+    }
+
+    fn sin_mut(&mut self) {
+        let (re, im) = *self;
+        let (sin, cos) = re.sin_cos();
+        *self = (sin * im.cosh(), cos * im.sinh());
+    }
+
+    fn cos_mut(&mut self) {
+        let (re, im) = *self;
+        let (sin, cos) = re.sin_cos();
+        *self = (cos * im.cosh(), -sin * im.sinh());
+    }
+
+    fn exceeds(&self, bailout: f64) -> bool {
+        self.0 * self.0 + self.1 * self.1 > bailout * bailout
+    }
+
+    fn parts(&self) -> (f64, f64) {
+        *self
+    }
+
+    fn modulus(&self) -> Float {
+        Float::with_val(53, self.0.hypot(self.1))
+    }
+
+    fn arg(&self) -> f64 {
+        self.1.atan2(self.0)
+    }
+
+    fn distance(&self, other: &Self) -> Float {
+        Float::with_val(53, (self.0 - other.0).hypot(self.1 - other.1))
+    }
+
+    fn within(&self, other: &Self, epsilon_squared: &Float, _scratch: &mut Self) -> bool {
+        let (re, im) = (self.0 - other.0, self.1 - other.1);
+        re * re + im * im < epsilon_squared.to_f64()
+    }
+
+    fn axis_distance(&self, other: &Self) -> f64 {
+        (self.0 - other.0).abs().min((self.1 - other.1).abs())
+    }
+}
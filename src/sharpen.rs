@@ -0,0 +1,106 @@
+use image::{DynamicImage, ImageBuffer, Pixel, Primitive, Rgb};
+use palette::{LinSrgb, Srgb};
+
+/// An image in linear light
+type Linear = ImageBuffer<Rgb<f32>, Vec<f32>>;
+
+/// Standard deviations of the blur that the kernel reaches out to, past
+/// which its weights are too small to matter
+const KERNEL_REACH: f32 = 3_f32;
+
+/// Sharpen `img` with an unsharp mask: add `amount` times its difference
+/// from a Gaussian blur of it, `radius` pixels in standard deviation.
+/// Sharpened in linear light, whatever the image is encoded in
+pub fn sharpen(img: &mut DynamicImage, amount: f32, radius: f32) {
+    match img {
+        DynamicImage::ImageRgb8(img) => sharpen_encoded(img, amount, radius),
+        DynamicImage::ImageRgb16(img) => sharpen_encoded(img, amount, radius),
+        // HDR images are already linear, and may run past 1
+        DynamicImage::ImageRgb32F(img) => unsharp_mask(img, amount, radius),
+        _ => {}
+    }
+}
+
+/// Sharpen an sRGB encoded image, decoding it to linear light and back
+fn sharpen_encoded<S: Primitive + 'static>(
+    img: &mut ImageBuffer<Rgb<S>, Vec<S>>,
+    amount: f32,
+    radius: f32,
+) where
+    Rgb<S>: Pixel<Subpixel = S>,
+{
+    let max = S::DEFAULT_MAX_VALUE.to_f32().unwrap_or(1_f32);
+    let mut linear: Linear = ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        let [red, green, blue] = img
+            .get_pixel(x, y)
+            .0
+            .map(|c| c.to_f32().unwrap_or(0_f32) / max);
+        let linear = Srgb::new(red, green, blue).into_linear();
+        Rgb([linear.red, linear.green, linear.blue])
+    });
+    unsharp_mask(&mut linear, amount, radius);
+    for (pixel, linear) in img.pixels_mut().zip(linear.pixels()) {
+        let [red, green, blue] = linear.0;
+        let encoded = Srgb::from_linear(LinSrgb::new(red, green, blue));
+        let channels = [encoded.red, encoded.green, encoded.blue];
+        pixel.0 = channels
+            .map(|c| S::from((c * max).round().clamp(0_f32, max)).unwrap_or(S::DEFAULT_MIN_VALUE));
+    }
+}
+
+fn unsharp_mask(img: &mut Linear, amount: f32, radius: f32) {
+    let blurred = blur(img, radius);
+    for (pixel, blurred) in img.pixels_mut().zip(blurred.pixels()) {
+        for (channel, blurred) in pixel.0.iter_mut().zip(blurred.0) {
+            // Light can't go below none
+            *channel = (*channel + amount * (*channel - blurred)).max(0_f32);
+        }
+    }
+}
+
+/// `img` blurred by a Gaussian `radius` pixels in standard deviation,
+/// across and then down. Pixels past the edges are taken to be those on
+/// them, so the kernel reaches no further than across the image
+fn blur(img: &Linear, radius: f32) -> Linear {
+    let across = img.width().max(img.height()) as f32;
+    let reach = (radius * KERNEL_REACH).ceil().clamp(1_f32, across) as i64;
+    let weights: Vec<f32> = (-reach..=reach)
+        .map(|offset| (-(offset * offset) as f32 / (2_f32 * radius * radius)).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+
+    let (width, height) = (img.width() as i64, img.height() as i64);
+    let pass = |img: &Linear, across: bool| -> Linear {
+        ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+            let mut sum = [0_f32; 3];
+            for (weight, offset) in weights.iter().zip(-reach..=reach) {
+                let (x, y) = if across {
+                    ((x as i64 + offset).clamp(0, width - 1), y as i64)
+                } else {
+                    (x as i64, (y as i64 + offset).clamp(0, height - 1))
+                };
+                let pixel = img.get_pixel(x as u32, y as u32);
+                for (sum, channel) in sum.iter_mut().zip(pixel.0) {
+                    *sum += weight * channel;
+                }
+            }
+            Rgb(sum.map(|sum| sum / total))
+        })
+    };
+    pass(&pass(img, true), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_radii_reach_across_the_image() {
+        // Columns 0 to 3, evened out to within the middle two
+        let img: Linear = ImageBuffer::from_fn(4, 3, |x, _| Rgb([x as f32; 3]));
+        let blurred = blur(&img, 1e20_f32);
+        for pixel in blurred.pixels() {
+            assert!(pixel.0[0] >= 1_f32 && pixel.0[0] <= 2_f32, "{:?}", pixel);
+        }
+    }
+}
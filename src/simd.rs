@@ -0,0 +1,60 @@
+use std::{
+    array,
+    simd::{cmp::SimdPartialOrd, Mask, Select, Simd},
+};
+
+/// Orbits iterated side by side in each vector
+pub const LANES: usize = 4;
+
+type Lanes = Simd<f64, LANES>;
+
+/// z_0 and c of an orbit, as (real, imaginary)
+pub type Orbit = ((f64, f64), (f64, f64));
+
+/// Iterate z^2 + c from each (z_0, c) in `orbits`, `LANES` at a time,
+/// returning how many iterations each took to escape past `bailout`
+/// (`take` if it never did) with z where it stopped. The same arithmetic
+/// as iterating each orbit in `(f64, f64)`, so the results are identical
+pub fn iterate(orbits: &[Orbit], take: usize, bailout: f64) -> Vec<(usize, (f64, f64))> {
+    let mut results = Vec::with_capacity(orbits.len());
+    let bailout = Lanes::splat(bailout * bailout);
+    let two = Lanes::splat(2_f64);
+
+    for chunk in orbits.chunks(LANES) {
+        // A short last chunk is made up with copies of its first orbit
+        let gather = |part: fn(&Orbit) -> f64| {
+            Lanes::from_array(array::from_fn(|i| part(chunk.get(i).unwrap_or(&chunk[0]))))
+        };
+        let (mut z_re, mut z_im) = (gather(|o| o.0 .0), gather(|o| o.0 .1));
+        let (c_re, c_im) = (gather(|o| o.1 .0), gather(|o| o.1 .1));
+
+        let mut iterations = Simd::<u64, LANES>::splat(0);
+        let mut active = Mask::<i64, LANES>::splat(true);
+        let (mut end_re, mut end_im) = (z_re, z_im);
+        for _ in 0..take {
+            // Lanes that escaped carry on, but are no longer counted
+            let re = (z_re + z_im) * (z_re - z_im);
+            let im = two * z_re * z_im;
+            z_re = re + c_re;
+            z_im = im + c_im;
+
+            let escaped = active & (z_re * z_re + z_im * z_im).simd_gt(bailout);
+            end_re = escaped.select(z_re, end_re);
+            end_im = escaped.select(z_im, end_im);
+            active &= !escaped;
+            if !active.any() {
+                break;
+            }
+            iterations += active.select(Simd::splat(1), Simd::splat(0));
+        }
+        end_re = active.select(z_re, end_re);
+        end_im = active.select(z_im, end_im);
+
+        let (iterations, end_re, end_im) =
+            (iterations.to_array(), end_re.to_array(), end_im.to_array());
+        for i in 0..chunk.len() {
+            results.push((iterations[i] as usize, (end_re[i], end_im[i])));
+        }
+    }
+    results
+}
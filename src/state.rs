@@ -0,0 +1,121 @@
+use crate::RenderConfig;
+use rug::{Complex, Float};
+use std::io::{self, BufRead, Write};
+
+/// Where the orbit of a single sample got to
+#[derive(Clone)]
+pub(crate) enum SampleState {
+    /// Not iterated yet
+    Fresh,
+    /// Known to be in the set, however long it is iterated
+    Inside,
+    Escaped {
+        iterations: usize,
+        escape_modulus: f64,
+        escape_angle: f64,
+    },
+    /// Still iterating, with z after `iterations` steps
+    Pending { iterations: usize, z: Complex },
+}
+
+/// Where the orbit of every sample of an image got to, so that the render
+/// can be continued to a larger `take` without iterating it again
+pub struct RenderState {
+    /// Row by row from the top left, with each pixel's samples together
+    pub(crate) samples: Vec<SampleState>,
+}
+
+impl RenderState {
+    /// Nothing iterated yet, for an image rendered with `config`
+    pub fn new(config: &RenderConfig) -> Self {
+        RenderState {
+            samples: vec![SampleState::Fresh; sample_count(config)],
+        }
+    }
+
+    /// Whether this is the state of an image rendered with `config`
+    pub fn fits(&self, config: &RenderConfig) -> bool {
+        self.samples.len() == sample_count(config)
+    }
+
+    /// Write one sample per line, with z in exact hexadecimal
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for sample in &self.samples {
+            match sample {
+                SampleState::Fresh => writeln!(writer, "f")?,
+                SampleState::Inside => writeln!(writer, "i")?,
+                SampleState::Escaped {
+                    iterations,
+                    escape_modulus,
+                    escape_angle,
+                } => writeln!(
+                    writer,
+                    "e {} {} {}",
+                    iterations, escape_modulus, escape_angle
+                )?,
+                SampleState::Pending { iterations, z } => writeln!(
+                    writer,
+                    "p {} {} {} {}",
+                    iterations,
+                    z.prec().0.max(z.prec().1),
+                    z.real().to_string_radix(16, None),
+                    z.imag().to_string_radix(16, None)
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the samples written by `write_to`
+    pub fn read_from(reader: impl BufRead) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid sample state: {}", line),
+            )
+        };
+
+        let mut samples = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split(' ').collect();
+            let sample = match fields[..] {
+                ["f"] => SampleState::Fresh,
+                ["i"] => SampleState::Inside,
+                // States saved before the angle was kept have none
+                ["e", iterations, escape_modulus] | ["e", iterations, escape_modulus, _] => {
+                    let escape_angle = match fields.get(3) {
+                        Some(angle) => angle.parse().map_err(|_| invalid(&line))?,
+                        None => 0_f64,
+                    };
+                    SampleState::Escaped {
+                        iterations: iterations.parse().map_err(|_| invalid(&line))?,
+                        escape_modulus: escape_modulus.parse().map_err(|_| invalid(&line))?,
+                        escape_angle,
+                    }
+                }
+                ["p", iterations, prec, real, imag] => {
+                    let prec: u32 = prec.parse().map_err(|_| invalid(&line))?;
+                    let parse = |part: &str| {
+                        Float::parse_radix(part, 16)
+                            .map(|part| Float::with_val(prec, part))
+                            .map_err(|_| invalid(&line))
+                    };
+                    SampleState::Pending {
+                        iterations: iterations.parse().map_err(|_| invalid(&line))?,
+                        z: Complex::with_val(prec, (parse(real)?, parse(imag)?)),
+                    }
+                }
+                _ => return Err(invalid(&line)),
+            };
+            samples.push(sample);
+        }
+
+        Ok(RenderState { samples })
+    }
+}
+
+fn sample_count(config: &RenderConfig) -> usize {
+    let samples = config.supersample.max(1) as usize;
+    config.resolution.0 as usize * config.resolution.1 as usize * samples * samples
+}
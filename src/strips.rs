@@ -0,0 +1,28 @@
+use crate::metadata::png_writer;
+use mandelbrot::{render_with_progress, Progress, RenderConfig};
+use std::io::Write;
+
+/// Render the image as strips of `rows` rows from the top, writing each
+/// one to the PNG `writer` as it finishes, so that no more than one
+/// strip is ever held in memory
+pub fn render_strips(
+    config: &RenderConfig,
+    rows: u32,
+    writer: impl Write,
+    compression: png::Compression,
+    text: &[(&str, String)],
+    progress: &Progress,
+) -> Result<(), png::EncodingError> {
+    let (width, height) = config.resolution;
+    let mut writer = png_writer(writer, width, height, compression, text)?;
+    let mut stream = writer.stream_writer()?;
+
+    for y in (0..height).step_by(rows as usize) {
+        let strip_config = config.tile(0, y, width, rows.min(height - y));
+        let strip = render_with_progress(&strip_config, progress);
+        stream.write_all(&strip)?;
+    }
+
+    stream.finish()?;
+    writer.finish()
+}
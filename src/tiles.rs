@@ -0,0 +1,345 @@
+use crate::distributed::Worker;
+use clap::ArgEnum;
+use image::{GenericImage, ImageBuffer, ImageResult, Rgb};
+use mandelbrot::{render_with_progress, Progress, RenderConfig};
+use rayon::ThreadPoolBuilder;
+use std::{
+    fs, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+type Tile = ImageBuffer<Rgb<u8>, Vec<u8>>;
+
+/// Order the tiles of a tiled render are handed out in
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Across each row of tiles, from the top down
+    Rows,
+    /// Down each column of tiles, from the left
+    Columns,
+    /// Along a Z-order curve, so the tiles rendered at about the same
+    /// time are near each other in every direction
+    Morton,
+}
+
+/// How an image is split into tiles
+pub struct Tiling {
+    /// Pixels across and down each tile, which are square
+    pub size: u32,
+    pub order: TileOrder,
+}
+
+/// What renders the tiles of a tiled render
+pub enum Renderers<'a> {
+    /// This machine's threads, all rendering the rows of one tile after
+    /// another, or split into pools of this many if given
+    Threads(Option<NonZeroUsize>),
+    /// The workers listening at `addresses`, each sent `options` to
+    /// render the image they describe
+    Workers {
+        addresses: &'a [String],
+        options: &'a [String],
+    },
+}
+
+/// Directory the tiles of `output` are checkpointed to while rendering,
+/// ie. deep.png -> deep.checkpoint
+pub fn checkpoint_dir(output: &str) -> PathBuf {
+    Path::new(output).with_extension("checkpoint")
+}
+
+/// Render the image as `tiling`'s square tiles, saving each one to
+/// `checkpoint` as it finishes. With `resume`, tiles already saved there
+/// by an interrupted render with the same `settings` are loaded instead
+/// of rendered. Tiles are rendered by `renderers`.
+/// Tiles left unfinished when `progress` is cancelled aren't saved, and
+/// the checkpoint is kept to resume from
+pub fn render_tiled(
+    config: &RenderConfig,
+    tiling: Tiling,
+    renderers: Renderers,
+    checkpoint: &Path,
+    resume: bool,
+    settings: &[(&str, String)],
+    progress: &Progress,
+) -> ImageResult<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+    let (width, height) = config.resolution;
+    let tile_size = tiling.size.max(1);
+
+    let mut manifest = format!("tile-size: {}\n", tile_size);
+    for (keyword, value) in settings {
+        manifest.push_str(&format!("{}: {}\n", keyword, value));
+    }
+
+    let manifest_path = checkpoint.join("manifest");
+    if resume && checkpoint.exists() {
+        if fs::read_to_string(&manifest_path)? != manifest {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} was made with different settings", checkpoint.display()),
+            )
+            .into());
+        }
+    } else {
+        if checkpoint.exists() {
+            fs::remove_dir_all(checkpoint)?;
+        }
+        fs::create_dir_all(checkpoint)?;
+        fs::write(&manifest_path, &manifest)?;
+    }
+
+    let tiles = tile_corners(config.resolution, tile_size, tiling.order);
+
+    // Given what renders a tile from each x, y, width and height
+    let render_tile = |&(x, y): &(u32, u32),
+                       render: &dyn Fn(u32, u32, u32, u32) -> ImageResult<Tile>|
+     -> ImageResult<_> {
+        let tile_width = tile_size.min(width - x);
+        let tile_height = tile_size.min(height - y);
+        let path = checkpoint.join(format!("tile_{}_{}.png", x, y));
+
+        let saved = image::open(&path)
+            .ok()
+            .map(|tile| tile.to_rgb8())
+            .filter(|tile| tile.dimensions() == (tile_width, tile_height));
+        let tile = match saved {
+            Some(tile) => {
+                progress
+                    .pixels
+                    .fetch_add(tile_width as u64 * tile_height as u64, Ordering::Relaxed);
+                tile
+            }
+            None => {
+                let tile = render(x, y, tile_width, tile_height)?;
+                if progress.cancelled.load(Ordering::Relaxed) {
+                    return Ok(((x, y), tile));
+                }
+
+                // Written under a temporary name and renamed once complete,
+                // so an interrupted write is never mistaken for a tile
+                let partial = checkpoint.join(format!("tile_{}_{}.partial.png", x, y));
+                tile.save(&partial)?;
+                fs::rename(&partial, &path)?;
+                tile
+            }
+        };
+        Ok(((x, y), tile))
+    };
+    let local = |x, y, width, height| -> ImageResult<Tile> {
+        Ok(render_with_progress(
+            &config.tile(x, y, width, height),
+            progress,
+        ))
+    };
+    let rendered: Vec<_> = match renderers {
+        Renderers::Threads(Some(threads)) => {
+            render_in_pools(&tiles, threads.get(), |tile| render_tile(tile, &local))?
+        }
+        // Each tile's rows are already shared between every thread, so the
+        // tiles are finished in `tiling.order`
+        Renderers::Threads(None) => tiles
+            .iter()
+            .map(|tile| render_tile(tile, &local))
+            .collect::<ImageResult<_>>()?,
+        Renderers::Workers { addresses, options } => render_on_workers(
+            &tiles,
+            addresses,
+            options,
+            &progress.cancelled,
+            |tile, worker| {
+                render_tile(tile, &|x, y, width, height| {
+                    let tile = worker.render(x, y, width, height)?;
+                    progress
+                        .pixels
+                        .fetch_add(width as u64 * height as u64, Ordering::Relaxed);
+                    Ok(tile)
+                })
+            },
+        )?,
+    };
+
+    let mut img = ImageBuffer::new(width, height);
+    for ((x, y), tile) in rendered {
+        img.copy_from(&tile, x, y)?;
+    }
+
+    if !progress.cancelled.load(Ordering::Relaxed) {
+        fs::remove_dir_all(checkpoint)?;
+    }
+    Ok(img)
+}
+
+/// Top left corners of the tiles `tile_size` pixels across of an image of
+/// `resolution`, in the `order` they're rendered in
+fn tile_corners((width, height): (u32, u32), tile_size: u32, order: TileOrder) -> Vec<(u32, u32)> {
+    let mut tiles: Vec<(u32, u32)> = (0..height)
+        .step_by(tile_size as usize)
+        .flat_map(|y| (0..width).step_by(tile_size as usize).map(move |x| (x, y)))
+        .collect();
+    match order {
+        TileOrder::Rows => {}
+        TileOrder::Columns => tiles.sort_by_key(|&(x, y)| (x, y)),
+        TileOrder::Morton => tiles.sort_by_key(|&(x, y)| morton(x / tile_size, y / tile_size)),
+    }
+    tiles
+}
+
+/// Position of the tile (`x`, `y`) along a Z-order curve, the bits of
+/// the two interleaved
+fn morton(x: u32, y: u32) -> u64 {
+    // Each bit of a u32 spread out to every other bit of a u64
+    fn spread(n: u32) -> u64 {
+        let mut n = n as u64;
+        n = (n | n << 16) & 0x0000_FFFF_0000_FFFF;
+        n = (n | n << 8) & 0x00FF_00FF_00FF_00FF;
+        n = (n | n << 4) & 0x0F0F_0F0F_0F0F_0F0F;
+        n = (n | n << 2) & 0x3333_3333_3333_3333;
+        (n | n << 1) & 0x5555_5555_5555_5555
+    }
+    spread(x) | spread(y) << 1
+}
+
+/// `render` every one of `tiles`, with the threads of the current pool
+/// split into pools of `threads` that each take the next tile left once
+/// they finish one. A tile's rows then share the caches of the few
+/// threads rendering it, rather than being stolen by every thread there is
+fn render_in_pools<T: Send>(
+    tiles: &[(u32, u32)],
+    threads: usize,
+    render: impl Fn(&(u32, u32)) -> ImageResult<T> + Sync,
+) -> ImageResult<Vec<T>> {
+    let pools = (rayon::current_num_threads() / threads).max(1);
+    let next = AtomicUsize::new(0);
+    let rendered: Vec<ImageResult<Vec<(usize, T)>>> = thread::scope(|s| {
+        let workers: Vec<_> = (0..pools)
+            .map(|_| {
+                s.spawn(|| {
+                    // Threads that can't be started fail to spawn with
+                    // EAGAIN, which is WouldBlock
+                    let pool = ThreadPoolBuilder::new()
+                        .num_threads(threads)
+                        .build()
+                        .map_err(|e| io::Error::new(io::ErrorKind::WouldBlock, e))?;
+                    let mut rendered = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match tiles.get(i) {
+                            Some(tile) => rendered.push((i, pool.install(|| render(tile))?)),
+                            None => return Ok(rendered),
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("Tile pools don't panic"))
+            .collect()
+    });
+    in_order(rendered)
+}
+
+/// `render` every one of `tiles` with a worker, connecting to each of
+/// those at `addresses` with `options`. Each takes the next tile left
+/// once it sends one back, so faster workers render more of them. No
+/// more are handed out once `cancelled`
+fn render_on_workers<T: Send>(
+    tiles: &[(u32, u32)],
+    addresses: &[String],
+    options: &[String],
+    cancelled: &AtomicBool,
+    render: impl Fn(&(u32, u32), &Worker) -> ImageResult<T> + Sync,
+) -> ImageResult<Vec<T>> {
+    let next = AtomicUsize::new(0);
+    let rendered: Vec<ImageResult<Vec<(usize, T)>>> = thread::scope(|s| {
+        let workers: Vec<_> = addresses
+            .iter()
+            .map(|address| {
+                let (next, options, render) = (&next, options, &render);
+                s.spawn(move || {
+                    let worker = Worker::connect(address, options)?;
+                    let mut rendered = Vec::new();
+                    while !cancelled.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match tiles.get(i) {
+                            Some(tile) => rendered.push((i, render(tile, &worker)?)),
+                            None => break,
+                        }
+                    }
+                    Ok(rendered)
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("Workers' connections don't panic"))
+            .collect()
+    });
+    in_order(rendered)
+}
+
+/// The tiles each of several renderers numbered as they took them, back
+/// in the order of their numbers
+fn in_order<T>(rendered: Vec<ImageResult<Vec<(usize, T)>>>) -> ImageResult<Vec<T>> {
+    let mut tiles = Vec::new();
+    for renderer in rendered {
+        tiles.extend(renderer?);
+    }
+    tiles.sort_by_key(|(i, _)| *i);
+    Ok(tiles.into_iter().map(|(_, tile)| tile).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mandelbrot::{benchmark_config, render};
+    use std::env;
+
+    #[test]
+    fn orders_take_the_tiles_in_turn() {
+        let corners = |order| tile_corners((40, 30), 10, order);
+        assert_eq!(
+            corners(TileOrder::Rows)[..5],
+            [(0, 0), (10, 0), (20, 0), (30, 0), (0, 10)]
+        );
+        assert_eq!(
+            corners(TileOrder::Columns)[..4],
+            [(0, 0), (0, 10), (0, 20), (10, 0)]
+        );
+        assert_eq!(
+            corners(TileOrder::Morton)[..5],
+            [(0, 0), (10, 0), (0, 10), (10, 10), (20, 0)]
+        );
+        for order in [TileOrder::Columns, TileOrder::Morton] {
+            let mut sorted = corners(order);
+            sorted.sort_by_key(|&(x, y)| (y, x));
+            assert_eq!(sorted, corners(TileOrder::Rows));
+        }
+    }
+
+    #[test]
+    fn every_order_renders_the_same_bytes() {
+        // Tiles cut short at the right and bottom edges too
+        let config = benchmark_config((70, 50), 53);
+        let checkpoint = env::temp_dir().join("mandelbrot-tile-order.checkpoint");
+        let whole = render(&config);
+        for order in [TileOrder::Rows, TileOrder::Columns, TileOrder::Morton] {
+            for threads in [None, NonZeroUsize::new(1)] {
+                let tiled = render_tiled(
+                    &config,
+                    Tiling { size: 16, order },
+                    Renderers::Threads(threads),
+                    &checkpoint,
+                    false,
+                    &[],
+                    &Progress::default(),
+                )
+                .unwrap();
+                assert!(tiled == whole, "{:?} with {:?}", order, threads);
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+#[path = "../src/distributed.rs"]
+mod distributed;
+
+use distributed::Worker;
+use image::{GenericImage, ImageBuffer};
+use mandelbrot::{benchmark_config, render};
+use std::{net::TcpListener, thread};
+
+const OPTIONS: [&str; 2] = ["-x", "48x36"];
+
+/// Address of a worker rendering tiles of `benchmark_config` in the
+/// background, for coordinators that send `OPTIONS`
+fn spawn_worker() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        distributed::serve(listener, |options| {
+            if options != OPTIONS {
+                return Err(format!("Unexpected options {:?}", options));
+            }
+            let config = benchmark_config((48, 36), 53);
+            Ok(move |x, y, width, height| render(&config.tile(x, y, width, height)))
+        })
+    });
+    address
+}
+
+#[test]
+fn workers_render_the_whole_image() {
+    let options: Vec<String> = OPTIONS.iter().map(|option| option.to_string()).collect();
+    let workers = [
+        Worker::connect(&spawn_worker(), &options).unwrap(),
+        Worker::connect(&spawn_worker(), &options).unwrap(),
+    ];
+
+    // Tiles 24 by 12, taken by each worker in turn
+    let mut stitched = ImageBuffer::new(48, 36);
+    for (i, (x, y)) in (0..3)
+        .flat_map(|y| (0..2).map(move |x| (x * 24, y * 12)))
+        .enumerate()
+    {
+        let tile = workers[i % 2].render(x, y, 24, 12).unwrap();
+        stitched.copy_from(&tile, x, y).unwrap();
+    }
+    assert!(stitched == render(&benchmark_config((48, 36), 53)));
+}
+
+#[test]
+fn workers_refuse_jobs_they_cannot_render() {
+    let options = vec!["--unknown".to_owned()];
+    let error = Worker::connect(&spawn_worker(), &options).err().unwrap();
+    assert!(error.to_string().contains("Unexpected options"));
+}